@@ -0,0 +1,126 @@
+use std::{error::Error, fs::File, io::BufWriter, path::Path};
+use crate::render::{self, Image, RenderOptions};
+use crate::space::Space;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnimationFormat {
+    Gif,
+    Apng,
+}
+
+impl AnimationFormat {
+    pub fn from_file_str(file_str: &str) -> Option<AnimationFormat> {
+        let extension = Path::new(file_str).extension()?.to_str()?.to_lowercase();
+        match extension.as_str() {
+            "gif" => Some(AnimationFormat::Gif),
+            "apng" | "png" => Some(AnimationFormat::Apng),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnimationOptions {
+    pub start_time: usize,
+    pub end_time: usize, // inclusive
+    pub frame_delay_ms: u16,
+    pub crop_to_bounding_box: bool,
+    pub render: RenderOptions, // render.cell_size is the scale in pixels per cell
+}
+
+impl AnimationOptions {
+    pub fn new(start_time: usize, end_time: usize) -> AnimationOptions {
+        AnimationOptions {
+            start_time,
+            end_time,
+            frame_delay_ms: 100,
+            crop_to_bounding_box: false,
+            render: RenderOptions::default(),
+        }
+    }
+}
+
+// Renders every generation of the range from the history of the space.
+pub fn render_frames(space: &Space, options: &AnimationOptions) -> Result<Vec<Image>, Box<dyn Error>> {
+    if options.start_time > options.end_time {
+        return Err(format!("Invalid time range {}..={}!", options.start_time, options.end_time).into());
+    }
+    if render::get_image_size(space, &options.render).is_none() {
+        return Err(format!("Frames with a cell size of {} are too large!", options.render.cell_size).into());
+    }
+    let mut states: Vec<Space> = Vec::new();
+    for time in options.start_time..=options.end_time {
+        match space.state_at(time) {
+            Some(state) => states.push(state),
+            None => return Err(format!("No state saved for time {}!", time).into()),
+        }
+    }
+    let mut frames: Vec<Image> = states.iter().map(|state| render::render(state, &options.render)).collect();
+    if options.crop_to_bounding_box {
+        if let Some((x_min, y_min, x_max, y_max)) = get_bounding_box(&states) {
+            let cell_size = options.render.cell_size.max(1);
+            frames = frames.iter().map(|frame| frame.crop(
+                x_min as u32 * cell_size,
+                y_min as u32 * cell_size,
+                (x_max - x_min + 1) as u32 * cell_size,
+                (y_max - y_min + 1) as u32 * cell_size,
+            )).collect();
+        }
+    }
+    Ok(frames)
+}
+
+// Smallest box (x_min, y_min, x_max, y_max) containing the alive cells of all states.
+fn get_bounding_box(states: &[Space]) -> Option<(u16, u16, u16, u16)> {
+    let mut bounding_box: Option<(u16, u16, u16, u16)> = None;
+    for state in states {
+        for cell in state.get_alive_cells() {
+            bounding_box = Some(match bounding_box {
+                None => (cell.x, cell.y, cell.x, cell.y),
+                Some((x_min, y_min, x_max, y_max)) => (x_min.min(cell.x), y_min.min(cell.y), x_max.max(cell.x), y_max.max(cell.y)),
+            });
+        }
+    }
+    bounding_box
+}
+
+pub fn write_gif(file_str: &str, frames: &[Image], frame_delay_ms: u16) -> Result<(), Box<dyn Error>> {
+    let first = frames.first().ok_or("No frames to write!")?;
+    let width = u16::try_from(first.width)?;
+    let height = u16::try_from(first.height)?;
+    let file = File::create(Path::new(file_str))?;
+    let mut encoder = gif::Encoder::new(BufWriter::new(file), width, height, &[])?;
+    encoder.set_repeat(gif::Repeat::Infinite)?;
+    for frame in frames {
+        let mut pixels = frame.pixels.clone();
+        let mut gif_frame = gif::Frame::from_rgba_speed(width, height, &mut pixels, 10);
+        gif_frame.delay = frame_delay_ms / 10; // in hundredths of a second
+        encoder.write_frame(&gif_frame)?;
+    }
+    Ok(())
+}
+
+pub fn write_apng(file_str: &str, frames: &[Image], frame_delay_ms: u16) -> Result<(), Box<dyn Error>> {
+    let first = frames.first().ok_or("No frames to write!")?;
+    let file = File::create(Path::new(file_str))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), first.width, first.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(frames.len() as u32, 0)?;
+    encoder.set_frame_delay(frame_delay_ms, 1000)?;
+    let mut writer = encoder.write_header()?;
+    for frame in frames {
+        writer.write_image_data(&frame.pixels)?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+pub fn export_animation(file_str: &str, space: &Space, options: &AnimationOptions) -> Result<(), Box<dyn Error>> {
+    let format = AnimationFormat::from_file_str(file_str).ok_or("Unknown animation format, use .gif or .apng!")?;
+    let frames = render_frames(space, options)?;
+    match format {
+        AnimationFormat::Gif => write_gif(file_str, &frames, options.frame_delay_ms),
+        AnimationFormat::Apng => write_apng(file_str, &frames, options.frame_delay_ms),
+    }
+}
@@ -0,0 +1,110 @@
+use std::env;
+use std::process;
+use cellular_automata::animation::{self, AnimationFormat, AnimationOptions};
+use cellular_automata::colormap::Colormap;
+use cellular_automata::render::{self, RenderOptions};
+use cellular_automata::space::automaton::Automaton;
+use cellular_automata::space::{io, table};
+use cellular_automata::space::reaction::Channel;
+use cellular_automata::space::update::{UpdateOptions, UpdateScheme};
+
+const USAGE: &str = "usage: render <input.space> <output.png|output.gif|output.apng> [--generations N] [--cell-size N] [--colormap NAME] [--trace] [--age] [--grid] [--delay MS] [--crop] [--automaton NAME] [--rule-file PATH] [--channel u|v|blend] [--update SCHEME] [--probability P] [--rate R] [--block-size N] [--seed N] [--fire-histogram PATH] [--avalanche-histogram PATH]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(message) = run(&args) {
+        eprintln!("{}", message);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    if args.len() < 2 {
+        return Err("missing input or output file".to_string());
+    }
+    let mut options = RenderOptions::default();
+    let mut generations: usize = 0;
+    let mut frame_delay_ms: u16 = 100;
+    let mut crop_to_bounding_box = false;
+    let mut automaton = Automaton::default();
+    let mut update = UpdateOptions::default();
+    let mut seed: Option<u64> = None;
+    let mut fire_histogram: Option<String> = None;
+    let mut avalanche_histogram: Option<String> = None;
+    let mut i = 2;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--generations" => generations = parse_value(args, &mut i)?,
+            "--cell-size" => options.cell_size = parse_value(args, &mut i)?,
+            "--colormap" => {
+                let name: String = parse_value(args, &mut i)?;
+                options.colormap = Colormap::from_name(&name).ok_or(format!("unknown colormap '{}'", name))?;
+            }
+            "--trace" => options.tracing = true,
+            "--age" => options.color_by_age = true,
+            "--grid" => options.grid_lines = true,
+            "--delay" => frame_delay_ms = parse_value(args, &mut i)?,
+            "--crop" => crop_to_bounding_box = true,
+            "--automaton" => {
+                let name: String = parse_value(args, &mut i)?;
+                automaton = Automaton::from_name(&name).ok_or(format!("unknown automaton '{}'", name))?;
+            }
+            "--rule-file" => {
+                let path: String = parse_value(args, &mut i)?;
+                automaton = Automaton::Table(table::read_rule_file(&path).map_err(|error| error.to_string())?);
+            }
+            "--channel" => {
+                let name: String = parse_value(args, &mut i)?;
+                options.channel = Channel::from_name(&name).ok_or(format!("unknown channel '{}'", name))?;
+            }
+            "--update" => {
+                let name: String = parse_value(args, &mut i)?;
+                update.scheme = UpdateScheme::from_name(&name).ok_or(format!("unknown update scheme '{}'", name))?;
+            }
+            "--probability" => update.probability = parse_value(args, &mut i)?,
+            "--rate" => update.rate = parse_value(args, &mut i)?,
+            "--block-size" => update.block_size = parse_value(args, &mut i)?,
+            "--seed" => seed = Some(parse_value(args, &mut i)?),
+            "--fire-histogram" => fire_histogram = Some(parse_value(args, &mut i)?),
+            "--avalanche-histogram" => avalanche_histogram = Some(parse_value(args, &mut i)?),
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+        i += 1;
+    }
+    let mut space = io::read_from_file(&args[0]).map_err(|error| error.to_string())?;
+    space.set_automaton(automaton);
+    space.update = update;
+    if let Some(seed) = seed {
+        space.set_seed(seed);
+    }
+    for time in 1..=generations {
+        space.compute_next_generation();
+        space.save_state(time);
+    }
+    if let Some(path) = fire_histogram {
+        space.fire_statistics.write_histogram_csv(&path, space.displayed_time).map_err(|error| error.to_string())?;
+    }
+    if let Some(path) = avalanche_histogram {
+        space.avalanche_statistics.write_histogram_csv(&path, space.displayed_time).map_err(|error| error.to_string())?;
+    }
+    let output = &args[1];
+    if output.ends_with(".png") {
+        return render::render_to_png(output, &space, &options).map_err(|error| error.to_string());
+    }
+    if AnimationFormat::from_file_str(output).is_none() {
+        return Err(format!("unknown output format '{}'", output));
+    }
+    let mut animation_options = AnimationOptions::new(0, generations);
+    animation_options.frame_delay_ms = frame_delay_ms;
+    animation_options.crop_to_bounding_box = crop_to_bounding_box;
+    animation_options.render = options;
+    animation::export_animation(output, &space, &animation_options).map_err(|error| error.to_string())
+}
+
+fn parse_value<T: std::str::FromStr>(args: &[String], i: &mut usize) -> Result<T, String> {
+    let option = &args[*i];
+    *i += 1;
+    let value = args.get(*i).ok_or(format!("missing value for '{}'", option))?;
+    value.parse().map_err(|_| format!("invalid value '{}' for '{}'", value, option))
+}
@@ -0,0 +1,61 @@
+use std::env;
+use std::process;
+use cellular_automata::census::{self, SearchOptions};
+use cellular_automata::space::rule::Rule;
+use cellular_automata::space::soup::Symmetry;
+
+const USAGE: &str = "usage: search [--soups N] [--seed N] [--size N] [--density D] [--symmetry NAME] [--margin N] [--generations N] [--rule RULE] [--output DIR]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    if let Err(message) = run(&args) {
+        eprintln!("{}", message);
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+}
+
+fn run(args: &[String]) -> Result<(), String> {
+    let mut options = SearchOptions::default();
+    let mut output: Option<String> = None;
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--soups" => options.num_soups = parse_value(args, &mut i)?,
+            "--seed" => options.first_seed = parse_value(args, &mut i)?,
+            "--size" => options.soup_size = parse_value(args, &mut i)?,
+            "--density" => {
+                options.density = parse_value(args, &mut i)?;
+                if !(0. ..=1.).contains(&options.density) {
+                    return Err(format!("density {} is not between 0 and 1", options.density));
+                }
+            }
+            "--symmetry" => {
+                let name: String = parse_value(args, &mut i)?;
+                options.symmetry = Symmetry::from_name(&name).ok_or(format!("unknown symmetry '{}'", name))?;
+            }
+            "--margin" => options.margin = parse_value(args, &mut i)?,
+            "--generations" => options.max_generations = parse_value(args, &mut i)?,
+            "--rule" => {
+                let rulestring: String = parse_value(args, &mut i)?;
+                options.rule = Rule::parse(&rulestring).map_err(|error| error.to_string())?;
+            }
+            "--output" => output = Some(parse_value(args, &mut i)?),
+            other => return Err(format!("unknown option '{}'", other)),
+        }
+        i += 1;
+    }
+    let census = census::search(&options)?;
+    print!("{}", census::format_report(&census, &options));
+    if let Some(output) = output {
+        census::write_census(&output, &census, &options).map_err(|error| error.to_string())?;
+    }
+    Ok(())
+}
+
+fn parse_value<T: std::str::FromStr>(args: &[String], i: &mut usize) -> Result<T, String> {
+    let option = &args[*i];
+    *i += 1;
+    let value = args.get(*i).ok_or(format!("missing value for '{}'", option))?;
+    value.parse().map_err(|_| format!("invalid value '{}' for '{}'", value, option))
+}
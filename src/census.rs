@@ -0,0 +1,191 @@
+use std::collections::{HashMap, VecDeque};
+use std::{error::Error, fs, path::Path};
+use rayon::prelude::*;
+use crate::space::io;
+use crate::space::object::{Classifier, ObjectKind, MAX_PERIOD};
+use crate::space::rule::Rule;
+use crate::space::soup::{Region, SoupOptions, Symmetry};
+use crate::space::Space;
+
+const EDGE_DISTANCE: u16 = 3; // spaceships this close to the edge are counted and removed
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    pub num_soups: usize,
+    pub first_seed: u64, // soups use the seeds first_seed..first_seed + num_soups
+    pub soup_size: u16,
+    pub density: f64,
+    pub symmetry: Symmetry,
+    pub margin: u16, // empty cells around the soup
+    pub max_generations: usize,
+    pub rule: Rule,
+}
+
+impl Default for SearchOptions {
+    fn default() -> SearchOptions {
+        SearchOptions {
+            num_soups: 100,
+            first_seed: 0,
+            soup_size: 16,
+            density: 0.5,
+            symmetry: Symmetry::C1,
+            margin: 40,
+            max_generations: 4000,
+            rule: Rule::conways_game_of_life(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SoupResult {
+    pub seed: u64,
+    pub generations: usize, // until the soup stabilized
+    pub stabilized: bool,
+    pub objects: Vec<String>, // codes of the final objects and the escaped spaceships
+}
+
+impl SoupResult {
+    // Soups which did not settle or produced an object without a name.
+    pub fn is_notable(&self) -> bool {
+        !self.stabilized || self.objects.iter().any(|code| code.contains('_'))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Census {
+    pub num_soups: usize,
+    pub counts: HashMap<String, usize>,
+    pub notable_soups: Vec<SoupResult>,
+}
+
+impl Census {
+    pub fn add(&mut self, result: SoupResult) {
+        self.num_soups += 1;
+        for code in &result.objects {
+            *self.counts.entry(code.clone()).or_insert(0) += 1;
+        }
+        if result.is_notable() {
+            self.notable_soups.push(result);
+        }
+    }
+
+    // Most common objects first.
+    pub fn get_sorted_counts(&self) -> Vec<(&str, usize)> {
+        let mut counts: Vec<(&str, usize)> = self.counts.iter().map(|(code, count)| (code.as_str(), *count)).collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        counts
+    }
+}
+
+pub fn build_soup(seed: u64, options: &SearchOptions) -> Result<Space, String> {
+    let size = options.margin.checked_mul(2).and_then(|margins| margins.checked_add(options.soup_size))
+        .ok_or(format!("Soup size {} with a margin of {} is too large!", options.soup_size, options.margin))?;
+    let mut space = Space::new(size, size);
+    space.rule = options.rule;
+    space.set_seed(seed);
+    space.fill_random(&SoupOptions {
+        density: options.density,
+        region: Some(Region::new(options.margin, options.margin, options.soup_size, options.soup_size)),
+        symmetry: options.symmetry,
+    });
+    Ok(space)
+}
+
+pub fn run_soup(seed: u64, options: &SearchOptions) -> Result<SoupResult, String> {
+    Ok(run_space(build_soup(seed, options)?, options))
+}
+
+// Runs the space until it repeats itself, then separates and classifies the remaining objects.
+pub fn run_space(space: Space, options: &SearchOptions) -> SoupResult {
+    run_space_with_classifier(space, options, &Classifier::new())
+}
+
+fn run_space_with_classifier(mut space: Space, options: &SearchOptions, classifier: &Classifier) -> SoupResult {
+    let seed = space.get_seed();
+    let mut objects: Vec<String> = Vec::new();
+    let mut recent_states: VecDeque<Vec<(u16, u16)>> = VecDeque::with_capacity(MAX_PERIOD);
+    for generation in 0..=options.max_generations {
+        remove_escaping_spaceships(&mut space, classifier, &mut objects);
+        let alive_positions: Vec<(u16, u16)> = space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+        if recent_states.contains(&alive_positions) {
+            for object in space.separate_objects() {
+                objects.push(classifier.classify(&object, options.rule).get_code());
+            }
+            return SoupResult { seed, generations: generation, stabilized: true, objects };
+        }
+        if recent_states.len() == MAX_PERIOD {
+            recent_states.pop_front();
+        }
+        recent_states.push_back(alive_positions);
+        space.compute_conways_game_of_life_single_threaded();
+    }
+    SoupResult { seed, generations: options.max_generations, stabilized: false, objects }
+}
+
+fn remove_escaping_spaceships(space: &mut Space, classifier: &Classifier, objects: &mut Vec<String>) {
+    let (x_dim, y_dim) = (space.x_dim(), space.y_dim());
+    let is_near_edge = |&(x, y): &(u16, u16)| x < EDGE_DISTANCE || y < EDGE_DISTANCE || x + EDGE_DISTANCE >= x_dim || y + EDGE_DISTANCE >= y_dim;
+    if !space.get_alive_cells().iter().any(|cell| is_near_edge(&(cell.x, cell.y))) {
+        return;
+    }
+    for object in space.separate_objects() {
+        if !object.iter().any(is_near_edge) {
+            continue;
+        }
+        let classification = classifier.classify(&object, space.rule);
+        if let ObjectKind::Spaceship { .. } = classification.kind {
+            objects.push(classification.get_code());
+            for (x, y) in object {
+                space.get_cell_mut(x, y).unwrap().kill();
+            }
+        }
+    }
+}
+
+// Runs the soups in parallel, the census lists the notable soups in the order of their seeds.
+pub fn search(options: &SearchOptions) -> Result<Census, String> {
+    let classifier = Classifier::new();
+    let results: Vec<SoupResult> = (0..options.num_soups as u64)
+        .into_par_iter()
+        .map(|i| Ok(run_space_with_classifier(build_soup(options.first_seed + i, options)?, options, &classifier)))
+        .collect::<Result<_, String>>()?;
+    let mut census = Census::default();
+    for result in results {
+        census.add(result);
+    }
+    Ok(census)
+}
+
+pub fn format_report(census: &Census, options: &SearchOptions) -> String {
+    let mut report = String::new();
+    report.push_str(&format!(
+        "Census of {} soups ({}, {}, {}x{}, density {}, seeds {} to {})\n",
+        census.num_soups, options.rule, options.symmetry.name(), options.soup_size, options.soup_size, options.density,
+        options.first_seed, options.first_seed + census.num_soups.saturating_sub(1) as u64,
+    ));
+    for (code, count) in census.get_sorted_counts() {
+        report.push_str(&format!("{:>8} {}\n", count, code));
+    }
+    report.push_str(&format!("Notable soups: {}\n", census.notable_soups.len()));
+    for result in &census.notable_soups {
+        if result.stabilized {
+            let codes: Vec<&str> = result.objects.iter().filter(|code| code.contains('_')).map(|code| code.as_str()).collect();
+            report.push_str(&format!("  seed {}: {}\n", result.seed, codes.join(" ")));
+        } else {
+            report.push_str(&format!("  seed {}: did not stabilize within {} generations\n", result.seed, result.generations));
+        }
+    }
+    report
+}
+
+// Writes the report to census.txt and the initial state of every notable soup to soup_<seed>.txt.
+pub fn write_census(dir_str: &str, census: &Census, options: &SearchOptions) -> Result<(), Box<dyn Error>> {
+    let dir = Path::new(dir_str);
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("census.txt"), format_report(census, options))?;
+    for result in &census.notable_soups {
+        let file_path = dir.join(format!("soup_{}.txt", result.seed));
+        io::write_to_file(file_path.to_str().ok_or("Invalid output directory!")?, &build_soup(result.seed, options)?)?;
+    }
+    Ok(())
+}
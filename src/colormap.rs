@@ -0,0 +1,178 @@
+const VIRIDIS: [(f32, f32, f32); 5] = [
+    (0.267, 0.005, 0.329),
+    (0.231, 0.322, 0.545),
+    (0.129, 0.569, 0.553),
+    (0.369, 0.788, 0.384),
+    (0.992, 0.906, 0.145),
+];
+
+const MAGMA: [(f32, f32, f32); 6] = [
+    (0.001, 0.000, 0.016),
+    (0.231, 0.059, 0.439),
+    (0.549, 0.161, 0.506),
+    (0.871, 0.286, 0.408),
+    (0.996, 0.624, 0.427),
+    (0.988, 0.992, 0.749),
+];
+
+const FIRE: [(f32, f32, f32); 4] = [
+    (0.0, 0.0, 0.0),
+    (0.8, 0.0, 0.0),
+    (1.0, 0.6, 0.0),
+    (1.0, 1.0, 0.8),
+];
+
+const GRAYSCALE: [(f32, f32, f32); 2] = [
+    (0.0, 0.0, 0.0),
+    (1.0, 1.0, 1.0),
+];
+
+// colorblind-safe
+const CIVIDIS: [(f32, f32, f32); 6] = [
+    (0.000, 0.133, 0.306),
+    (0.208, 0.271, 0.424),
+    (0.400, 0.412, 0.439),
+    (0.580, 0.557, 0.467),
+    (0.784, 0.722, 0.400),
+    (0.996, 0.910, 0.220),
+];
+
+// colorblind-safe, Okabe-Ito blue to orange
+const BLUE_ORANGE: [(f32, f32, f32); 3] = [
+    (0.000, 0.447, 0.698),
+    (0.337, 0.706, 0.914),
+    (0.902, 0.624, 0.000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Colormap {
+    Solid,
+    Viridis,
+    Magma,
+    Fire,
+    Grayscale,
+    Cividis,
+    BlueOrange,
+    Custom,
+}
+
+impl Colormap {
+    pub const ALL: [Colormap; 8] = [
+        Colormap::Solid,
+        Colormap::Viridis,
+        Colormap::Magma,
+        Colormap::Fire,
+        Colormap::Grayscale,
+        Colormap::Cividis,
+        Colormap::BlueOrange,
+        Colormap::Custom,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Colormap::Solid => "solid",
+            Colormap::Viridis => "viridis",
+            Colormap::Magma => "magma",
+            Colormap::Fire => "fire",
+            Colormap::Grayscale => "grayscale",
+            Colormap::Cividis => "cividis",
+            Colormap::BlueOrange => "blue-orange",
+            Colormap::Custom => "custom",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Colormap> {
+        Colormap::ALL.iter().find(|colormap| colormap.name() == name).copied()
+    }
+
+    pub fn next(&self) -> Colormap {
+        let index = Colormap::ALL.iter().position(|colormap| colormap == self).unwrap();
+        Colormap::ALL[(index + 1) % Colormap::ALL.len()]
+    }
+
+    // Maps a value in [0, 1] to (R, G, B, A). Solid keeps the single color and fades it via alpha.
+    pub fn map(&self, value: f32, solid_color: &(f32, f32, f32), custom: &Gradient) -> (f32, f32, f32, f32) {
+        let value = value.clamp(0., 1.);
+        let color = match self {
+            Colormap::Solid => return (solid_color.0, solid_color.1, solid_color.2, value),
+            Colormap::Viridis => interpolate_evenly(&VIRIDIS, value),
+            Colormap::Magma => interpolate_evenly(&MAGMA, value),
+            Colormap::Fire => interpolate_evenly(&FIRE, value),
+            Colormap::Grayscale => interpolate_evenly(&GRAYSCALE, value),
+            Colormap::Cividis => interpolate_evenly(&CIVIDIS, value),
+            Colormap::BlueOrange => interpolate_evenly(&BLUE_ORANGE, value),
+            Colormap::Custom => custom.sample(value),
+        };
+        (color.0, color.1, color.2, 1.)
+    }
+}
+
+// Gradient defined by stops (position in [0, 1], color), kept sorted by position.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Gradient {
+    stops: Vec<(f32, (f32, f32, f32))>,
+}
+
+impl Gradient {
+    pub fn new(start: (f32, f32, f32), end: (f32, f32, f32)) -> Gradient {
+        Gradient { stops: vec![(0., start), (1., end)] }
+    }
+
+    pub fn from_stops(mut stops: Vec<(f32, (f32, f32, f32))>) -> Option<Gradient> {
+        if stops.len() < 2 {
+            return None;
+        }
+        stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Some(Gradient { stops })
+    }
+
+    pub fn stops(&self) -> &Vec<(f32, (f32, f32, f32))> {
+        &self.stops
+    }
+
+    pub fn add_stop(&mut self, position: f32, color: (f32, f32, f32)) {
+        let position = position.clamp(0., 1.);
+        let index = self.stops.iter().position(|stop| stop.0 > position).unwrap_or(self.stops.len());
+        self.stops.insert(index, (position, color));
+    }
+
+    // Removes the stop closest to position, but always keeps at least two stops.
+    pub fn remove_stop_near(&mut self, position: f32) {
+        if self.stops.len() <= 2 {
+            return;
+        }
+        let mut closest = 0;
+        for (index, stop) in self.stops.iter().enumerate() {
+            if (stop.0 - position).abs() < (self.stops[closest].0 - position).abs() {
+                closest = index;
+            }
+        }
+        self.stops.remove(closest);
+    }
+
+    pub fn sample(&self, value: f32) -> (f32, f32, f32) {
+        let first = self.stops[0];
+        if value <= first.0 {
+            return first.1;
+        }
+        for window in self.stops.windows(2) {
+            let (lower, upper) = (window[0], window[1]);
+            if value <= upper.0 {
+                let span = upper.0 - lower.0;
+                let t = if span > 0. { (value - lower.0) / span } else { 1. };
+                return lerp(lower.1, upper.1, t);
+            }
+        }
+        self.stops[self.stops.len() - 1].1
+    }
+}
+
+fn interpolate_evenly(colors: &[(f32, f32, f32)], value: f32) -> (f32, f32, f32) {
+    let scaled = value * (colors.len() - 1) as f32;
+    let index = (scaled.floor() as usize).min(colors.len() - 2);
+    lerp(colors[index], colors[index + 1], scaled - index as f32)
+}
+
+fn lerp(a: (f32, f32, f32), b: (f32, f32, f32), t: f32) -> (f32, f32, f32) {
+    (a.0 + (b.0 - a.0) * t, a.1 + (b.1 - a.1) * t, a.2 + (b.2 - a.2) * t)
+}
@@ -0,0 +1,9 @@
+pub mod animation;
+pub mod census;
+pub mod colormap;
+pub mod render;
+pub mod session;
+pub mod simulation;
+pub mod space;
+#[allow(unused_must_use, clippy::bool_assert_comparison, clippy::useless_vec, clippy::get_first, clippy::empty_line_after_outer_attr)]
+mod test;
@@ -3,14 +3,35 @@ use std::time::Instant;
 use macroquad::miniquad::window::set_window_size;
 use macroquad::prelude::*;
 use native_dialog::FileDialog;
-use crate::space::{io, Space};
-
-mod space;
-mod test;
+use cellular_automata::animation::AnimationOptions;
+use cellular_automata::colormap::{Colormap, Gradient};
+use cellular_automata::render::{self, RenderOptions};
+use cellular_automata::session;
+use cellular_automata::simulation::{Command, Simulation, Snapshot};
+use cellular_automata::space::{io, table, Space};
+use cellular_automata::space::automaton::Automaton;
+use cellular_automata::space::elementary::LinearRule;
+use cellular_automata::space::rule::Rule;
+use cellular_automata::space::object::{ObjectKind, SpaceObject};
+use cellular_automata::space::lattice::GasModel;
+use cellular_automata::space::reaction::Channel;
+use cellular_automata::space::soup::{Region, SoupOptions, Symmetry};
+use cellular_automata::space::update::UpdateOptions;
 
 const CELL_SIZE : f32 = 28. ; // 20
 const START_GRID_X_DIM: u16 = 25;
 const START_GRID_Y_DIM: u16 = 25;
+const RULER_STEP: u16 = 5;
+const RULER_SIZE: f32 = 14.;
+const CHARACTERIZE_GENERATIONS: usize = 300;
+const LEGEND_WIDTH: f32 = 150.;
+const DIGIT_KEYS: [KeyCode; 10] = [
+    KeyCode::Key0, KeyCode::Key1, KeyCode::Key2, KeyCode::Key3, KeyCode::Key4,
+    KeyCode::Key5, KeyCode::Key6, KeyCode::Key7, KeyCode::Key8, KeyCode::Key9,
+];
+const LEGEND_ROW_HEIGHT: f32 = 20.;
+const VELOCITY_BLOCK_SIZE: u16 = 4;
+const PLOT_SIZE: (f32, f32) = (240., 80.);
 
 const ASCII_ART: &str = "
                                                 ██████╗ ██╗   ██╗███████╗████████╗██╗   ██╗
@@ -41,133 +62,182 @@ fn window_conf() -> Conf {
 async fn main() {
 
     println!("{}", ASCII_ART);
-    let mut run: bool = false;
-    let mut space = Space::new(START_GRID_X_DIM, START_GRID_Y_DIM);
-    let mut time_step_start: usize = 0;
+    let mut simulation = Simulation::new(Space::new(START_GRID_X_DIM, START_GRID_Y_DIM));
     let mut settings = Settings::new(screen_width(), screen_height());
     let time = Instant::now();
     loop {
-        let time_step_current = space.displayed_time;
+        let snapshot = simulation.poll().clone();
+        let space: &Space = &snapshot.space;
         clear_background(BLACK);
-        if run {
-            space.compute_conways_game_of_life_multithreaded();
-            space.save_state(time_step_current + 1);
-        }
         let current_screen_width = screen_width();
         let current_screen_height = screen_height();
         // Resizing Window
         {
             if current_screen_width != settings.screen_width || current_screen_height != settings.screen_height {
-                let mut resized_space = Space::new((current_screen_width / CELL_SIZE) as u16, (current_screen_height / CELL_SIZE) as u16);
-                for alive_cell in space.get_alive_cells() {
-                    let res_cell = resized_space.get_cell_mut(alive_cell.x, alive_cell.y);
-                    if res_cell.is_ok() {
-                        res_cell.unwrap().revive();
-                    }
-                }
-                space = resized_space;
+                simulation.send(Command::Resize((current_screen_width / CELL_SIZE) as u16, (current_screen_height / CELL_SIZE) as u16));
                 settings.screen_width = current_screen_width;
                 settings.screen_height = current_screen_height;
             }
         }
-        draw(&mut space, settings.tracing, &settings.color, &settings.fps, settings.fps_is_on);
+        draw(&snapshot, &settings);
         if settings.fps_is_on {
             settings.compute_fps(time);
         }
         let mouse_position: (f32, f32) = mouse_position();
+        if settings.inspector_is_on && !settings.is_active {
+            draw_inspector(space, mouse_position, current_screen_width, current_screen_height);
+        }
         if settings.is_active {
             settings.draw(current_screen_width, current_screen_height);
             process_red_slider(&mut settings, mouse_position);
             process_green_slider(&mut settings, mouse_position);
             process_blue_slider(&mut settings, mouse_position);
+            process_colormap_controls(&mut settings, mouse_position);
         } else {
             // Mouse Control
             {
-                if is_mouse_button_pressed(MouseButton::Left) {
-                    let cell_x = (mouse_position.0 / CELL_SIZE).floor() as u16;
-                    let cell_y = (mouse_position.1 / CELL_SIZE).floor() as u16;
-                    if cell_x < space.x_dim() && cell_y < space.y_dim() {
-                        space.get_cell_mut(cell_x, cell_y).unwrap().revive();
+                let cell_x = (mouse_position.0 / CELL_SIZE).floor() as u16;
+                let cell_y = (mouse_position.1 / CELL_SIZE).floor() as u16;
+                let is_in_space = cell_x < space.x_dim() && cell_y < space.y_dim();
+                let legend_state = space.automaton.get_palette()
+                    .and_then(|_| get_legend_state(mouse_position, space.automaton.get_num_states(), space.x_dim() as f32 * CELL_SIZE));
+                if is_key_down(KeyCode::LeftShift) {
+                    process_region_selection(&mut settings, (cell_x, cell_y), is_in_space);
+                } else if let (Some(state), true) = (legend_state, is_mouse_button_pressed(MouseButton::Left)) {
+                    settings.paint_state = state;
+                } else {
+                    let paint_cell = |simulation: &Simulation| match (&space.automaton, space.automaton.get_palette()) {
+                        (Automaton::Turmite(_), _) => simulation.send(Command::AddAnt(cell_x, cell_y)),
+                        (Automaton::ReactionDiffusion(_), _) => simulation.send(Command::PaintChemical(cell_x, cell_y)),
+                        (Automaton::Sandpile(_), _) => simulation.send(Command::DropGrain(cell_x, cell_y)),
+                        (Automaton::LatticeGas(_), _) => simulation.send(Command::PaintGas(cell_x, cell_y, settings.paint_state)),
+                        (_, Some(_)) => simulation.send(Command::PaintCell(cell_x, cell_y, settings.paint_state)),
+                        (_, None) => simulation.send(Command::ReviveCell(cell_x, cell_y)),
+                    };
+                    let erase_cell = |simulation: &Simulation| match space.automaton {
+                        Automaton::Turmite(_) => {
+                            simulation.send(Command::RemoveAnts(cell_x, cell_y));
+                            simulation.send(Command::KillCell(cell_x, cell_y));
+                        }
+                        Automaton::LatticeGas(_) => simulation.send(Command::PaintGas(cell_x, cell_y, 0)),
+                        _ => simulation.send(Command::KillCell(cell_x, cell_y)),
+                    };
+                    if is_mouse_button_pressed(MouseButton::Left) && is_in_space {
+                        paint_cell(&simulation);
                         settings.dragging = true;
                     }
-                }
-                if is_mouse_button_pressed(MouseButton::Right) {
-                    let cell_x = (mouse_position.0 / CELL_SIZE).floor() as u16;
-                    let cell_y = (mouse_position.1 / CELL_SIZE).floor() as u16;
-                    if cell_x < space.x_dim() && cell_y < space.y_dim() {
-                        space.get_cell_mut(cell_x, cell_y).unwrap().kill();
+                    if is_mouse_button_pressed(MouseButton::Right) && is_in_space {
+                        erase_cell(&simulation);
                         settings.dragging = true;
                     }
-                }
-                if settings.dragging {
-                    let cell_x = (mouse_position.0 / CELL_SIZE).floor() as usize;
-                    let cell_y = (mouse_position.1 / CELL_SIZE).floor() as usize;
-                    if cell_x < space.x_dim() as usize && cell_y < space.y_dim() as usize {
+                    if settings.dragging && is_in_space {
                         if is_mouse_button_down(MouseButton::Left) {
-                            space.get_cell_mut(cell_x as u16, cell_y as u16).unwrap().revive();
+                            paint_cell(&simulation);
                         }
                         if is_mouse_button_down(MouseButton::Right) {
-                            space.get_cell_mut(cell_x as u16, cell_y as u16).unwrap().kill();
+                            erase_cell(&simulation);
                         }
                     }
-                }
-                if is_mouse_button_released(MouseButton::Left) || is_mouse_button_released(MouseButton::Right) {
-                    settings.dragging = false;
-                    if space.states_hash_map.contains_key(&(time_step_current + 1)) {
-                        let mut time_key_to_remove = time_step_current + 1;
-                        while space.states_hash_map.contains_key(&time_key_to_remove) {
-                            space.states_hash_map.remove(&time_key_to_remove);
-                            time_key_to_remove += 1;
-                        }
+                    if settings.dragging && (is_mouse_button_released(MouseButton::Left) || is_mouse_button_released(MouseButton::Right)) {
+                        settings.dragging = false;
+                        simulation.send(Command::CommitEdit);
                     }
-                    space.save_state(time_step_current +1);
                 }
             }
         }
         // Key Control
-        {
+        if settings.life_rule_input.is_some() {
+            process_life_rule_input(&simulation, &mut settings);
+        } else {
             if is_key_pressed(KeyCode::Space) {
-                if !run {
-                    time_step_start = time_step_current;
-                }
-                run = !run;
+                simulation.send(Command::ToggleRun);
             }
             if is_key_pressed(KeyCode::Left) {
-                run = false;
-                if time_step_current > 0 {
-                    space.load_state(time_step_current - 1);
-                }
+                simulation.send(Command::StepBackward);
             }
             if is_key_pressed(KeyCode::Right) {
-                if space.states_hash_map.contains_key(&(time_step_current + 1)) {
-                    space.load_state(time_step_current +1);
-                } else {
-                    space.compute_conways_game_of_life_multithreaded();
-                    space.save_state(time_step_current +1);
-                }
-                run = false;
+                simulation.send(Command::StepForward);
             }
             if is_key_pressed(KeyCode::Up) {
-                if time_step_current > time_step_start {
-                    space.load_state(time_step_start);
-                }
-                run = false;
+                simulation.send(Command::JumpToStart);
             }
             if is_key_pressed(KeyCode::Down) {
-                if time_step_current < space.states_hash_map.len() - 1 {
-                    space.load_state(space.states_hash_map.len() - 1);
-                }
-                run = false;
+                simulation.send(Command::JumpToEnd);
             }
             if is_key_pressed(KeyCode::E) && is_key_down(KeyCode::LeftControl) {
                 if let Some(path) = show_export_dialog().await {
-                    io::write_to_file(path.to_str().unwrap(), &space).unwrap();
+                    io::write_to_file(path.to_str().unwrap(), space).unwrap();
+                }
+            }
+            if is_key_pressed(KeyCode::P) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_screenshot_dialog().await {
+                    let result = match path.to_str() {
+                        Some(file_str) => render::render_to_png(file_str, space, &settings.render_options()),
+                        None => Err("the path is not valid unicode".into()),
+                    };
+                    if let Err(error) = result {
+                        eprintln!("Failed to save screenshot: {}", error);
+                    }
+                }
+            }
+            if is_key_pressed(KeyCode::LeftBracket) {
+                settings.animation_start = space.displayed_time;
+            }
+            if is_key_pressed(KeyCode::RightBracket) {
+                settings.animation_end = Some(space.displayed_time);
+            }
+            if is_key_pressed(KeyCode::M) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_animation_export_dialog().await {
+                    let end_time = settings.animation_end.unwrap_or(snapshot.history_len - 1);
+                    let mut options = AnimationOptions::new(settings.animation_start, end_time);
+                    options.render = settings.render_options();
+                    simulation.send(Command::ExportAnimation(path.to_str().unwrap().to_string(), options));
                 }
             }
+            if is_key_pressed(KeyCode::S) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_session_save_dialog().await {
+                    simulation.send(Command::SaveSession(path.to_str().unwrap().to_string(), settings.render_options()));
+                }
+            }
+            if is_key_pressed(KeyCode::O) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_session_open_dialog().await {
+                    match session::read_session(path.to_str().unwrap()) {
+                        Ok((session_space, view)) => {
+                            settings.apply_render_options(&view);
+                            set_window_size((session_space.x_dim() as f32 * CELL_SIZE) as u32, (session_space.y_dim() as f32 * CELL_SIZE) as u32);
+                            simulation.send(Command::Replace(Box::new(session_space)));
+                        }
+                        Err(error) => eprintln!("Failed to open session: {}", error),
+                    }
+                }
+            }
+            if is_key_pressed(KeyCode::B) {
+                simulation.send(Command::SetBoundary(space.boundary.next()));
+            }
+            if is_key_pressed(KeyCode::O) && !is_key_down(KeyCode::LeftControl) {
+                let mut options = space.update;
+                if is_key_down(KeyCode::LeftShift) {
+                    options.probability = options.next_probability();
+                } else if is_key_down(KeyCode::LeftAlt) {
+                    options = options.next_parameter();
+                } else {
+                    options.scheme = options.scheme.next();
+                }
+                simulation.send(Command::SetUpdate(options));
+            }
+            if is_key_pressed(KeyCode::E) && !is_key_down(KeyCode::LeftControl) {
+                clear_input_queue();
+                settings.life_rule_input = Some(space.rule.to_string());
+            }
             if is_key_pressed(KeyCode::I) && is_key_down(KeyCode::LeftControl) {
                 if let Some(path) = show_import_dialog().await {
-                    space = io::read_from_file(path.to_str().unwrap()).unwrap();
-                    set_window_size((space.x_dim() as f32 * CELL_SIZE) as u32, (space.y_dim() as f32 * CELL_SIZE) as u32);
+                    match io::read_from_file(path.to_str().unwrap()) {
+                        Ok(imported_space) => {
+                            set_window_size((imported_space.x_dim() as f32 * CELL_SIZE) as u32, (imported_space.y_dim() as f32 * CELL_SIZE) as u32);
+                            simulation.send(Command::Replace(Box::new(imported_space)));
+                        }
+                        Err(error) => eprintln!("Failed to import space: {}", error),
+                    }
                 }
             }
             if is_key_pressed(KeyCode::KpAdd) && is_key_down(KeyCode::X) {
@@ -175,85 +245,149 @@ async fn main() {
                 if is_key_down(KeyCode::LeftControl) {
                     increment = 10;
                 }
-                let mut new_space = Space::new(space.x_dim()+increment, space.y_dim());
-                for alive_cell in space.get_alive_cells() {
-                    new_space.get_cell_mut(alive_cell.x, alive_cell.y).unwrap().revive();
-                }
-                space = new_space;
-                set_window_size((space.x_dim() as f32 * CELL_SIZE) as u32, (space.y_dim() as f32 * CELL_SIZE) as u32);
+                resize_space(&simulation, space.x_dim() + increment, space.y_dim());
             }
             if is_key_pressed(KeyCode::KpSubtract) && is_key_down(KeyCode::X) {
                 let mut decrement: u16 = 1;
-                if is_key_down(KeyCode::LeftControl) {
-                    if space.x_dim() >= 27 {
-                        decrement = 10;
-                    }
+                if is_key_down(KeyCode::LeftControl) && space.x_dim() >= 27 {
+                    decrement = 10;
                 }
                 if space.x_dim() <= 17 {
                     decrement = 0;
                 }
-                let mut new_space = Space::new(space.x_dim()-decrement, space.y_dim());
-                for alive_cell in space.get_alive_cells() {
-                    let res = new_space.get_cell_mut(alive_cell.x, alive_cell.y);
-                    if res.is_ok() {
-                        res.unwrap().revive();
-                    }
-                }
-                space = new_space;
-                set_window_size((space.x_dim() as f32 * CELL_SIZE) as u32, (space.y_dim() as f32 * CELL_SIZE) as u32);
+                resize_space(&simulation, space.x_dim() - decrement, space.y_dim());
             }
             if is_key_pressed(KeyCode::KpAdd) && is_key_down(KeyCode::Z) { // English Layout
                 let mut increment: u16 = 1;
                 if is_key_down(KeyCode::LeftControl) {
                     increment = 10;
                 }
-                let mut new_space = Space::new(space.x_dim(), space.y_dim()+increment);
-                for alive_cell in space.get_alive_cells() {
-                    new_space.get_cell_mut(alive_cell.x, alive_cell.y).unwrap().revive();
-                }
-                space = new_space;
-                set_window_size((space.x_dim() as f32 * CELL_SIZE) as u32, (space.y_dim() as f32 * CELL_SIZE) as u32);
+                resize_space(&simulation, space.x_dim(), space.y_dim() + increment);
             }
             if is_key_pressed(KeyCode::KpSubtract) && is_key_down(KeyCode::Z) {
                 let mut decrement: u16 = 1;
-                if is_key_down(KeyCode::LeftControl) {
-                    if space.y_dim() > 10 {
-                        decrement = 10;
-                    }
+                if is_key_down(KeyCode::LeftControl) && space.y_dim() > 10 {
+                    decrement = 10;
                 }
                 if space.y_dim() == 1 {
                     decrement = 0;
                 }
-                let mut new_space = Space::new(space.x_dim(), space.y_dim()-decrement);
-                for alive_cell in space.get_alive_cells() {
-                    let res = new_space.get_cell_mut(alive_cell.x, alive_cell.y);
-                    if res.is_ok() {
-                        res.unwrap().revive();
-                    }
-                }
-                space = new_space;
-                set_window_size((space.x_dim() as f32 * CELL_SIZE) as u32, (space.y_dim() as f32 * CELL_SIZE) as u32);
+                resize_space(&simulation, space.x_dim(), space.y_dim() - decrement);
             }
             if is_key_down(KeyCode::R) {
                 if is_key_down(KeyCode::LeftControl) {
-                    space.revive_random_cells(10);
+                    simulation.send(Command::ReviveRandomCells(10));
                 } else {
-                    space.revive_random_cells(1);
+                    simulation.send(Command::ReviveRandomCells(1));
                 }
             }
             if is_key_released(KeyCode::R) {
-                space.save_state(time_step_current +1);
+                simulation.send(Command::CommitEdit);
+            }
+            if is_key_pressed(KeyCode::C) && !is_key_down(KeyCode::LeftControl) {
+                settings.colormap = settings.colormap.next();
+            }
+            if is_key_pressed(KeyCode::A) {
+                settings.color_by_age = !settings.color_by_age;
+            }
+            if is_key_pressed(KeyCode::G) {
+                settings.grid_is_on = !settings.grid_is_on;
+            }
+            if is_key_pressed(KeyCode::U) {
+                settings.rulers_is_on = !settings.rulers_is_on;
+            }
+            if is_key_pressed(KeyCode::H) {
+                settings.inspector_is_on = !settings.inspector_is_on;
+            }
+            if is_key_pressed(KeyCode::P) && !is_key_down(KeyCode::LeftControl) {
+                if snapshot.characterization.is_none() {
+                    simulation.send(Command::Characterize(settings.soup_region, CHARACTERIZE_GENERATIONS));
+                } else {
+                    simulation.send(Command::ClearCharacterization);
+                }
+            }
+            if is_key_pressed(KeyCode::W) {
+                if let Automaton::Linear(rule) = space.automaton {
+                    settings.linear_rule = rule;
+                    simulation.send(Command::SetAutomaton(Automaton::LifeLike));
+                } else {
+                    simulation.send(Command::SetAutomaton(Automaton::Linear(settings.linear_rule)));
+                }
+            }
+            if is_key_pressed(KeyCode::M) && !is_key_down(KeyCode::LeftControl) {
+                let automaton = space.automaton.next_multi_state();
+                settings.paint_state = automaton.get_num_states() - 1;
+                simulation.send(Command::SetAutomaton(automaton));
+            }
+            if is_key_pressed(KeyCode::Q) {
+                simulation.send(Command::SetAutomaton(space.automaton.next_turmite()));
+            }
+            if is_key_pressed(KeyCode::V) && is_key_down(KeyCode::LeftControl) {
+                settings.velocity_field_is_on = !settings.velocity_field_is_on;
+            }
+            if is_key_pressed(KeyCode::V) && !is_key_down(KeyCode::LeftControl) {
+                simulation.send(Command::SetAutomaton(space.automaton.next_continuous()));
+            }
+            if is_key_pressed(KeyCode::J) {
+                simulation.send(Command::SetAutomaton(space.automaton.next_margolus()));
+            }
+            if is_key_pressed(KeyCode::D) {
+                settings.channel = settings.channel.next();
+            }
+            if let Automaton::Linear(rule) = space.automaton {
+                process_rule_input(&simulation, &mut settings, rule);
+            } else if space.automaton.get_palette().is_some() {
+                process_palette_keys(&mut settings, space.automaton.get_num_states());
+            }
+            if is_key_pressed(KeyCode::L) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_rule_open_dialog().await {
+                    match table::read_rule_file(path.to_str().unwrap()) {
+                        Ok(rule) => {
+                            let automaton = Automaton::Table(rule);
+                            settings.paint_state = automaton.get_num_states() - 1;
+                            simulation.send(Command::SetAutomaton(automaton));
+                        }
+                        Err(error) => eprintln!("Failed to load rule file: {}", error),
+                    }
+                }
+            }
+            if is_key_pressed(KeyCode::L) && !is_key_down(KeyCode::LeftControl) {
+                simulation.send(Command::ShowObjects(snapshot.objects.is_none()));
+            }
+            if is_key_pressed(KeyCode::N) {
+                let seed: u64 = if is_key_down(KeyCode::LeftShift) { space.get_seed() } else { ::rand::random() };
+                simulation.send(Command::FillRandom(seed, settings.soup_options()));
+            }
+            if is_key_pressed(KeyCode::PageUp) {
+                settings.soup_density = (settings.soup_density + 0.05).min(1.);
+            }
+            if is_key_pressed(KeyCode::PageDown) {
+                settings.soup_density = (settings.soup_density - 0.05).max(0.);
+            }
+            if is_key_pressed(KeyCode::Y) {
+                settings.soup_symmetry = settings.soup_symmetry.next();
             }
             if is_key_pressed(KeyCode::T) {
                 settings.tracing = !settings.tracing;
             }
             if is_key_pressed(KeyCode::K) {
-                space.kill_all_cells();
+                simulation.send(Command::KillAllCells);
             }
             if is_key_pressed(KeyCode::Escape) {
                 settings.is_active = !settings.is_active;
             }
-            if is_key_pressed(KeyCode::F) {
+            if is_key_pressed(KeyCode::F) && is_key_down(KeyCode::LeftControl) {
+                if let Some(path) = show_histogram_export_dialog().await {
+                    let result = match space.automaton {
+                        Automaton::Sandpile(_) => space.avalanche_statistics.write_histogram_csv(path.to_str().unwrap(), space.displayed_time),
+                        _ => space.fire_statistics.write_histogram_csv(path.to_str().unwrap(), space.displayed_time),
+                    };
+                    if let Err(error) = result {
+                        eprintln!("Failed to export the histogram: {}", error);
+                    }
+                }
+            }
+            if is_key_pressed(KeyCode::F) && !is_key_down(KeyCode::LeftControl) {
                 settings.fps_is_on = !settings.fps_is_on;
                 if settings.fps_is_on {
                     settings.fps_time_start = time.elapsed().as_secs();
@@ -264,80 +398,443 @@ async fn main() {
     }
 }
 
+fn resize_space(simulation: &Simulation, x_dim: u16, y_dim: u16) {
+    simulation.send(Command::Resize(x_dim, y_dim));
+    set_window_size((x_dim as f32 * CELL_SIZE) as u32, (y_dim as f32 * CELL_SIZE) as u32);
+}
+
 fn process_red_slider(settings: &mut Settings, mouse_position: (f32, f32)) {
-    if settings.is_in_red_slider(mouse_position) {
-        if is_mouse_button_pressed(MouseButton::Left) {
-            settings.slider_red_dragging = true;
-        }
+    if settings.is_in_red_slider(mouse_position) && is_mouse_button_pressed(MouseButton::Left) {
+        settings.slider_red_dragging = true;
     }
-    if is_mouse_button_released(MouseButton::Left) {
-        if settings.slider_red_dragging {
-            settings.slider_red_dragging = false;
-        }
+    if is_mouse_button_released(MouseButton::Left) && settings.slider_red_dragging {
+        settings.slider_red_dragging = false;
     }
-    if settings.slider_red_dragging && is_mouse_button_down(MouseButton::Left) {
-        if mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
-            let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
-            let float_value: f32 = relative_position / settings.color_bar_width;
-            settings.color.0 = float_value;
-        }
+    if settings.slider_red_dragging && is_mouse_button_down(MouseButton::Left)
+        && mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
+        let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
+        let float_value: f32 = relative_position / settings.color_bar_width;
+        settings.color.0 = float_value;
     }
 }
 
 fn process_green_slider(settings: &mut Settings, mouse_position: (f32, f32)) {
-    if settings.is_in_green_slider(mouse_position) {
-        if is_mouse_button_pressed(MouseButton::Left) {
-            settings.slider_green_dragging = true;
+    if settings.is_in_green_slider(mouse_position) && is_mouse_button_pressed(MouseButton::Left) {
+        settings.slider_green_dragging = true;
+    }
+    if is_mouse_button_released(MouseButton::Left) && settings.slider_green_dragging {
+        settings.slider_green_dragging = false;
+    }
+    if settings.slider_green_dragging && is_mouse_button_down(MouseButton::Left)
+        && mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
+        let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
+        let float_value: f32 = relative_position / settings.color_bar_width;
+        settings.color.1 = float_value;
+    }
+}
+
+fn process_blue_slider(settings: &mut Settings, mouse_position: (f32, f32)) {
+    if settings.is_in_blue_slider(mouse_position) && is_mouse_button_pressed(MouseButton::Left) {
+        settings.slider_blue_dragging = true;
+    }
+    if is_mouse_button_released(MouseButton::Left) && settings.slider_blue_dragging {
+        settings.slider_blue_dragging = false;
+    }
+    if settings.slider_blue_dragging && is_mouse_button_down(MouseButton::Left)
+        && mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
+        let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
+        let float_value: f32 = relative_position / settings.color_bar_width;
+        settings.color.2 = float_value;
+    }
+}
+
+fn process_region_selection(settings: &mut Settings, cell: (u16, u16), is_in_space: bool) {
+    if is_mouse_button_pressed(MouseButton::Left) && is_in_space {
+        settings.region_start = Some(cell);
+    }
+    if let Some(start) = settings.region_start {
+        if is_mouse_button_down(MouseButton::Left) && is_in_space {
+            let x = start.0.min(cell.0);
+            let y = start.1.min(cell.1);
+            settings.soup_region = Some(Region::new(x, y, start.0.max(cell.0) - x + 1, start.1.max(cell.1) - y + 1));
         }
     }
     if is_mouse_button_released(MouseButton::Left) {
-        if settings.slider_green_dragging {
-            settings.slider_green_dragging = false;
+        settings.region_start = None;
+    }
+    if is_mouse_button_pressed(MouseButton::Right) {
+        settings.soup_region = None;
+    }
+}
+
+fn process_rule_input(simulation: &Simulation, settings: &mut Settings, rule: LinearRule) {
+    for (digit, key) in DIGIT_KEYS.iter().enumerate() {
+        if is_key_pressed(*key) {
+            settings.rule_input.push_str(&digit.to_string());
         }
     }
-    if settings.slider_green_dragging && is_mouse_button_down(MouseButton::Left) {
-        if mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
-            let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
-            let float_value: f32 = relative_position / settings.color_bar_width;
-            settings.color.1 = float_value;
+    if is_key_pressed(KeyCode::Backspace) {
+        settings.rule_input.pop();
+    }
+    let mut new_rule = None;
+    if is_key_pressed(KeyCode::Enter) {
+        if let Ok(code) = settings.rule_input.parse::<u64>() {
+            new_rule = Some(rule.with_code(code));
         }
+        settings.rule_input.clear();
+    }
+    if is_key_pressed(KeyCode::Equal) || is_key_pressed(KeyCode::KpAdd) && !is_key_down(KeyCode::X) && !is_key_down(KeyCode::Z) {
+        new_rule = Some(rule.next());
+    }
+    if is_key_pressed(KeyCode::Minus) || is_key_pressed(KeyCode::KpSubtract) && !is_key_down(KeyCode::X) && !is_key_down(KeyCode::Z) {
+        new_rule = Some(rule.previous());
+    }
+    if is_key_pressed(KeyCode::Tab) {
+        new_rule = Some(rule.next_kind());
+    }
+    if let Some(new_rule) = new_rule {
+        settings.linear_rule = new_rule;
+        simulation.send(Command::SetAutomaton(Automaton::Linear(new_rule)));
     }
 }
 
-fn process_blue_slider(settings: &mut Settings, mouse_position: (f32, f32)) {
-    if settings.is_in_blue_slider(mouse_position) {
-        if is_mouse_button_pressed(MouseButton::Left) {
-            settings.slider_blue_dragging = true;
-        }
+// Edits the rulestring of the life-like rule, totalistic like B36/S23 or with Hensel's letters like B2-a/S12.
+fn process_life_rule_input(simulation: &Simulation, settings: &mut Settings) {
+    let Some(input) = settings.life_rule_input.as_mut() else {
+        return;
+    };
+    // the queue returns the last typed character first
+    let mut typed_chars = Vec::new();
+    while let Some(typed_char) = get_char_pressed() {
+        typed_chars.push(typed_char);
     }
-    if is_mouse_button_released(MouseButton::Left) {
-        if settings.slider_blue_dragging {
-            settings.slider_blue_dragging = false;
+    input.extend(typed_chars.into_iter().rev().filter(|typed_char| typed_char.is_ascii_alphanumeric() || *typed_char == '/' || *typed_char == '-'));
+    if is_key_pressed(KeyCode::Backspace) {
+        input.pop();
+    }
+    if is_key_pressed(KeyCode::Enter) {
+        match Rule::parse(input) {
+            Ok(rule) => {
+                simulation.send(Command::SetRule(rule));
+                settings.life_rule_input = None;
+            }
+            Err(error) => eprintln!("{}", error),
         }
+    } else if is_key_pressed(KeyCode::Escape) {
+        settings.life_rule_input = None;
     }
-    if settings.slider_blue_dragging && is_mouse_button_down(MouseButton::Left) {
-        if mouse_position.0 >= settings.bar_x_position && mouse_position.0 <= settings.bar_x_position + settings.color_bar_width {
-            let relative_position: f32 = mouse_position.0 - settings.bar_x_position;
-            let float_value: f32 = relative_position / settings.color_bar_width;
-            settings.color.2 = float_value;
+}
+
+fn process_palette_keys(settings: &mut Settings, num_states: u8) {
+    for (state, key) in DIGIT_KEYS.iter().enumerate().take(num_states as usize) {
+        if is_key_pressed(*key) {
+            settings.paint_state = state as u8;
         }
     }
 }
 
-fn draw(space: &mut Space, tracing: bool, color: &(f32, f32, f32), fps: &u64, fps_is_on: bool) {
-    if tracing {
-        for cell in space.get_cells_with_energy() {
-            let color = Color::new(color.0, color.1, color.2, cell.get_state() as f32 / 255.);
-            draw_rectangle(cell.x as f32 * CELL_SIZE, cell.y as f32 * CELL_SIZE, CELL_SIZE, CELL_SIZE, color);
+fn process_colormap_controls(settings: &mut Settings, mouse_position: (f32, f32)) {
+    if settings.is_in_colormap_button(mouse_position) && is_mouse_button_pressed(MouseButton::Left) {
+        settings.colormap = settings.colormap.next();
+    }
+    if settings.is_in_age_button(mouse_position) && is_mouse_button_pressed(MouseButton::Left) {
+        settings.color_by_age = !settings.color_by_age;
+    }
+    if settings.colormap == Colormap::Custom && settings.is_in_gradient_bar(mouse_position) {
+        let gradient_position = (mouse_position.0 - settings.bar_x_position) / settings.color_bar_width;
+        if is_mouse_button_pressed(MouseButton::Left) {
+            settings.custom_gradient.add_stop(gradient_position, settings.color);
         }
+        if is_mouse_button_pressed(MouseButton::Right) {
+            settings.custom_gradient.remove_stop_near(gradient_position);
+        }
+    }
+}
+
+fn to_color(rgba: (f32, f32, f32, f32)) -> Color {
+    Color::new(rgba.0, rgba.1, rgba.2, rgba.3)
+}
+
+fn draw(snapshot: &Snapshot, settings: &Settings) {
+    let space: &Space = &snapshot.space;
+    let cells = if settings.tracing || !space.automaton.has_traces() {
+        space.get_cells_with_energy()
     } else {
-        for cell in space.get_alive_cells() {
-            let color = Color::new(color.0, color.1, color.2, 1.);
-            draw_rectangle(cell.x as f32 * CELL_SIZE, cell.y as f32 * CELL_SIZE, CELL_SIZE, CELL_SIZE, color);
+        space.get_alive_cells()
+    };
+    let render_options = settings.render_options();
+    for cell in cells {
+        let color = to_color(render::get_cell_color(space, cell, &render_options));
+        draw_rectangle(cell.x as f32 * CELL_SIZE, cell.y as f32 * CELL_SIZE, CELL_SIZE, CELL_SIZE, color);
+    }
+    if settings.grid_is_on {
+        draw_grid_lines(space);
+    }
+    if settings.rulers_is_on {
+        draw_rulers(space);
+    }
+    if let Some(objects) = &snapshot.objects {
+        draw_object_labels(objects);
+    }
+    if let Automaton::Turmite(_) = space.automaton {
+        draw_ants(space);
+    }
+    if let (Automaton::LatticeGas(model), true) = (&space.automaton, settings.velocity_field_is_on) {
+        draw_velocity_field(space, *model);
+    }
+    if let Some(region) = settings.soup_region {
+        draw_rectangle_lines(
+            region.x as f32 * CELL_SIZE, region.y as f32 * CELL_SIZE,
+            region.width as f32 * CELL_SIZE, region.height as f32 * CELL_SIZE, 2., YELLOW
+        );
+    }
+    if let Some(characterization) = &snapshot.characterization {
+        draw_text_panel(characterization, space.x_dim() as f32 * CELL_SIZE);
+    }
+    if settings.fps_is_on {
+        draw_text(&settings.fps.to_string(), 20., 20., 20., WHITE);
+    }
+    if let (Some(palette), Some(state_names)) = (space.automaton.get_palette(), space.automaton.get_state_names()) {
+        draw_legend(palette, state_names, settings.paint_state, space.x_dim() as f32 * CELL_SIZE);
+    }
+    if let Automaton::Linear(rule) = space.automaton {
+        let rule_status = format!("1D rule: {}  (type a number + enter, +/- to step, tab for colors and radius)  {}", rule, settings.rule_input);
+        draw_text(&rule_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Turmite(rule) = space.automaton {
+        let turmite_status = format!("turmite: {}  ants: {}  (click to place ants, q for the next turmite)", rule, space.ants.len());
+        draw_text(&turmite_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Continuous(rule) = space.automaton {
+        let continuous_status = format!("continuous: {}  (n for random values, v for the next rule)", rule);
+        draw_text(&continuous_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Margolus(rule) = space.automaton {
+        let reversibility = if rule.is_reversible() { "reversible, steps back by computation" } else { "not reversible" };
+        let block_status = format!("block rule: {}  ({}, j for the next rule)", rule, reversibility);
+        draw_text(&block_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Cyclic(rule) = space.automaton {
+        let cyclic_status = format!("cyclic: {}  (n for random states, m for the next rule)", rule);
+        draw_text(&cyclic_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::ReactionDiffusion(rule) = space.automaton {
+        let reaction_status = format!("reaction-diffusion: {}  channel: {}  (paint v with the mouse, d for the channel)", rule, settings.channel.name());
+        draw_text(&reaction_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::ForestFire(rule) = space.automaton {
+        let statistics = &space.fire_statistics;
+        let fire_status = format!(
+            "forest fire: {}  fires: {} burnt out, {} burning  (n for trees, ctrl + f to export the fire sizes)",
+            rule, statistics.get_num_fires(space.displayed_time), statistics.get_num_burning_fires(space.displayed_time)
+        );
+        draw_text(&fire_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Sandpile(rule) = space.automaton {
+        let statistics = &space.avalanche_statistics;
+        let sandpile_status = format!(
+            "sandpile: {}  grains dropped: {}  avalanches: {}, largest {} topplings  (click to drop grains, ctrl + f to export the avalanches)",
+            rule, statistics.get_num_drops(space.displayed_time), statistics.get_num_avalanches(space.displayed_time), statistics.get_largest_size(space.displayed_time)
+        );
+        draw_text(&sandpile_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::LatticeGas(model) = space.automaton {
+        let gas_status = format!(
+            "lattice gas: {}  particles: {}  (paint particles or obstacles from the legend, ctrl + v for the velocity field)",
+            model.name().to_uppercase(), space.get_num_particles()
+        );
+        draw_text(&gas_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Automaton::Schelling(rule) = space.automaton {
+        let statistics = &space.segregation_statistics;
+        let index = space.get_segregation_index().map_or("-".to_string(), |index| format!("{:.3}", index));
+        let moves = statistics.get_moves(space.displayed_time).map_or("-".to_string(), |moves| moves.to_string());
+        let schelling_status = format!("schelling: {}  segregation index: {}  moved: {}  (n for agents, m for the next rule)", rule, index, moves);
+        draw_text(&schelling_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+        draw_segregation_plot(space, space.y_dim() as f32 * CELL_SIZE - 50.);
+    }
+    if let Automaton::Table(rule) = &space.automaton {
+        let table = rule.get_table();
+        let table_status = format!(
+            "rule table: {}  states: {}  {} neighbourhood, {} symmetry  (ctrl+l to load a rule file)",
+            table.get_name(), table.get_num_states(), table.get_neighborhood().name(), table.get_symmetry().name()
+        );
+        draw_text(&table_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if space.automaton == Automaton::LifeLike && space.update != UpdateOptions::default() {
+        let update_status = format!("update: {}  (o for the next scheme, alt + o for the rate or block size, shift + o for the transition probability)", space.update);
+        draw_text(&update_status, 10., space.y_dim() as f32 * CELL_SIZE - 30., 20., WHITE);
+    }
+    if let Some(input) = &settings.life_rule_input {
+        let input_status = format!("life rule: {}_  (enter to apply, esc to cancel)", input);
+        draw_text(&input_status, 10., space.y_dim() as f32 * CELL_SIZE - 50., 20., WHITE);
+    }
+    if settings.is_active {
+        let soup_status = format!(
+            "seed: {}  density: {}%  symmetry: {}",
+            space.get_seed(), (settings.soup_density * 100.).round(), settings.soup_symmetry.name()
+        );
+        draw_text(&soup_status, 10., space.y_dim() as f32 * CELL_SIZE - 10., 20., WHITE);
+    }
+}
+
+fn draw_grid_lines(space: &Space) {
+    let color = Color::new(0.3, 0.3, 0.3, 1.);
+    let width = space.x_dim() as f32 * CELL_SIZE;
+    let height = space.y_dim() as f32 * CELL_SIZE;
+    for x in 0..=space.x_dim() {
+        draw_line(x as f32 * CELL_SIZE, 0., x as f32 * CELL_SIZE, height, 1., color);
+    }
+    for y in 0..=space.y_dim() {
+        draw_line(0., y as f32 * CELL_SIZE, width, y as f32 * CELL_SIZE, 1., color);
+    }
+}
+
+fn draw_rulers(space: &Space) {
+    let background = Color::new(0., 0., 0., 0.6);
+    draw_rectangle(0., 0., space.x_dim() as f32 * CELL_SIZE, RULER_SIZE, background);
+    draw_rectangle(0., RULER_SIZE, RULER_SIZE, space.y_dim() as f32 * CELL_SIZE - RULER_SIZE, background);
+    for x in (0..space.x_dim()).step_by(RULER_STEP as usize) {
+        let position = x as f32 * CELL_SIZE;
+        draw_line(position, 0., position, RULER_SIZE, 1., WHITE);
+        draw_text(&x.to_string(), position + 2., RULER_SIZE - 3., 14., WHITE);
+    }
+    for y in (RULER_STEP..space.y_dim()).step_by(RULER_STEP as usize) {
+        let position = y as f32 * CELL_SIZE;
+        draw_line(0., position, RULER_SIZE, position, 1., WHITE);
+        draw_text(&y.to_string(), 1., position + 12., 14., WHITE);
+    }
+}
+
+fn draw_object_labels(objects: &[SpaceObject]) {
+    for object in objects {
+        let color = match object.classification.kind {
+            ObjectKind::StillLife => GREEN,
+            ObjectKind::Oscillator { .. } => ORANGE,
+            ObjectKind::Spaceship { .. } => SKYBLUE,
+            ObjectKind::Unknown => GRAY,
+        };
+        let x = object.x as f32 * CELL_SIZE;
+        let y = object.y as f32 * CELL_SIZE;
+        draw_rectangle_lines(x, y, object.width as f32 * CELL_SIZE, object.height as f32 * CELL_SIZE, 2., color);
+        draw_text(&object.get_label(), x, (y - 4.).max(14.), 18., color);
+    }
+}
+
+// Ants are drawn as triangles pointing in their heading.
+fn draw_ants(space: &Space) {
+    for ant in &space.ants {
+        let center = vec2((ant.x as f32 + 0.5) * CELL_SIZE, (ant.y as f32 + 0.5) * CELL_SIZE);
+        let (dx, dy) = ant.heading.get_offset();
+        let forward = vec2(dx as f32, dy as f32) * CELL_SIZE * 0.6;
+        let side = vec2(-forward.y, forward.x) * 0.7;
+        draw_triangle(center + forward, center - forward * 0.6 + side, center - forward * 0.6 - side, RED);
+    }
+}
+
+// Arrows of the mean velocity of the blocks, a full cell long for a velocity of one cell per generation.
+fn draw_velocity_field(space: &Space, model: GasModel) {
+    for ((x, y), (vx, vy)) in space.get_velocity_field(model, VELOCITY_BLOCK_SIZE) {
+        let start = vec2(x, y) * CELL_SIZE;
+        let arrow = vec2(vx, vy) * VELOCITY_BLOCK_SIZE as f32 * CELL_SIZE;
+        if arrow.length() < 1. {
+            continue;
         }
+        let end = start + arrow;
+        let side = vec2(-arrow.y, arrow.x).normalize() * 4.;
+        let back = end - arrow.normalize() * 8.;
+        draw_line(start.x, start.y, end.x, end.y, 2., YELLOW);
+        draw_triangle(end, back + side, back - side, YELLOW);
     }
-    if fps_is_on {
-        draw_text(&*fps.to_string(), 20., 20., 20., WHITE);
+}
+
+fn draw_legend(palette: &[(f32, f32, f32)], state_names: &[&str], paint_state: u8, current_width: f32) {
+    let (x, y) = get_legend_position(current_width);
+    let height = LEGEND_ROW_HEIGHT * state_names.len() as f32 + 6.;
+    draw_rectangle(x, y, LEGEND_WIDTH, height, Color::new(0., 0., 0., 0.8));
+    draw_rectangle_lines(x, y, LEGEND_WIDTH, height, 1., WHITE);
+    for (state, (name, color)) in state_names.iter().zip(palette).enumerate() {
+        let row_y = y + 3. + LEGEND_ROW_HEIGHT * state as f32;
+        draw_rectangle(x + 6., row_y + 3., 14., 14., Color::new(color.0, color.1, color.2, 1.));
+        draw_rectangle_lines(x + 6., row_y + 3., 14., 14., 1., GRAY);
+        let text_color = if state as u8 == paint_state { YELLOW } else { WHITE };
+        draw_text(&format!("{} {}", state, name), x + 28., row_y + 15., 18., text_color);
+    }
+}
+
+fn get_legend_position(current_width: f32) -> (f32, f32) {
+    ((current_width - LEGEND_WIDTH - 10.).max(0.), RULER_SIZE + 10.)
+}
+
+// State of the legend row under the mouse.
+fn get_legend_state(mouse_position: (f32, f32), num_states: u8, current_width: f32) -> Option<u8> {
+    let (x, y) = get_legend_position(current_width);
+    if mouse_position.0 < x || mouse_position.0 > x + LEGEND_WIDTH || mouse_position.1 < y + 3. {
+        return None;
+    }
+    let state = ((mouse_position.1 - y - 3.) / LEGEND_ROW_HEIGHT) as u8;
+    (state < num_states).then_some(state)
+}
+
+// Segregation index of the generations up to the displayed one, between 0.5 and 1 from bottom to top.
+fn draw_segregation_plot(space: &Space, bottom: f32) {
+    let (x, y) = (10., (bottom - PLOT_SIZE.1).max(0.));
+    draw_rectangle(x, y, PLOT_SIZE.0, PLOT_SIZE.1, Color::new(0., 0., 0., 0.8));
+    draw_rectangle_lines(x, y, PLOT_SIZE.0, PLOT_SIZE.1, 1., WHITE);
+    draw_text("segregation", x + 6., y + 16., 16., WHITE);
+    let indices: Vec<(usize, f32)> = space.segregation_statistics.get_indices().range(..=space.displayed_time).map(|(&time, &index)| (time, index)).collect();
+    let num_times = (space.displayed_time + 1).max(2) as f32;
+    let to_point = |(time, index): (usize, f32)| {
+        vec2(x + PLOT_SIZE.0 * time as f32 / (num_times - 1.), y + PLOT_SIZE.1 * (1. - ((index - 0.5) * 2.).clamp(0., 1.)))
+    };
+    for pair in indices.windows(2) {
+        let (start, end) = (to_point(pair[0]), to_point(pair[1]));
+        draw_line(start.x, start.y, end.x, end.y, 2., YELLOW);
+    }
+    if let Some(&last) = indices.last() {
+        let point = to_point(last);
+        draw_circle(point.x, point.y, 3., YELLOW);
+    }
+}
+
+fn draw_text_panel(lines: &[String], current_width: f32) {
+    let width = 320.;
+    let height = 18. * lines.len() as f32 + 6.;
+    let x = (current_width - width - 10.).max(0.);
+    let y = 10.;
+    draw_rectangle(x, y, width, height, Color::new(0., 0., 0., 0.8));
+    draw_rectangle_lines(x, y, width, height, 1., WHITE);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x + 6., y + 18. * (i + 1) as f32, 18., WHITE);
+    }
+}
+
+fn draw_inspector(space: &Space, mouse_position: (f32, f32), current_width: f32, current_height: f32) {
+    let cell_x = (mouse_position.0 / CELL_SIZE).floor() as u16;
+    let cell_y = (mouse_position.1 / CELL_SIZE).floor() as u16;
+    let Ok(cell) = space.get_cell(cell_x, cell_y) else {
+        return;
+    };
+    let mut lines = vec![format!("({}, {})", cell_x, cell_y), format!("state: {}", cell.get_state())];
+    // neighbours and fate follow the life-like rule of the space
+    if matches!(space.automaton, Automaton::LifeLike) {
+        lines.push(format!("neighbours: {}", Space::count_alive_neighbours(space, cell)));
+        lines.push(format!("next: {}", space.get_cell_fate(cell_x, cell_y).unwrap().name()));
+    }
+    if let Some(description) = space.values.get_description(cell_x, cell_y) {
+        lines.push(description);
+    } else if let Some(birth_time) = space.get_birth_time(cell_x, cell_y).unwrap() {
+        lines.push(format!("alive for: {}", space.get_generations_alive(cell_x, cell_y).unwrap()));
+        lines.push(format!("born: {}", birth_time));
+    }
+    let width = 130.;
+    let height = 18. * lines.len() as f32 + 6.;
+    let x = (mouse_position.0 + 16.).min(current_width - width).max(0.);
+    let y = (mouse_position.1 + 16.).min(current_height - height).max(0.);
+    draw_rectangle(x, y, width, height, Color::new(0., 0., 0., 0.8));
+    draw_rectangle_lines(x, y, width, height, 1., WHITE);
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(line, x + 6., y + 18. * (i + 1) as f32, 18., WHITE);
     }
 }
 
@@ -353,6 +850,79 @@ async fn show_export_dialog() -> Option<PathBuf>{
     }
 }
 
+async fn show_screenshot_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Save Screenshot")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .set_filename("screenshot.png")
+        .show_save_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
+async fn show_animation_export_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Export Animation")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .set_filename("export.gif")
+        .add_filter("Animation", &["gif", "apng"])
+        .show_save_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
+async fn show_session_save_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Save Session")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .set_filename("session.casession")
+        .show_save_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
+async fn show_session_open_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Open Session")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .set_filename("session.casession")
+        .show_open_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
+async fn show_rule_open_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Load Rule")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .add_filter("Golly rule", &["rule"])
+        .show_open_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
+async fn show_histogram_export_dialog() -> Option<PathBuf>{
+    let dialog_window = FileDialog::new()
+        .set_title("Export Histogram")
+        .set_location(std::env::current_dir().unwrap().as_path())
+        .set_filename("histogram.csv")
+        .show_save_single_file();
+    match dialog_window {
+        Ok(Some(path)) => Some(path),
+        _ => None,
+    }
+}
+
 async fn show_import_dialog() -> Option<PathBuf>{
     let dialog_window = FileDialog::new()
         .set_title("Import Grid")
@@ -374,6 +944,9 @@ struct Settings {
     settings_width: f32,
     settings_height: f32,
     color: (f32, f32, f32),
+    colormap: Colormap,
+    custom_gradient: Gradient,
+    color_by_age: bool,
     color_bar_width: f32,
     color_bar_height: f32,
     slider_width: f32,
@@ -385,6 +958,25 @@ struct Settings {
     slider_blue_position: (f32, f32),
     slider_blue_dragging: bool,
     bar_x_position: f32,
+    colormap_button_position: (f32, f32),
+    age_button_position: (f32, f32),
+    age_button_width: f32,
+    gradient_bar_position: (f32, f32),
+    soup_density: f64,
+    soup_symmetry: Symmetry,
+    soup_region: Option<Region>,
+    region_start: Option<(u16, u16)>,
+    animation_start: usize,
+    animation_end: Option<usize>,
+    grid_is_on: bool,
+    rulers_is_on: bool,
+    inspector_is_on: bool,
+    velocity_field_is_on: bool,
+    channel: Channel,
+    linear_rule: LinearRule,
+    paint_state: u8,
+    rule_input: String,
+    life_rule_input: Option<String>, // rulestring being typed, None if not editing
     fps: u64,
     fps_is_on: bool,
     fps_counter: u64,
@@ -400,8 +992,11 @@ impl Settings {
             tracing: false,
             is_active: false,
             settings_width: 300.,
-            settings_height: 100.,
+            settings_height: 160.,
             color: (0.05, 0.15, 1.), // (R, G, B)
+            colormap: Colormap::Solid,
+            custom_gradient: Gradient::new((0., 0., 0.), (0.05, 0.15, 1.)),
+            color_by_age: false,
             color_bar_width: 200.,
             color_bar_height: 20.,
             slider_width: 10.,
@@ -413,12 +1008,59 @@ impl Settings {
             slider_blue_position: (0.0, 0.0),
             slider_blue_dragging: false,
             bar_x_position: 0.0,
+            colormap_button_position: (0.0, 0.0),
+            age_button_position: (0.0, 0.0),
+            age_button_width: 60.,
+            gradient_bar_position: (0.0, 0.0),
+            soup_density: 0.5,
+            soup_symmetry: Symmetry::C1,
+            soup_region: None,
+            region_start: None,
+            animation_start: 0,
+            animation_end: None,
+            grid_is_on: false,
+            rulers_is_on: false,
+            inspector_is_on: false,
+            velocity_field_is_on: false,
+            channel: Channel::default(),
+            linear_rule: LinearRule::Elementary(30),
+            paint_state: 1,
+            rule_input: String::new(),
+            life_rule_input: None,
             fps: 0,
             fps_is_on: false,
             fps_counter: 0,
             fps_time_start: 0,
         }
     }
+    fn render_options(&self) -> RenderOptions {
+        RenderOptions {
+            cell_size: CELL_SIZE as u32,
+            color: self.color,
+            colormap: self.colormap,
+            custom_gradient: self.custom_gradient.clone(),
+            tracing: self.tracing,
+            color_by_age: self.color_by_age,
+            grid_lines: self.grid_is_on,
+            channel: self.channel,
+            ..RenderOptions::default()
+        }
+    }
+
+    fn soup_options(&self) -> SoupOptions {
+        SoupOptions { density: self.soup_density, region: self.soup_region, symmetry: self.soup_symmetry }
+    }
+
+    fn apply_render_options(&mut self, view: &RenderOptions) {
+        self.color = view.color;
+        self.colormap = view.colormap;
+        self.custom_gradient = view.custom_gradient.clone();
+        self.tracing = view.tracing;
+        self.color_by_age = view.color_by_age;
+        self.grid_is_on = view.grid_lines;
+        self.channel = view.channel;
+    }
+
     fn get_position(&self, current_width: f32, current_height: f32) -> (f32, f32) {
         (0.5 * current_width - 0.5 * self.settings_width, 0.5 * current_height - 0.5 * self.settings_height)
     }
@@ -460,9 +1102,58 @@ impl Settings {
         draw_rectangle(
             position.0 + 30. + self.color_bar_width,
             position.1 + 15., self.settings_width - self.color_bar_width - 40.,
-            self.settings_height - 40.,
+            3. * self.color_bar_height,
             color
         );
+        self.colormap_button_position = (self.bar_x_position, position.1 + 100.);
+        draw_rectangle(
+            self.colormap_button_position.0, self.colormap_button_position.1,
+            self.color_bar_width, self.color_bar_height, LIGHTGRAY
+        );
+        draw_text(
+            &format!("colormap: {}", self.colormap.name()),
+            self.colormap_button_position.0 + 5., self.colormap_button_position.1 + 15., 20., BLACK
+        );
+        self.age_button_position = (self.bar_x_position + self.color_bar_width + 10., position.1 + 100.);
+        draw_rectangle(
+            self.age_button_position.0, self.age_button_position.1,
+            self.age_button_width, self.color_bar_height, if self.color_by_age { DARKGRAY } else { LIGHTGRAY }
+        );
+        draw_text("age", self.age_button_position.0 + 5., self.age_button_position.1 + 15., 20., BLACK);
+        self.gradient_bar_position = (self.bar_x_position, position.1 + 130.);
+        let num_gradient_slices = 50;
+        let slice_width = self.color_bar_width / num_gradient_slices as f32;
+        for i in 0..num_gradient_slices {
+            let value = i as f32 / (num_gradient_slices - 1) as f32;
+            let rgba = self.colormap.map(value, &self.color, &self.custom_gradient);
+            draw_rectangle(
+                self.gradient_bar_position.0 + i as f32 * slice_width, self.gradient_bar_position.1,
+                slice_width, self.color_bar_height, Color::new(rgba.0, rgba.1, rgba.2, rgba.3)
+            );
+        }
+        if self.colormap == Colormap::Custom {
+            for stop in self.custom_gradient.stops() {
+                draw_rectangle(
+                    self.gradient_bar_position.0 + stop.0 * self.color_bar_width - 1., self.gradient_bar_position.1 - 2.,
+                    2., self.color_bar_height + 4., BLACK
+                );
+            }
+        }
+    }
+
+    fn is_in_colormap_button(&self, mouse_position: (f32, f32)) -> bool {
+        mouse_position.0 >= self.colormap_button_position.0 && mouse_position.0 <= self.colormap_button_position.0 + self.color_bar_width &&
+            mouse_position.1 >= self.colormap_button_position.1 && mouse_position.1 <= self.colormap_button_position.1 + self.color_bar_height
+    }
+
+    fn is_in_age_button(&self, mouse_position: (f32, f32)) -> bool {
+        mouse_position.0 >= self.age_button_position.0 && mouse_position.0 <= self.age_button_position.0 + self.age_button_width &&
+            mouse_position.1 >= self.age_button_position.1 && mouse_position.1 <= self.age_button_position.1 + self.color_bar_height
+    }
+
+    fn is_in_gradient_bar(&self, mouse_position: (f32, f32)) -> bool {
+        mouse_position.0 >= self.gradient_bar_position.0 && mouse_position.0 <= self.gradient_bar_position.0 + self.color_bar_width &&
+            mouse_position.1 >= self.gradient_bar_position.1 && mouse_position.1 <= self.gradient_bar_position.1 + self.color_bar_height
     }
 
     fn is_in_red_slider(&self, mouse_position: (f32, f32)) -> bool {
@@ -0,0 +1,177 @@
+use std::{error::Error, fs::File, io::BufWriter, path::Path};
+use crate::colormap::{Colormap, Gradient};
+use crate::space::automaton::Automaton;
+use crate::space::cell::Cell;
+use crate::space::reaction::Channel;
+use crate::space::Space;
+
+const AGE_COLOR_RANGE: u32 = 100; // generations alive until a cell gets the oldest color
+const MAX_IMAGE_BYTES: usize = 1 << 30; // larger images are rejected by render_to_png
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RenderOptions {
+    pub cell_size: u32,
+    pub color: (f32, f32, f32),
+    pub colormap: Colormap,
+    pub custom_gradient: Gradient,
+    pub background: (f32, f32, f32),
+    pub tracing: bool,
+    pub color_by_age: bool,
+    pub grid_lines: bool,
+    pub grid_color: (f32, f32, f32),
+    pub channel: Channel, // chemical drawn for reaction-diffusion
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            cell_size: 10,
+            color: (0.05, 0.15, 1.),
+            colormap: Colormap::Solid,
+            custom_gradient: Gradient::new((0., 0., 0.), (0.05, 0.15, 1.)),
+            background: (0., 0., 0.),
+            tracing: false,
+            color_by_age: false,
+            grid_lines: false,
+            grid_color: (0.3, 0.3, 0.3),
+            channel: Channel::default(),
+        }
+    }
+}
+
+// RGBA image with 8 bits per channel, rows stored top to bottom.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, background: (f32, f32, f32)) -> Image {
+        let num_bytes = get_num_bytes(width, height).expect("image too large");
+        let mut pixels = Vec::with_capacity(num_bytes);
+        for _ in 0..num_bytes / 4 {
+            pixels.extend_from_slice(&[to_u8(background.0), to_u8(background.1), to_u8(background.2), 255]);
+        }
+        Image { width, height, pixels }
+    }
+
+    pub fn get_pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let index = (y as usize * self.width as usize + x as usize) * 4;
+        [self.pixels[index], self.pixels[index + 1], self.pixels[index + 2], self.pixels[index + 3]]
+    }
+
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        let mut pixels = Vec::with_capacity(get_num_bytes(width, height).expect("image too large"));
+        for py in y..y + height {
+            let start = (py as usize * self.width as usize + x as usize) * 4;
+            pixels.extend_from_slice(&self.pixels[start..start + width as usize * 4]);
+        }
+        Image { width, height, pixels }
+    }
+
+    // Draws a rectangle over the image, blending by the alpha of the color.
+    fn blend_rectangle(&mut self, x: u32, y: u32, width: u32, height: u32, rgba: (f32, f32, f32, f32)) {
+        for py in y..(y + height).min(self.height) {
+            for px in x..(x + width).min(self.width) {
+                let index = (py as usize * self.width as usize + px as usize) * 4;
+                for (channel, value) in [rgba.0, rgba.1, rgba.2].iter().enumerate() {
+                    let current = self.pixels[index + channel] as f32 / 255.;
+                    self.pixels[index + channel] = to_u8(value * rgba.3 + current * (1. - rgba.3));
+                }
+            }
+        }
+    }
+}
+
+// Fresh cells get the top of the colormap, cells alive for AGE_COLOR_RANGE generations or more the lower quarter.
+pub fn get_color_value(cell: &Cell, color_by_age: bool) -> f32 {
+    if color_by_age && cell.is_alive() {
+        let age_fraction = cell.get_generations_alive().min(AGE_COLOR_RANGE) as f32 / AGE_COLOR_RANGE as f32;
+        1. - 0.75 * age_fraction
+    } else {
+        cell.get_state() as f32 / 255.
+    }
+}
+
+// Palette color of multi-state automata, otherwise the colormap color of the state or the chosen chemical.
+pub fn get_cell_color(space: &Space, cell: &Cell, options: &RenderOptions) -> (f32, f32, f32, f32) {
+    if let Automaton::ReactionDiffusion(_) = space.automaton {
+        let value = options.channel.get_value(space.get_concentrations(cell.x, cell.y));
+        return options.colormap.map(value, &options.color, &options.custom_gradient);
+    }
+    match space.automaton.get_palette() {
+        Some(palette) => {
+            let color = palette[cell.get_state_index(palette.len() as u8) as usize];
+            (color.0, color.1, color.2, 1.)
+        }
+        None => {
+            let color_by_age = options.color_by_age && !space.automaton.has_cell_values();
+            options.colormap.map(get_color_value(cell, color_by_age), &options.color, &options.custom_gradient)
+        }
+    }
+}
+
+pub fn render(space: &Space, options: &RenderOptions) -> Image {
+    let cell_size = options.cell_size.max(1);
+    let mut image = Image::new(space.x_dim() as u32 * cell_size, space.y_dim() as u32 * cell_size, options.background);
+    let cells = if options.tracing || !space.automaton.has_traces() {
+        space.get_cells_with_energy()
+    } else {
+        space.get_alive_cells()
+    };
+    for cell in cells {
+        let rgba = get_cell_color(space, cell, options);
+        image.blend_rectangle(cell.x as u32 * cell_size, cell.y as u32 * cell_size, cell_size, cell_size, rgba);
+    }
+    if options.grid_lines {
+        let grid_color = (options.grid_color.0, options.grid_color.1, options.grid_color.2, 1.);
+        for x in 0..=space.x_dim() as u32 {
+            let px = (x * cell_size).min(image.width - 1);
+            image.blend_rectangle(px, 0, 1, image.height, grid_color);
+        }
+        for y in 0..=space.y_dim() as u32 {
+            let py = (y * cell_size).min(image.height - 1);
+            image.blend_rectangle(0, py, image.width, 1, grid_color);
+        }
+    }
+    image
+}
+
+pub fn write_png(file_str: &str, image: &Image) -> Result<(), Box<dyn Error>> {
+    let file = File::create(Path::new(file_str))?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), image.width, image.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&image.pixels)?;
+    Ok(())
+}
+
+pub fn render_to_png(file_str: &str, space: &Space, options: &RenderOptions) -> Result<(), Box<dyn Error>> {
+    if get_image_size(space, options).is_none() {
+        return Err(format!("image of {}x{} cells with a cell size of {} is too large", space.x_dim(), space.y_dim(), options.cell_size).into());
+    }
+    write_png(file_str, &render(space, options))
+}
+
+// Width and height of the rendered space in pixels, None if the image would be larger than MAX_IMAGE_BYTES.
+pub fn get_image_size(space: &Space, options: &RenderOptions) -> Option<(u32, u32)> {
+    let cell_size = options.cell_size.max(1);
+    let width = (space.x_dim() as u32).checked_mul(cell_size)?;
+    let height = (space.y_dim() as u32).checked_mul(cell_size)?;
+    get_num_bytes(width, height).filter(|num_bytes| *num_bytes <= MAX_IMAGE_BYTES)?;
+    Some((width, height))
+}
+
+// Bytes of the pixels of an RGBA image, None on overflow.
+fn get_num_bytes(width: u32, height: u32) -> Option<usize> {
+    (width as usize).checked_mul(height as usize)?.checked_mul(4)
+}
+
+fn to_u8(value: f32) -> u8 {
+    (value.clamp(0., 1.) * 255.).round() as u8
+}
@@ -0,0 +1,428 @@
+use std::{error::Error, fmt, fs, path::Path};
+use crate::colormap::{Colormap, Gradient};
+use crate::render::RenderOptions;
+use crate::space::automaton::Automaton;
+use crate::space::reaction::Channel;
+use crate::space::rule::{Boundary, Rule};
+use crate::space::continuous::ContinuousGrid;
+use crate::space::forest::FireGeneration;
+use crate::space::sandpile::AvalancheGeneration;
+use crate::space::table::{RuleTable, TableRule};
+use crate::space::turmite::{Ant, Heading};
+use crate::space::update::{UpdateOptions, UpdateScheme};
+use crate::space::values::{CellValues, ValueGrid};
+use crate::space::Space;
+
+const SESSION_HEADER: &str = "#CellularAutomata session";
+const SESSION_VERSION: u32 = 9; // version 8 sessions have no segregation statistics, version 7 sessions have no avalanche statistics, version 6 sessions have no fire statistics, version 5 sessions have no update schemes, version 4 sessions have no rule tables, version 3 sessions have no cell values, version 2 sessions have no ants, version 1 sessions have no automaton and store alive cells as <x>,<y>,<generations_alive>
+
+// A session stores the space with its complete history, rule and boundary, plus the view settings.
+//
+// Format (version 9), one entry per line:
+//   #CellularAutomata session
+//   version 9
+//   size <x_dim> <y_dim>
+//   rule <rulestring>
+//   table <line>                                 lines of the Golly .rule file of a table automaton
+//   automaton <life|linear rule|named rule|turmite:<rule>|table:<name>|...>
+//   boundary <bounded|toroidal>
+//   update <scheme> <probability> <rate> <block_size>
+//   displayed_time <time>
+//   seed <seed>
+//   view <key> <values...>
+//   cell <x> <y> <state> <generations_alive>     one line per cell with energy
+//   state <time> <x>,<y>,<state>,<generations_alive>...  one line per saved generation, in order
+//   ant <x> <y> <heading> <state>                one line per ant, headings 0 to 3 clockwise from north
+//   ants <time> <x>,<y>,<heading>,<state>...     one line per saved generation with ants
+//   values continuous <x>,<y>,<value>...         values of the automaton that do not fit into the cell states, nonzero ones only
+//   values chemicals <x>,<y>,<u>,<v>...          or the concentrations differing from the empty background u = 1, v = 0
+//   values fires <x>,<y>,<fire>...               or the numbers of the fires burning the cells, nonzero ones only
+//   values heights <x>,<y>,<grains>...           or the grains of a sandpile, nonzero ones only
+//   values particles <x>,<y>,<bits>...           or the directions of the lattice gas particles and the obstacle bit 64, nonzero ones only
+//   state_values <time> continuous <x>,<y>,<value>...  one line per saved generation with values
+//   fire_statistics <time> <fire>,<trees>... burnt_out <size>...  fires still burning and burnt out in a generation
+//   avalanche_statistics <time> <drops> <size>,<duration>...  grains dropped and avalanches of a saved generation
+//   segregation_index <time> <index>             segregation index of a Schelling model in a generation
+//   segregation_moves <time> <moves>             agents moved to reach a generation
+pub fn write_session(file_str: &str, space: &Space, view: &RenderOptions) -> Result<(), Box<dyn Error>> {
+    let mut content = String::new();
+    content.push_str(&format!("{}\n", SESSION_HEADER));
+    content.push_str(&format!("version {}\n", SESSION_VERSION));
+    content.push_str(&format!("size {} {}\n", space.x_dim(), space.y_dim()));
+    content.push_str(&format!("rule {}\n", space.rule));
+    if let Automaton::Table(rule) = &space.automaton {
+        for line in rule.get_table().get_source().lines().filter(|line| !line.trim().is_empty()) {
+            content.push_str(&format!("table {}\n", line));
+        }
+    }
+    content.push_str(&format!("automaton {}\n", space.automaton.name()));
+    content.push_str(&format!("boundary {}\n", space.boundary.name()));
+    content.push_str(&format!("update {} {} {} {}\n", space.update.scheme.name(), space.update.probability, space.update.rate, space.update.block_size));
+    content.push_str(&format!("displayed_time {}\n", space.displayed_time));
+    content.push_str(&format!("seed {}\n", space.get_seed()));
+    content.push_str(&format!("view cell_size {}\n", view.cell_size));
+    content.push_str(&format!("view color {} {} {}\n", view.color.0, view.color.1, view.color.2));
+    content.push_str(&format!("view background {} {} {}\n", view.background.0, view.background.1, view.background.2));
+    content.push_str(&format!("view grid_color {} {} {}\n", view.grid_color.0, view.grid_color.1, view.grid_color.2));
+    content.push_str(&format!("view colormap {}\n", view.colormap.name()));
+    content.push_str("view gradient");
+    for (position, color) in view.custom_gradient.stops() {
+        content.push_str(&format!(" {} {} {} {}", position, color.0, color.1, color.2));
+    }
+    content.push('\n');
+    content.push_str(&format!("view tracing {}\n", view.tracing as u8));
+    content.push_str(&format!("view color_by_age {}\n", view.color_by_age as u8));
+    content.push_str(&format!("view grid_lines {}\n", view.grid_lines as u8));
+    content.push_str(&format!("view channel {}\n", view.channel.name()));
+    for cell in space.get_cells_with_energy() {
+        content.push_str(&format!("cell {} {} {} {}\n", cell.x, cell.y, cell.get_state(), cell.get_generations_alive()));
+    }
+    for (time, saved_tuples) in space.states_hash_map.iter() {
+        content.push_str(&format!("state {}", time));
+        for saved_tuple in saved_tuples {
+            content.push_str(&format!(" {},{},{},{}", saved_tuple.0, saved_tuple.1, saved_tuple.2, saved_tuple.3));
+        }
+        content.push('\n');
+    }
+    for ant in &space.ants {
+        content.push_str(&format!("ant {} {} {} {}\n", ant.x, ant.y, ant.heading as u8, ant.state));
+    }
+    for (time, ants) in space.ants_hash_map.iter() {
+        content.push_str(&format!("ants {}", time));
+        for ant in ants {
+            content.push_str(&format!(" {},{},{},{}", ant.x, ant.y, ant.heading as u8, ant.state));
+        }
+        content.push('\n');
+    }
+    if !space.values.is_empty() {
+        content.push_str(&format!("values{}\n", get_values_entry(&space.values)));
+    }
+    for (time, values) in space.values_hash_map.iter() {
+        content.push_str(&format!("state_values {}{}\n", time, get_values_entry(values)));
+    }
+    for (time, generation) in &space.fire_statistics.generations {
+        let mut burning: Vec<(&u32, &usize)> = generation.burning.iter().collect();
+        burning.sort();
+        content.push_str(&format!("fire_statistics {}", time));
+        for (fire, trees) in burning {
+            content.push_str(&format!(" {},{}", fire, trees));
+        }
+        content.push_str(" burnt_out");
+        for size in &generation.burnt_out {
+            content.push_str(&format!(" {}", size));
+        }
+        content.push('\n');
+    }
+    for (time, generation) in &space.avalanche_statistics.generations {
+        content.push_str(&format!("avalanche_statistics {} {}", time, generation.num_drops));
+        for (size, duration) in &generation.avalanches {
+            content.push_str(&format!(" {},{}", size, duration));
+        }
+        content.push('\n');
+    }
+    for (time, index) in space.segregation_statistics.get_indices() {
+        content.push_str(&format!("segregation_index {} {}\n", time, index));
+    }
+    for (time, moves) in &space.segregation_statistics.moves {
+        content.push_str(&format!("segregation_moves {} {}\n", time, moves));
+    }
+    fs::write(Path::new(file_str), content)?;
+    Ok(())
+}
+
+pub fn read_session(file_str: &str) -> Result<(Space, RenderOptions), Box<dyn Error>> {
+    let content = fs::read_to_string(Path::new(file_str))?;
+    let mut lines = content.lines().enumerate().filter(|(_, line)| !line.trim().is_empty());
+    match lines.next() {
+        Some((_, line)) if line.trim() == SESSION_HEADER => {}
+        _ => return Err("Not a session file!".into()),
+    }
+    let mut space: Option<Space> = None;
+    let mut view = RenderOptions::default();
+    let mut displayed_time: usize = 0;
+    let mut table_source = String::new();
+    for (line_index, line) in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        let error = || -> Box<dyn Error> { format!("Invalid session entry in line {}: '{}'", line_index + 1, line).into() };
+        match tokens[0] {
+            "version" => {
+                let version: u32 = parse_token(&tokens, 1).ok_or_else(error)?;
+                if version == 0 || version > SESSION_VERSION {
+                    return Err(format!("Unsupported session version {}!", version).into());
+                }
+            }
+            "size" => {
+                let x_dim: u16 = parse_token(&tokens, 1).filter(|&x_dim| x_dim > 0).ok_or_else(error)?;
+                let y_dim: u16 = parse_token(&tokens, 2).filter(|&y_dim| y_dim > 0).ok_or_else(error)?;
+                let mut new_space = Space::new(x_dim, y_dim);
+                new_space.states_hash_map.clear();
+                space = Some(new_space);
+            }
+            "displayed_time" => displayed_time = parse_token(&tokens, 1).ok_or_else(error)?,
+            "view" => parse_view_entry(&tokens, &mut view).ok_or_else(error)?,
+            "table" => {
+                table_source.push_str(line.trim_start()["table".len()..].trim_start());
+                table_source.push('\n');
+            }
+            key => {
+                let space = space.as_mut().ok_or("Session entries before the size!")?;
+                match key {
+                    "rule" => space.rule = Rule::parse(tokens.get(1).ok_or_else(error)?)?,
+                    "automaton" => {
+                        let name = tokens.get(1).ok_or_else(error)?;
+                        // tables come with the session instead of being looked up by name
+                        space.automaton = if name.starts_with("table:") && !table_source.is_empty() {
+                            let rule = TableRule::new(RuleTable::parse(&table_source)?);
+                            Some(Automaton::Table(rule)).filter(|automaton| automaton.name() == *name).ok_or_else(error)?
+                        } else {
+                            Automaton::from_name(name).ok_or_else(error)?
+                        };
+                    }
+                    "seed" => space.set_seed(parse_token(&tokens, 1).ok_or_else(error)?),
+                    "boundary" => space.boundary = tokens.get(1).and_then(|name| Boundary::from_name(name)).ok_or_else(error)?,
+                    "update" => {
+                        space.update = UpdateOptions {
+                            scheme: tokens.get(1).and_then(|name| UpdateScheme::from_name(name)).ok_or_else(error)?,
+                            probability: parse_token(&tokens, 2).filter(|probability| (0. ..=1.).contains(probability)).ok_or_else(error)?,
+                            rate: parse_token(&tokens, 3).filter(|rate| (0. ..=1.).contains(rate)).ok_or_else(error)?,
+                            block_size: parse_token(&tokens, 4).filter(|&block_size| block_size > 0).ok_or_else(error)?,
+                        };
+                    }
+                    "cell" => {
+                        let cell = space.get_cell_mut(parse_token(&tokens, 1).ok_or_else(error)?, parse_token(&tokens, 2).ok_or_else(error)?)?;
+                        cell.set_state(parse_token(&tokens, 3).ok_or_else(error)?);
+                        cell.set_generations_alive(parse_token(&tokens, 4).ok_or_else(error)?);
+                    }
+                    "state" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let saved_tuples = tokens[2..].iter().map(|token| parse_saved_tuple(token)).collect::<Option<Vec<_>>>().ok_or_else(error)?;
+                        if saved_tuples.iter().any(|saved_tuple| saved_tuple.0 >= space.x_dim() || saved_tuple.1 >= space.y_dim()) {
+                            return Err(error());
+                        }
+                        space.states_hash_map.insert(time, saved_tuples);
+                    }
+                    "ant" => {
+                        let ant = parse_ant(&tokens[1..], space).ok_or_else(error)?;
+                        space.ants.push(ant);
+                    }
+                    "ants" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let ants = tokens[2..].iter()
+                            .map(|token| parse_ant(&token.split(',').collect::<Vec<_>>(), space))
+                            .collect::<Option<Vec<_>>>()
+                            .ok_or_else(error)?;
+                        space.ants_hash_map.insert(time, ants);
+                    }
+                    "values" => space.values = parse_values(&tokens[1..], space).ok_or_else(error)?,
+                    "state_values" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let values = parse_values(&tokens[2..], space).ok_or_else(error)?;
+                        space.values_hash_map.insert(time, values);
+                    }
+                    "fire_statistics" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let generation = parse_fire_generation(&tokens[2..]).ok_or_else(error)?;
+                        space.fire_statistics.generations.insert(time, generation);
+                    }
+                    "avalanche_statistics" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let generation = parse_avalanche_generation(&tokens[2..]).ok_or_else(error)?;
+                        space.avalanche_statistics.generations.insert(time, generation);
+                    }
+                    "segregation_index" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        let index: f32 = parse_token(&tokens, 2).filter(|index| (0. ..=1.).contains(index)).ok_or_else(error)?;
+                        space.segregation_statistics.indices.insert(time, index);
+                    }
+                    "segregation_moves" => {
+                        let time: usize = parse_token(&tokens, 1).ok_or_else(error)?;
+                        space.segregation_statistics.moves.insert(time, parse_token(&tokens, 2).ok_or_else(error)?);
+                    }
+                    _ => return Err(error()),
+                }
+            }
+        }
+    }
+    let mut space = space.ok_or("Session without size!")?;
+    if space.states_hash_map.is_empty() {
+        space.save_state(displayed_time);
+    }
+    // the history is stepped through by time, so the saved times must be 0 to the number of states - 1
+    if (0..space.states_hash_map.len()).any(|time| !space.states_hash_map.contains_key(&time)) {
+        return Err("Saved states must be numbered from 0 without gaps!".into());
+    }
+    if !space.states_hash_map.contains_key(&displayed_time) {
+        return Err(format!("No state saved for the displayed time {}!", displayed_time).into());
+    }
+    // crafted states would break the automaton, e.g. aging states between the steps of life-like rules
+    if !space.flat().iter().all(|cell| space.automaton.is_valid_state(cell.get_state()))
+        || !space.states_hash_map.values().flatten().all(|saved_tuple| space.automaton.is_valid_state(saved_tuple.2)) {
+        return Err(format!("Invalid cell state for the automaton {}!", space.automaton.name()).into());
+    }
+    if !space.values.belong_to(&space.automaton) || !space.values_hash_map.values().all(|values| values.belong_to(&space.automaton)) {
+        return Err(format!("Cell values of another automaton than {}!", space.automaton.name()).into());
+    }
+    space.displayed_time = displayed_time;
+    Ok((space, view))
+}
+
+fn parse_view_entry(tokens: &[&str], view: &mut RenderOptions) -> Option<()> {
+    match *tokens.get(1)? {
+        "cell_size" => view.cell_size = parse_token(tokens, 2)?,
+        "color" => view.color = parse_color(tokens, 2)?,
+        "background" => view.background = parse_color(tokens, 2)?,
+        "grid_color" => view.grid_color = parse_color(tokens, 2)?,
+        "colormap" => view.colormap = Colormap::from_name(tokens.get(2)?)?,
+        "gradient" => {
+            let mut stops = Vec::new();
+            for i in (2..tokens.len()).step_by(4) {
+                stops.push((parse_token(tokens, i)?, parse_color(tokens, i + 1)?));
+            }
+            view.custom_gradient = Gradient::from_stops(stops)?;
+        }
+        "tracing" => view.tracing = parse_token::<u8>(tokens, 2)? == 1,
+        "color_by_age" => view.color_by_age = parse_token::<u8>(tokens, 2)? == 1,
+        "grid_lines" => view.grid_lines = parse_token::<u8>(tokens, 2)? == 1,
+        "channel" => view.channel = Channel::from_name(tokens.get(2)?)?,
+        _ => return None,
+    }
+    Some(())
+}
+
+fn parse_ant(values: &[&str], space: &Space) -> Option<Ant> {
+    if values.len() != 4 {
+        return None;
+    }
+    let x: u16 = parse_token(values, 0).filter(|&x| x < space.x_dim())?;
+    let y: u16 = parse_token(values, 1).filter(|&y| y < space.y_dim())?;
+    let heading = *Heading::ALL.get(parse_token::<usize>(values, 2)?)?;
+    Some(Ant { x, y, heading, state: parse_token(values, 3)? })
+}
+
+// Kind of the values followed by one token per position.
+fn get_values_entry(values: &CellValues) -> String {
+    let mut entry = String::new();
+    match values {
+        CellValues::Empty => {}
+        CellValues::Continuous(grid) => {
+            entry.push_str(" continuous");
+            for y in 0..grid.y_dim() {
+                for x in 0..grid.x_dim() {
+                    if grid.get_value(x, y) > 0. {
+                        entry.push_str(&format!(" {},{},{}", x, y, grid.get_value(x, y)));
+                    }
+                }
+            }
+        }
+        CellValues::Chemicals(u, v) => {
+            entry.push_str(" chemicals");
+            for y in 0..u.y_dim() {
+                for x in 0..u.x_dim() {
+                    if u.get_value(x, y) < 1. || v.get_value(x, y) > 0. {
+                        entry.push_str(&format!(" {},{},{},{}", x, y, u.get_value(x, y), v.get_value(x, y)));
+                    }
+                }
+            }
+        }
+        CellValues::Fires(fires) => entry.push_str(&format!(" fires{}", get_grid_entry(fires))),
+        CellValues::Heights(heights) => entry.push_str(&format!(" heights{}", get_grid_entry(heights))),
+        CellValues::Particles(particles) => entry.push_str(&format!(" particles{}", get_grid_entry(particles))),
+    }
+    entry
+}
+
+// One token per position with a value other than the default.
+fn get_grid_entry<T: Copy + Default + PartialEq + fmt::Display>(grid: &ValueGrid<T>) -> String {
+    let mut entry = String::new();
+    for y in 0..grid.y_dim() {
+        for x in 0..grid.x_dim() {
+            if grid.get_value(x, y) != T::default() {
+                entry.push_str(&format!(" {},{},{}", x, y, grid.get_value(x, y)));
+            }
+        }
+    }
+    entry
+}
+
+fn parse_values(tokens: &[&str], space: &Space) -> Option<CellValues> {
+    let entries: Vec<Vec<&str>> = tokens.get(1..)?.iter().map(|token| token.split(',').collect()).collect();
+    let get_position = |entry: &[&str]| -> Option<(u16, u16)> {
+        Some((parse_token(entry, 0).filter(|&x| x < space.x_dim())?, parse_token(entry, 1).filter(|&y| y < space.y_dim())?))
+    };
+    match *tokens.first()? {
+        "continuous" => {
+            let mut grid = ContinuousGrid::new(space.x_dim(), space.y_dim());
+            for entry in &entries {
+                let (x, y) = get_position(entry)?;
+                let value: f32 = parse_token(entry, 2).filter(|value: &f32| entry.len() == 3 && (0. ..=1.).contains(value))?;
+                grid.set_value(x, y, value);
+            }
+            Some(CellValues::Continuous(grid))
+        }
+        "chemicals" => {
+            let mut u = ContinuousGrid::filled(space.x_dim(), space.y_dim(), 1.);
+            let mut v = ContinuousGrid::new(space.x_dim(), space.y_dim());
+            for entry in &entries {
+                let (x, y) = get_position(entry)?;
+                let concentration = |index: usize| parse_token(entry, index).filter(|value: &f32| entry.len() == 4 && (0. ..=1.).contains(value));
+                u.set_value(x, y, concentration(2)?);
+                v.set_value(x, y, concentration(3)?);
+            }
+            Some(CellValues::Chemicals(u, v))
+        }
+        "fires" => Some(CellValues::Fires(parse_grid(&entries, space)?)),
+        "heights" => Some(CellValues::Heights(parse_grid(&entries, space)?)),
+        "particles" => Some(CellValues::Particles(parse_grid(&entries, space)?)),
+        _ => None,
+    }
+}
+
+fn parse_grid<T: Copy + Default + std::str::FromStr>(entries: &[Vec<&str>], space: &Space) -> Option<ValueGrid<T>> {
+    let mut grid = ValueGrid::new(space.x_dim(), space.y_dim());
+    for entry in entries {
+        if entry.len() != 3 {
+            return None;
+        }
+        let x: u16 = parse_token(entry, 0).filter(|&x| x < space.x_dim())?;
+        let y: u16 = parse_token(entry, 1).filter(|&y| y < space.y_dim())?;
+        grid.set_value(x, y, parse_token(entry, 2)?);
+    }
+    Some(grid)
+}
+
+fn parse_fire_generation(tokens: &[&str]) -> Option<FireGeneration> {
+    let separator = tokens.iter().position(|&token| token == "burnt_out")?;
+    let mut generation = FireGeneration::default();
+    for token in &tokens[..separator] {
+        let (fire, trees) = token.split_once(',')?;
+        generation.burning.insert(fire.parse().ok()?, trees.parse().ok()?);
+    }
+    generation.burnt_out = tokens[separator + 1..].iter().map(|token| token.parse().ok()).collect::<Option<_>>()?;
+    Some(generation)
+}
+
+fn parse_avalanche_generation(tokens: &[&str]) -> Option<AvalancheGeneration> {
+    let num_drops = parse_token(tokens, 0)?;
+    let avalanches = tokens[1..].iter()
+        .map(|token| token.split_once(',').and_then(|(size, duration)| Some((size.parse().ok()?, duration.parse().ok()?))))
+        .collect::<Option<_>>()?;
+    Some(AvalancheGeneration { avalanches, num_drops })
+}
+
+fn parse_token<T: std::str::FromStr>(tokens: &[&str], index: usize) -> Option<T> {
+    tokens.get(index)?.parse().ok()
+}
+
+fn parse_color(tokens: &[&str], index: usize) -> Option<(f32, f32, f32)> {
+    Some((parse_token(tokens, index)?, parse_token(tokens, index + 1)?, parse_token(tokens, index + 2)?))
+}
+
+// Version 1 tuples without state are alive cells.
+fn parse_saved_tuple(token: &str) -> Option<(u16, u16, u8, u32)> {
+    let values: Vec<&str> = token.split(',').collect();
+    match values.len() {
+        3 => Some((values[0].parse().ok()?, values[1].parse().ok()?, 255, values[2].parse().ok()?)),
+        4 => Some((values[0].parse().ok()?, values[1].parse().ok()?, values[2].parse().ok()?, values[3].parse().ok()?)),
+        _ => None,
+    }
+}
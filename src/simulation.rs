@@ -0,0 +1,297 @@
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use crate::animation::{self, AnimationOptions};
+use crate::render::RenderOptions;
+use crate::session;
+use crate::space::automaton::Automaton;
+use crate::space::forest::FireStatistics;
+use crate::space::rule::{Boundary, Rule};
+use crate::space::object::{Classifier, SpaceObject};
+use crate::space::sandpile::AvalancheStatistics;
+use crate::space::schelling::SegregationStatistics;
+use crate::space::soup::{Region, SoupOptions};
+use crate::space::update::UpdateOptions;
+use crate::space::Space;
+
+const IDLE_POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+// Edits and navigation requests sent from the renderer to the worker.
+pub enum Command {
+    ReviveCell(u16, u16),
+    KillCell(u16, u16),
+    PaintCell(u16, u16, u8), // state index of a multi-state automaton
+    AddAnt(u16, u16),
+    PaintChemical(u16, u16),
+    DropGrain(u16, u16),
+    PaintGas(u16, u16, u8), // state index of a lattice gas, an obstacle or a number of particles
+    RemoveAnts(u16, u16),
+    ReviveRandomCells(usize),
+    FillRandom(u64, SoupOptions),
+    KillAllCells,
+    CommitEdit,
+    ToggleRun,
+    StepForward,
+    StepBackward,
+    JumpToStart,
+    JumpToEnd,
+    Resize(u16, u16),
+    Replace(Box<Space>),
+    SetBoundary(Boundary),
+    SetRule(Rule),
+    SetUpdate(UpdateOptions),
+    SetAutomaton(Automaton),
+    ShowObjects(bool), // whether the worker classifies the objects of every published space
+    Characterize(Option<Region>, usize), // region and generations to run the pattern for
+    ClearCharacterization,
+    ExportAnimation(String, AnimationOptions),
+    SaveSession(String, RenderOptions),
+    Shutdown,
+}
+
+// Immutable view of the space published by the worker. The history is left out, only its length is kept.
+#[derive(Clone)]
+pub struct Snapshot {
+    pub space: Arc<Space>,
+    pub history_len: usize,
+    pub objects: Option<Arc<Vec<SpaceObject>>>, // classified objects of the space, None unless requested by ShowObjects
+    pub characterization: Option<Arc<Vec<String>>>, // report lines of the last characterization
+}
+
+impl Snapshot {
+    fn new(space: &Space, objects: Option<Arc<Vec<SpaceObject>>>, characterization: Option<Arc<Vec<String>>>) -> Snapshot {
+        Snapshot { space: Arc::new(space.snapshot()), history_len: space.states_hash_map.len(), objects, characterization }
+    }
+}
+
+pub struct Simulation {
+    commands: Sender<Command>,
+    mailbox: Arc<Mutex<Option<Snapshot>>>,
+    latest: Snapshot,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl Simulation {
+    pub fn new(space: Space) -> Simulation {
+        let (commands, receiver) = channel();
+        let mailbox = Arc::new(Mutex::new(None));
+        let latest = Snapshot::new(&space, None, None);
+        let worker_mailbox = Arc::clone(&mailbox);
+        let worker = thread::spawn(move || Worker::new(space, receiver, worker_mailbox).run());
+        Simulation { commands, mailbox, latest, worker: Some(worker) }
+    }
+
+    pub fn send(&self, command: Command) {
+        // The worker only stops after Shutdown, so a failed send can be ignored.
+        let _ = self.commands.send(command);
+    }
+
+    // Takes the newest published snapshot, if any, and returns the one to draw.
+    pub fn poll(&mut self) -> &Snapshot {
+        if let Some(snapshot) = self.mailbox.lock().unwrap().take() {
+            self.latest = snapshot;
+        }
+        &self.latest
+    }
+}
+
+impl Drop for Simulation {
+    fn drop(&mut self) {
+        self.send(Command::Shutdown);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+struct Worker {
+    space: Space,
+    commands: Receiver<Command>,
+    mailbox: Arc<Mutex<Option<Snapshot>>>,
+    running: bool,
+    time_step_start: usize,
+    classifier: Option<Classifier>, // set while the objects are shown
+    characterization: Option<Arc<Vec<String>>>,
+}
+
+impl Worker {
+    fn new(space: Space, commands: Receiver<Command>, mailbox: Arc<Mutex<Option<Snapshot>>>) -> Worker {
+        Worker { space, commands, mailbox, running: false, time_step_start: 0, classifier: None, characterization: None }
+    }
+
+    fn run(mut self) {
+        loop {
+            // While running, wait for the renderer to pick up the last generation before computing the next one.
+            let command = if self.running && self.mailbox.lock().unwrap().is_none() {
+                match self.commands.try_recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => {
+                        self.compute_next_generation();
+                        self.publish();
+                        continue;
+                    }
+                }
+            } else if self.running {
+                match self.commands.recv_timeout(IDLE_POLL_INTERVAL) {
+                    Ok(command) => Some(command),
+                    Err(RecvTimeoutError::Timeout) => None,
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            } else {
+                match self.commands.recv() {
+                    Ok(command) => Some(command),
+                    Err(_) => return,
+                }
+            };
+            if let Some(command) = command {
+                if !self.handle(command) {
+                    return;
+                }
+                while let Ok(command) = self.commands.try_recv() {
+                    if !self.handle(command) {
+                        return;
+                    }
+                }
+                self.publish();
+            }
+        }
+    }
+
+    fn handle(&mut self, command: Command) -> bool {
+        let time_step_current = self.space.displayed_time;
+        match command {
+            Command::ReviveCell(x, y) => {
+                if let Ok(cell) = self.space.get_cell_mut(x, y) {
+                    cell.revive();
+                }
+            }
+            Command::KillCell(x, y) => {
+                if let Ok(cell) = self.space.get_cell_mut(x, y) {
+                    cell.kill();
+                }
+            }
+            Command::PaintCell(x, y, state) => {
+                let num_states = self.space.automaton.get_num_states();
+                if let Ok(cell) = self.space.get_cell_mut(x, y) {
+                    cell.set_state_index(state, num_states);
+                }
+            }
+            Command::AddAnt(x, y) => self.space.add_ant(x, y),
+            Command::PaintChemical(x, y) => self.space.paint_chemical(x, y),
+            Command::DropGrain(x, y) => self.space.drop_grain(x, y),
+            Command::PaintGas(x, y, state) => {
+                if let Automaton::LatticeGas(model) = self.space.automaton {
+                    self.space.paint_lattice_gas(x, y, state, model);
+                }
+            }
+            Command::RemoveAnts(x, y) => self.space.remove_ants(x, y),
+            Command::ReviveRandomCells(num_cells) => self.space.revive_random_cells(num_cells),
+            Command::KillAllCells => {
+                self.space.kill_all_cells();
+                self.space.ants.clear();
+                self.space.fire_statistics = FireStatistics::default();
+                self.space.avalanche_statistics = AvalancheStatistics::default();
+                self.space.segregation_statistics = SegregationStatistics::default();
+            }
+            Command::FillRandom(seed, options) => {
+                self.space.set_seed(seed);
+                self.space.fill_random(&options);
+                self.space.fire_statistics = FireStatistics::default();
+                self.space.avalanche_statistics = AvalancheStatistics::default();
+                self.space.segregation_statistics = SegregationStatistics::default();
+                self.space.truncate_history(time_step_current + 1);
+                self.space.save_state(time_step_current + 1);
+            }
+            Command::CommitEdit => {
+                self.space.truncate_history(time_step_current + 1);
+                self.space.save_state(time_step_current + 1);
+            }
+            Command::ToggleRun => {
+                if !self.running {
+                    self.time_step_start = time_step_current;
+                }
+                self.running = !self.running;
+            }
+            Command::StepForward => {
+                self.running = false;
+                if self.space.states_hash_map.contains_key(&(time_step_current + 1)) {
+                    self.load_state(time_step_current + 1);
+                } else {
+                    self.compute_next_generation();
+                }
+            }
+            Command::StepBackward => {
+                self.running = false;
+                if time_step_current > 0 && !self.space.compute_previous_generation() {
+                    self.load_state(time_step_current - 1);
+                }
+            }
+            Command::JumpToStart => {
+                self.running = false;
+                if time_step_current > self.time_step_start {
+                    self.load_state(self.time_step_start);
+                }
+            }
+            Command::JumpToEnd => {
+                self.running = false;
+                let end_time = self.space.states_hash_map.len().saturating_sub(1);
+                if time_step_current < end_time {
+                    self.load_state(end_time);
+                }
+            }
+            Command::Resize(x_dim, y_dim) => {
+                if (x_dim, y_dim) != (self.space.x_dim(), self.space.y_dim()) {
+                    self.space = self.space.resized(x_dim, y_dim);
+                    self.time_step_start = 0;
+                }
+            }
+            Command::Replace(space) => {
+                self.space = *space;
+                self.time_step_start = 0;
+            }
+            Command::SetBoundary(boundary) => self.space.boundary = boundary,
+            Command::SetRule(rule) => self.space.rule = rule,
+            Command::SetUpdate(options) => self.space.update = options,
+            Command::SetAutomaton(automaton) => self.space.set_automaton(automaton),
+            Command::ShowObjects(show) => {
+                if show != self.classifier.is_some() {
+                    self.classifier = show.then(Classifier::new);
+                }
+            }
+            Command::Characterize(region, max_generations) => {
+                self.characterization = Some(Arc::new(self.space.characterize(region, max_generations).get_report()));
+            }
+            Command::ClearCharacterization => self.characterization = None,
+            Command::SaveSession(file_str, view) => {
+                if let Err(error) = session::write_session(&file_str, &self.space, &view) {
+                    eprintln!("Failed to save session: {}", error);
+                }
+            }
+            Command::ExportAnimation(file_str, options) => {
+                if let Err(error) = animation::export_animation(&file_str, &self.space, &options) {
+                    eprintln!("Failed to export animation: {}", error);
+                }
+            }
+            Command::Shutdown => return false,
+        }
+        true
+    }
+
+    fn compute_next_generation(&mut self) {
+        let time_step_current = self.space.displayed_time;
+        self.space.compute_next_generation();
+        self.space.save_state(time_step_current + 1);
+    }
+
+    fn load_state(&mut self, time: usize) {
+        if let Err(error) = self.space.load_state(time) {
+            eprintln!("Failed to load state: {}", error);
+        }
+    }
+
+    fn publish(&self) {
+        let objects = self.classifier.as_ref().map(|classifier| Arc::new(self.space.get_objects(classifier)));
+        *self.mailbox.lock().unwrap() = Some(Snapshot::new(&self.space, objects, self.characterization.clone()));
+    }
+}
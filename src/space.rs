@@ -1,19 +1,60 @@
+use crate::space::automaton::Automaton;
 use crate::space::cell::Cell;
+use crate::space::forest::FireStatistics;
+use crate::space::values::CellValues;
+use crate::space::rule::{Boundary, Rule};
+use crate::space::sandpile::AvalancheStatistics;
+use crate::space::schelling::SegregationStatistics;
+use crate::space::turmite::Ant;
+use crate::space::update::UpdateOptions;
 use std::{error::Error, fmt};
 use linked_hash_map::LinkedHashMap;
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::{thread_rng, Rng, SeedableRng};
 use rayon::prelude::*;
 
 
-pub(crate) mod cell;
+pub mod automaton;
+pub mod cell;
+pub mod characterize;
+pub mod continuous;
+pub mod cyclic;
+pub mod elementary;
+pub mod forest;
 pub mod io;
+pub mod lattice;
+pub mod margolus;
+pub mod multistate;
+pub mod object;
+pub mod reaction;
+pub mod rule;
+pub mod sandpile;
+pub mod schelling;
+pub mod soup;
+pub mod table;
+pub mod turmite;
+pub mod update;
+pub mod values;
 #[derive(Clone)]
 #[allow(dead_code)]
 pub struct Space {
     cells: Vec<Vec<Cell>>,
-    pub states_hash_map: LinkedHashMap<usize, Vec<(u16, u16)>>,
-    pub displayed_time: usize
+    pub states_hash_map: LinkedHashMap<usize, Vec<(u16, u16, u8, u32)>>,
+    pub ants: Vec<Ant>,
+    pub ants_hash_map: LinkedHashMap<usize, Vec<Ant>>, // ants of the saved states, times without ants are left out
+    pub values: CellValues,
+    pub values_hash_map: LinkedHashMap<usize, CellValues>, // values of the saved states, times without values are left out
+    pub displayed_time: usize,
+    pub rule: Rule,
+    pub automaton: Automaton,
+    pub boundary: Boundary,
+    pub update: UpdateOptions, // how life-like rules are applied
+    pub fire_statistics: FireStatistics, // sizes of the fires of forest fire automata
+    pub avalanche_statistics: AvalancheStatistics, // avalanches of sandpiles
+    pub segregation_statistics: SegregationStatistics, // segregation of Schelling models by generation
+    seed: u64,
+    rng: StdRng,
 }
 
 impl Space {
@@ -26,13 +67,31 @@ impl Space {
             }
             cells.push(column);
         }
-        let mut states_hashmap: LinkedHashMap<usize, Vec<(u16, u16)>> = LinkedHashMap::new();
+        let mut states_hashmap: LinkedHashMap<usize, Vec<(u16, u16, u8, u32)>> = LinkedHashMap::new();
         states_hashmap.insert(0, vec![]);
-        Space{cells, states_hash_map: states_hashmap, displayed_time: 0 }
+        let seed: u64 = thread_rng().gen();
+        Space{
+            cells,
+            states_hash_map: states_hashmap,
+            ants: vec![],
+            ants_hash_map: LinkedHashMap::new(),
+            values: CellValues::Empty,
+            values_hash_map: LinkedHashMap::new(),
+            displayed_time: 0,
+            rule: Rule::default(),
+            automaton: Automaton::default(),
+            boundary: Boundary::default(),
+            update: UpdateOptions::default(),
+            fire_statistics: FireStatistics::default(),
+            avalanche_statistics: AvalancheStatistics::default(),
+            segregation_statistics: SegregationStatistics::default(),
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
     }
 
     #[allow(dead_code)]
-    pub(crate) fn build_from_array(array: &Vec<Vec<u8>>) -> Space {
+    pub(crate) fn build_from_array(array: &[Vec<u8>]) -> Space {
         let x_dim = array[0].len() as u16;
         let y_dim = array.len() as u16;
         let mut space = Space::new(x_dim, y_dim);
@@ -46,25 +105,148 @@ impl Space {
         space
     }
 
+    pub fn resized(&self, x_dim: u16, y_dim: u16) -> Space {
+        let mut resized_space = Space::new(x_dim, y_dim);
+        resized_space.rule = self.rule;
+        resized_space.automaton = self.automaton.clone();
+        resized_space.boundary = self.boundary;
+        resized_space.update = self.update;
+        resized_space.fire_statistics = self.fire_statistics.clone();
+        resized_space.avalanche_statistics = self.avalanche_statistics.clone();
+        resized_space.segregation_statistics = self.segregation_statistics.clone();
+        resized_space.seed = self.seed;
+        resized_space.rng = self.rng.clone();
+        resized_space.ants = self.ants.iter().filter(|ant| ant.x < x_dim && ant.y < y_dim).copied().collect();
+        resized_space.values = self.values.resized(x_dim, y_dim);
+        for saved_cell in self.get_saved_cells() {
+            if let Ok(cell) = resized_space.get_cell_mut(saved_cell.x, saved_cell.y) {
+                cell.set_state(saved_cell.get_state());
+                cell.set_generations_alive(saved_cell.get_generations_alive());
+            }
+        }
+        resized_space
+    }
+
+    // Switches the automaton, the cells and saved states it can not have are mapped to its closest ones.
+    // Values the automaton does not compute with are dropped, it starts from the cell states instead.
+    pub fn set_automaton(&mut self, automaton: Automaton) {
+        if automaton == self.automaton {
+            return;
+        }
+        self.automaton = automaton.clone();
+        if !self.values.belong_to(&automaton) {
+            self.values = CellValues::Empty;
+        }
+        let times_to_clear: Vec<usize> = self.values_hash_map.iter()
+            .filter(|(_, values)| !values.belong_to(&automaton))
+            .map(|(time, _)| *time)
+            .collect();
+        for time in times_to_clear {
+            self.values_hash_map.remove(&time);
+        }
+        for cell in self.flat_mut() {
+            let state = automaton.get_valid_state(cell.get_state());
+            if state != cell.get_state() {
+                cell.set_state(state);
+                cell.set_generations_alive(0);
+            }
+        }
+        for (_, saved_tuples) in self.states_hash_map.iter_mut() {
+            for saved_tuple in saved_tuples.iter_mut() {
+                let state = automaton.get_valid_state(saved_tuple.2);
+                if state != saved_tuple.2 {
+                    (saved_tuple.2, saved_tuple.3) = (state, 0);
+                }
+            }
+            saved_tuples.retain(|saved_tuple| saved_tuple.2 > 0);
+        }
+        // lattice gases show the particles they keep, cells of other automata have none
+        if let Automaton::LatticeGas(model) = automaton {
+            self.show_particles(model);
+        }
+    }
+
+    pub fn snapshot(&self) -> Space {
+        Space {
+            cells: self.cells.clone(),
+            states_hash_map: LinkedHashMap::new(),
+            ants: self.ants.clone(),
+            ants_hash_map: LinkedHashMap::new(),
+            values: self.values.clone(),
+            values_hash_map: LinkedHashMap::new(),
+            displayed_time: self.displayed_time,
+            rule: self.rule,
+            automaton: self.automaton.clone(),
+            boundary: self.boundary,
+            update: self.update,
+            fire_statistics: self.fire_statistics.clone(),
+            avalanche_statistics: self.avalanche_statistics.clone(),
+            segregation_statistics: self.segregation_statistics.clone(),
+            seed: self.seed,
+            rng: self.rng.clone(),
+        }
+    }
+
+    pub fn truncate_history(&mut self, time: usize) {
+        let mut time_key_to_remove = time;
+        while self.states_hash_map.contains_key(&time_key_to_remove) {
+            self.states_hash_map.remove(&time_key_to_remove);
+            self.ants_hash_map.remove(&time_key_to_remove);
+            self.values_hash_map.remove(&time_key_to_remove);
+            time_key_to_remove += 1;
+        }
+    }
+
     pub fn save_state(&mut self, time: usize) {
-        let alive_cells = self.get_alive_cells();
-        let mut indices: Vec<(u16, u16)> = Vec::new();
-        for alive_cell in &alive_cells {
-            indices.push((alive_cell.x, alive_cell.y));
+        let saved_cells = self.get_saved_cells();
+        let mut indices: Vec<(u16, u16, u8, u32)> = Vec::new();
+        for saved_cell in &saved_cells {
+            indices.push((saved_cell.x, saved_cell.y, saved_cell.get_state(), saved_cell.get_generations_alive()));
         }
         self.states_hash_map.insert(time, indices);
+        self.avalanche_statistics.save(time);
+        if self.ants.is_empty() {
+            self.ants_hash_map.remove(&time);
+        } else {
+            self.ants_hash_map.insert(time, self.ants.clone());
+        }
+        if self.values.is_empty() {
+            self.values_hash_map.remove(&time);
+        } else {
+            self.values_hash_map.insert(time, self.values.clone());
+        }
         self.displayed_time = time;
     }
 
-    pub fn load_state(&mut self, time: usize) {
-        let cloned_space = self.clone();
-        self.kill_all_cells();
-        let alive_tuples = &cloned_space.states_hash_map[&time];
-        for alive_tuple in alive_tuples {
-            self.revive_cell(alive_tuple.0, alive_tuple.1);
-        }
+    pub fn load_state(&mut self, time: usize) -> Result<(), OutOfBoundsError> {
+        let state = self.state_at(time).ok_or_else(|| OutOfBoundsError::new(&format!("No state saved for time {}!", time)))?;
+        self.cells = state.cells;
+        self.ants = state.ants;
+        self.values = state.values;
         self.displayed_time = time;
+        Ok(())
+    }
 
+    // Space holding the saved state of the given time, without history.
+    pub fn state_at(&self, time: usize) -> Option<Space> {
+        let saved_tuples = self.states_hash_map.get(&time)?;
+        let mut state = Space::new(self.x_dim(), self.y_dim());
+        state.rule = self.rule;
+        state.automaton = self.automaton.clone();
+        state.boundary = self.boundary;
+        state.update = self.update;
+        state.seed = self.seed;
+        state.rng = self.rng.clone();
+        state.ants = self.ants_hash_map.get(&time).cloned().unwrap_or_default();
+        state.values = self.values_hash_map.get(&time).cloned().unwrap_or_default();
+        for saved_tuple in saved_tuples {
+            let cell = state.get_cell_mut(saved_tuple.0, saved_tuple.1).unwrap();
+            cell.set_state(saved_tuple.2);
+            cell.set_generations_alive(saved_tuple.3);
+        }
+        state.states_hash_map.clear();
+        state.displayed_time = time;
+        Some(state)
     }
 
     pub(crate) fn flat_mut(&mut self) -> Vec<&mut Cell> {
@@ -88,6 +270,11 @@ impl Space {
         cell.revive();
     }
 
+    pub fn let_cell_survive(&mut self, x: u16, y: u16) {
+        let cell: &mut Cell = self.get_cell_mut(x, y).unwrap();
+        cell.survive();
+    }
+
     pub fn let_cell_age(&mut self, x: u16, y: u16) {
         let cell: &mut Cell = self.get_cell_mut(x, y).unwrap();
         cell.age();
@@ -98,6 +285,7 @@ impl Space {
         for cell in alive_cells {
             cell.kill();
         }
+        self.values = CellValues::Empty;
     }
 
     #[allow(dead_code)]
@@ -105,15 +293,23 @@ impl Space {
         self.get_cell(x, y).unwrap().is_alive()
     }
 
+    pub fn get_seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Restarts the random number generator, so the following random fills can be reproduced.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = StdRng::seed_from_u64(seed);
+    }
+
     pub fn revive_random_cells(&mut self, num_cells: usize) {
-        let mut rng = thread_rng();
+        let mut indices: Vec<usize> = (0..self.x_dim() as usize * self.y_dim() as usize).collect();
+        indices.shuffle(&mut self.rng);
         let mut flat_cells = self.flat_mut();
-        let mut indices: Vec<usize> = (0..flat_cells.len()).collect();
-        indices.shuffle(&mut rng);
         let num_cells = num_cells.min(flat_cells.len());
-        for i in 0..num_cells {
-            let index = indices[i];
-            flat_cells.get_mut(index).unwrap().revive();
+        for index in indices.iter().take(num_cells) {
+            flat_cells.get_mut(*index).unwrap().revive();
         }
     }
 
@@ -134,17 +330,23 @@ impl Space {
     }
 
 
+    pub fn get_generations_alive(&self, x: u16, y: u16) -> Result<u32, OutOfBoundsError> {
+        Ok(self.get_cell(x, y)?.get_generations_alive())
+    }
+
+    // Generation in which the cell was born, None if it is not alive or the automaton does not count generations.
+    pub fn get_birth_time(&self, x: u16, y: u16) -> Result<Option<usize>, OutOfBoundsError> {
+        let cell = self.get_cell(x, y)?;
+        if cell.is_alive() && !self.automaton.has_cell_values() {
+            Ok(Some(self.displayed_time.saturating_sub(cell.get_generations_alive() as usize)))
+        } else {
+            Ok(None)
+        }
+    }
+
     #[allow(dead_code)]
     pub fn get_num_alive_cells(&self) -> usize {
-        let mut flat_cells = self.flat();
-        let mut num_alive_cells = 0;
-        while !flat_cells.is_empty() {
-            let cell = flat_cells.pop().unwrap();
-            if cell.is_alive() {
-                num_alive_cells += 1;
-            }
-        }
-        num_alive_cells
+        self.flat().iter().filter(|cell| cell.is_alive()).count()
     }
 
     pub fn get_alive_cells(&self) -> Vec<&Cell> {
@@ -158,6 +360,15 @@ impl Space {
         alive_cells
     }
 
+    // Cells kept in the history: alive cells, or all cells with a state if the automaton has no traces.
+    pub fn get_saved_cells(&self) -> Vec<&Cell> {
+        if self.automaton.has_traces() {
+            self.get_alive_cells()
+        } else {
+            self.get_cells_with_energy()
+        }
+    }
+
     pub fn get_cells_with_energy(&self) -> Vec<&Cell> {
         let flat_cells = self.flat();
         let mut cells_with_energy = Vec::new();
@@ -199,73 +410,77 @@ impl Space {
     }
 
     pub fn get_neighbors_vec(&self, cell: &Cell) -> Vec<&Cell> {
-        let mut neighbors_vec = Vec::new();
-        let cell_0_1 = self.get_cell(cell.x, cell.y + 1);
-        if cell_0_1.is_ok() {
-            neighbors_vec.push(cell_0_1.unwrap());
-        }
-        let cell_1_1 = self.get_cell(cell.x + 1, cell.y + 1);
-        if cell_1_1.is_ok() {
-            neighbors_vec.push(cell_1_1.unwrap());
-        }
-        let cell_1_0 = self.get_cell(cell.x + 1, cell.y);
-        if cell_1_0.is_ok() {
-            neighbors_vec.push(cell_1_0.unwrap());
-        }
-        if cell.y > 0 {
-            let cell_1_m1 = self.get_cell(cell.x + 1, cell.y - 1);
-            if cell_1_m1.is_ok() {
-                neighbors_vec.push(cell_1_m1.unwrap());
-            }
-            let cell_0_m1 = self.get_cell(cell.x, cell.y - 1);
-            if cell_0_m1.is_ok() {
-                neighbors_vec.push(cell_0_m1.unwrap());
-            }
-            if cell.x > 0 {
-                let cell_m1_m1 = self.get_cell(cell.x - 1, cell.y - 1);
-                if cell_m1_m1.is_ok() {
-                    neighbors_vec.push(cell_m1_m1.unwrap());
-                }
-            }
-        }
-        if cell.x > 0 {
-            let cell_m1_0 = self.get_cell(cell.x - 1, cell.y);
-            if cell_m1_0.is_ok() {
-                neighbors_vec.push(cell_m1_0.unwrap());
-            }
-            let cell_m1_1 = self.get_cell(cell.x - 1, cell.y + 1);
-            if cell_m1_1.is_ok() {
-                neighbors_vec.push(cell_m1_1.unwrap());
+        let mut neighbors_vec = Vec::with_capacity(8);
+        for (dx, dy) in NEIGHBOR_OFFSETS {
+            if let Some((x, y)) = self.get_neighbor_position(cell.x, cell.y, dx, dy) {
+                neighbors_vec.push(&self.cells[x as usize][y as usize]);
             }
         }
         neighbors_vec
     }
 
+    // Position of the cell at the given offset, wrapped around the edges if the boundary is toroidal.
+    pub fn get_neighbor_position(&self, x: u16, y: u16, dx: i32, dy: i32) -> Option<(u16, u16)> {
+        let x_dim = self.x_dim() as i32;
+        let y_dim = self.y_dim() as i32;
+        let mut neighbor_x = x as i32 + dx;
+        let mut neighbor_y = y as i32 + dy;
+        if self.boundary == Boundary::Toroidal {
+            neighbor_x = neighbor_x.rem_euclid(x_dim);
+            neighbor_y = neighbor_y.rem_euclid(y_dim);
+        }
+        if neighbor_x < 0 || neighbor_y < 0 || neighbor_x >= x_dim || neighbor_y >= y_dim {
+            return None;
+        }
+        Some((neighbor_x as u16, neighbor_y as u16))
+    }
+
+    pub fn compute_next_generation(&mut self) {
+        match self.automaton.clone() {
+            Automaton::LifeLike => self.compute_life_like_generation(),
+            Automaton::Linear(rule) => self.compute_linear_generation(&rule),
+            Automaton::MultiState(rule) => self.compute_multi_state_generation(&rule),
+            Automaton::Turmite(rule) => self.compute_turmite_generation(&rule),
+            Automaton::Continuous(rule) => self.compute_continuous_generation(&rule),
+            Automaton::ReactionDiffusion(rule) => self.compute_reaction_generation(&rule),
+            Automaton::Cyclic(rule) => self.compute_cyclic_generation(&rule),
+            Automaton::Margolus(rule) => self.compute_block_generation(&rule),
+            Automaton::Table(rule) => self.compute_table_generation(&rule),
+            Automaton::ForestFire(rule) => self.compute_forest_fire_generation(&rule),
+            Automaton::Sandpile(rule) => self.compute_sandpile_generation(&rule),
+            Automaton::LatticeGas(model) => self.compute_lattice_gas_generation(&model),
+            Automaton::Schelling(rule) => self.compute_schelling_generation(&rule),
+        }
+    }
+
     #[allow(dead_code)]
     pub fn compute_conways_game_of_life_single_threaded(&mut self) {
         let current_state = self.clone();
         for x in 0..self.x_dim() {
             for y in 0..self.y_dim() {
                 let current_cell = current_state.get_cell(x, y).unwrap();
-                let num_alive_neighbors = Self::count_alive_neighbours(&current_state, current_cell);
-                if current_cell.is_alive() {
-                    if num_alive_neighbors > 3 || num_alive_neighbors < 2 {
-                        self.let_cell_age(x, y);
-                    }
-                } else if !current_cell.is_alive() {
-                    if num_alive_neighbors == 3 {
-                        self.revive_cell(x, y);
-                    } else {
-                        if current_cell.get_state() > 0 && current_cell.get_state() < 255 {
-                            self.let_cell_age(x, y);
-                        }
-                    }
+                match Self::get_action_by_conways_game_of_life_rules(&current_state, current_cell) {
+                    Some(CellAction::Age) => self.let_cell_age(x, y),
+                    Some(CellAction::Survive) => self.let_cell_survive(x, y),
+                    Some(CellAction::Revive) => self.revive_cell(x, y),
+                    None => {}
                 }
             }
         }
     }
 
-    fn count_alive_neighbours(space: &Space, cell: &Cell) -> usize {
+    pub fn get_cell_fate(&self, x: u16, y: u16) -> Result<CellFate, OutOfBoundsError> {
+        let cell = self.get_cell(x, y)?;
+        let fate = match Self::get_action_by_conways_game_of_life_rules(self, cell) {
+            Some(CellAction::Revive) => CellFate::Born,
+            Some(CellAction::Survive) => CellFate::Survives,
+            Some(CellAction::Age) if cell.is_alive() => CellFate::Dies,
+            _ => CellFate::StaysDead,
+        };
+        Ok(fate)
+    }
+
+    pub fn count_alive_neighbours(space: &Space, cell: &Cell) -> usize {
         let mut num_alive_neighbors: usize = 0;
         let neighbors = space.get_neighbors_vec(cell);
         for neighbor in neighbors {
@@ -276,26 +491,44 @@ impl Space {
         num_alive_neighbors
     }
 
+    // Alive neighbours as a bitmask, bit i is set if the neighbour at NEIGHBOR_OFFSETS[i] is alive.
+    pub fn get_neighborhood_mask(space: &Space, cell: &Cell) -> u8 {
+        let mut neighborhood: u8 = 0;
+        for (bit, (dx, dy)) in NEIGHBOR_OFFSETS.into_iter().enumerate() {
+            if let Some((x, y)) = space.get_neighbor_position(cell.x, cell.y, dx, dy) {
+                if space.cells[x as usize][y as usize].is_alive() {
+                    neighborhood |= 1 << bit;
+                }
+            }
+        }
+        neighborhood
+    }
+
+    fn get_action_by_conways_game_of_life_rules(state_current: &Space, cell: &Cell) -> Option<CellAction> {
+        let neighborhood = Self::get_neighborhood_mask(state_current, cell);
+        if cell.is_alive() {
+            if !state_current.rule.survives_with(neighborhood) {
+                Some(CellAction::Age)
+            } else {
+                Some(CellAction::Survive)
+            }
+        } else if state_current.rule.is_born_with(neighborhood) {
+            Some(CellAction::Revive)
+        } else if cell.get_state() > 0 && cell.get_state() < 255 {
+            Some(CellAction::Age)
+        } else {
+            None
+        }
+    }
+
     fn get_changes_by_conways_game_of_life_rules_par(cells: Vec<& Cell>, state_current: &Space) -> Vec<(u16, u16, CellAction)> {
         let changes: Vec<(u16, u16, CellAction)> = cells
             .par_iter()
             .filter_map(
                 | cell: &&Cell | {
                     let cell_current = state_current.get_cell(cell.x, cell.y).unwrap();
-                    let num_alive_neighbors = Self::count_alive_neighbours(&state_current, cell_current);
-                    if cell_current.is_alive() {
-                        if num_alive_neighbors > 3 || num_alive_neighbors < 2 {
-                            Some((cell.x, cell.y, CellAction::Age))
-                        } else {
-                            None
-                        }
-                    } else if num_alive_neighbors == 3 {
-                        Some((cell.x, cell.y, CellAction::Revive))
-                    } else if cell_current.get_state() > 0 && cell_current.get_state() < 255 {
-                        Some((cell.x, cell.y, CellAction::Age))
-                    } else {
-                        None
-                    }
+                    Self::get_action_by_conways_game_of_life_rules(state_current, cell_current)
+                        .map(|action| (cell.x, cell.y, action))
                 }
             )
             .collect();
@@ -309,17 +542,41 @@ impl Space {
         for (x, y, action) in changes {
             match action {
                 CellAction::Age => self.let_cell_age(x, y),
+                CellAction::Survive => self.let_cell_survive(x, y),
                 CellAction::Revive => self.revive_cell(x, y),
             }
         }
     }
 }
 
+// Moore neighbourhood going around the cell, in the order returned by get_neighbors_vec.
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
 enum CellAction {
     Age,
+    Survive,
     Revive,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CellFate {
+    Born,
+    Survives,
+    Dies,
+    StaysDead,
+}
+
+impl CellFate {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CellFate::Born => "born",
+            CellFate::Survives => "survives",
+            CellFate::Dies => "dies",
+            CellFate::StaysDead => "stays dead",
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct OutOfBoundsError {
     pub message: String,
@@ -0,0 +1,198 @@
+use super::cell::Cell;
+use super::continuous::{self, ContinuousRule};
+use super::cyclic::{self, CyclicRule};
+use super::elementary::LinearRule;
+use super::forest::{self, ForestFireRule};
+use super::lattice::GasModel;
+use super::margolus::{self, BlockRule};
+use super::multistate::NamedRule;
+use super::reaction::{self, GrayScottRule};
+use super::sandpile::{self, SandpileRule};
+use super::schelling::{self, SchellingRule};
+use super::table::TableRule;
+use super::turmite::{self, TurmiteRule};
+
+// Kind of automaton computed by a space.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum Automaton {
+    #[default]
+    LifeLike, // alive or aging cells, stepped by the rule of the space
+    Linear(LinearRule), // space-time diagram of a one dimensional automaton
+    MultiState(NamedRule),
+    Turmite(TurmiteRule), // ants of the space walking over colored cells
+    Continuous(ContinuousRule), // values between 0 and 1 kept in a grid of the space, the states show them rounded
+    ReactionDiffusion(GrayScottRule), // chemicals u and v kept in two grids of the space, the states show v
+    Cyclic(CyclicRule),
+    Margolus(BlockRule), // 2x2 blocks of alive cells, reversible rules step backwards by computation
+    Table(TableRule), // rule table loaded from a Golly .rule file
+    ForestFire(ForestFireRule), // fires keep their number in the cell values of the burning cells
+    Sandpile(SandpileRule), // grains kept in the cell values, the state shows the height
+    LatticeGas(GasModel), // particle directions kept in the cell values, the state shows their number
+    Schelling(SchellingRule), // agents of two populations moving to empty cells
+}
+
+impl Automaton {
+    pub fn name(&self) -> String {
+        match self {
+            Automaton::LifeLike => "life".to_string(),
+            Automaton::Linear(rule) => rule.to_string(),
+            Automaton::MultiState(rule) => rule.name().to_string(),
+            Automaton::Turmite(rule) => format!("turmite:{}", rule),
+            Automaton::Continuous(rule) => rule.to_string(),
+            Automaton::ReactionDiffusion(rule) => rule.to_string(),
+            Automaton::Cyclic(rule) => rule.to_string(),
+            Automaton::Margolus(rule) => rule.to_string(),
+            Automaton::Table(rule) => rule.to_string(),
+            Automaton::ForestFire(rule) => rule.to_string(),
+            Automaton::Sandpile(rule) => rule.to_string(),
+            Automaton::LatticeGas(model) => model.name().to_string(),
+            Automaton::Schelling(rule) => rule.to_string(),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Automaton> {
+        match name {
+            "life" => Some(Automaton::LifeLike),
+            "hpp" | "fhp" => GasModel::from_name(name).map(Automaton::LatticeGas),
+            _ if name.starts_with("lenia:") || name.starts_with("smoothlife:") => ContinuousRule::parse(name).ok().map(Automaton::Continuous),
+            _ if name.starts_with("grayscott:") => GrayScottRule::parse(name).ok().map(Automaton::ReactionDiffusion),
+            _ if name.starts_with("cyclic:") || name.starts_with("rps:") || name.starts_with("gh:") => CyclicRule::parse(name).ok().map(Automaton::Cyclic),
+            _ if name.starts_with("forestfire:") => ForestFireRule::parse(name).ok().map(Automaton::ForestFire),
+            _ if name.starts_with("schelling:") => SchellingRule::parse(name).ok().map(Automaton::Schelling),
+            _ if name.starts_with("sandpile:") => SandpileRule::parse(name).ok().map(Automaton::Sandpile),
+            _ if name.starts_with("margolus:") => BlockRule::parse(name).ok().map(Automaton::Margolus),
+            _ if name.starts_with("turmite:") => TurmiteRule::parse(&name["turmite:".len()..]).ok().map(Automaton::Turmite),
+            _ => NamedRule::from_name(name).map(Automaton::MultiState).or(LinearRule::parse(name).ok().map(Automaton::Linear)),
+        }
+    }
+
+    pub fn get_num_states(&self) -> u8 {
+        match self {
+            Automaton::LifeLike | Automaton::Margolus(_) => 2,
+            Automaton::ForestFire(_) | Automaton::Schelling(_) => 3,
+            Automaton::Sandpile(_) => 5,
+            Automaton::LatticeGas(model) => model.get_num_states(),
+            Automaton::Linear(rule) => rule.get_num_colors(),
+            Automaton::MultiState(rule) => rule.get_num_states(),
+            Automaton::Turmite(rule) => rule.get_num_colors(),
+            Automaton::Cyclic(rule) => rule.num_states,
+            Automaton::Table(rule) => rule.get_table().get_num_states(),
+            Automaton::Continuous(_) | Automaton::ReactionDiffusion(_) => 255, // the value is the whole cell state
+        }
+    }
+
+    // Cycles from life through the continuous presets, then the reaction-diffusion presets.
+    pub fn next_continuous(&self) -> Automaton {
+        let automata: Vec<Automaton> = continuous::PRESETS.iter()
+            .map(|preset| Automaton::Continuous(ContinuousRule::parse(preset.1).unwrap()))
+            .chain(reaction::PRESETS.iter().map(|preset| Automaton::ReactionDiffusion(GrayScottRule::parse(preset.1).unwrap())))
+            .collect();
+        let index = automata.iter().position(|automaton| automaton == self).map_or(0, |index| index + 1);
+        automata.get(index).cloned().unwrap_or(Automaton::LifeLike)
+    }
+
+    // Cycles from life through the block rule presets.
+    pub fn next_margolus(&self) -> Automaton {
+        let automata: Vec<Automaton> = margolus::PRESETS.iter().map(|name| Automaton::Margolus(BlockRule::from_name(name).unwrap())).collect();
+        let index = automata.iter().position(|automaton| automaton == self).map_or(0, |index| index + 1);
+        automata.get(index).cloned().unwrap_or(Automaton::LifeLike)
+    }
+
+    // Cycles from life through the turmite presets.
+    pub fn next_turmite(&self) -> Automaton {
+        let rules: Vec<TurmiteRule> = turmite::PRESETS.iter().map(|preset| TurmiteRule::parse(preset.1).unwrap()).collect();
+        let index = match self {
+            Automaton::Turmite(rule) => rules.iter().position(|preset_rule| preset_rule == rule).map_or(0, |index| index + 1),
+            _ => 0,
+        };
+        rules.get(index).map_or(Automaton::LifeLike, |rule| Automaton::Turmite(*rule))
+    }
+
+    // Cycles from life through the named multi-state rules, then the cyclic, forest fire and sandpile presets,
+    // the lattice gases and the Schelling presets.
+    pub fn next_multi_state(&self) -> Automaton {
+        let automata: Vec<Automaton> = NamedRule::ALL.iter()
+            .map(|rule| Automaton::MultiState(*rule))
+            .chain(cyclic::PRESETS.iter().map(|preset| Automaton::Cyclic(CyclicRule::parse(preset.1).unwrap())))
+            .chain(forest::PRESETS.iter().map(|preset| Automaton::ForestFire(ForestFireRule::parse(preset.1).unwrap())))
+            .chain(sandpile::PRESETS.iter().map(|preset| Automaton::Sandpile(SandpileRule::parse(preset.1).unwrap())))
+            .chain(GasModel::ALL.iter().map(|model| Automaton::LatticeGas(*model)))
+            .chain(schelling::PRESETS.iter().map(|preset| Automaton::Schelling(SchellingRule::parse(preset.1).unwrap())))
+            .collect();
+        let index = automata.iter().position(|automaton| automaton == self).map_or(0, |index| index + 1);
+        automata.get(index).cloned().unwrap_or(Automaton::LifeLike)
+    }
+
+    // Colors and names of the states if they are drawn from a palette instead of the colormap.
+    pub fn get_palette(&self) -> Option<&[(f32, f32, f32)]> {
+        match self {
+            Automaton::MultiState(rule) => Some(rule.get_palette()),
+            Automaton::Cyclic(rule) => Some(rule.get_palette()),
+            Automaton::Table(rule) => Some(rule.get_table().get_colors()),
+            Automaton::ForestFire(rule) => Some(rule.get_palette()),
+            Automaton::Sandpile(rule) => Some(rule.get_palette()),
+            Automaton::LatticeGas(model) => Some(model.get_palette()),
+            Automaton::Schelling(rule) => Some(rule.get_palette()),
+            _ => None,
+        }
+    }
+
+    pub fn get_state_names(&self) -> Option<&'static [&'static str]> {
+        match self {
+            Automaton::MultiState(rule) => Some(rule.get_state_names()),
+            Automaton::Cyclic(rule) => Some(rule.get_state_names()),
+            Automaton::Table(rule) => Some(rule.get_state_names()),
+            Automaton::ForestFire(rule) => Some(rule.get_state_names()),
+            Automaton::Sandpile(rule) => Some(rule.get_state_names()),
+            Automaton::LatticeGas(model) => Some(model.get_state_names()),
+            Automaton::Schelling(rule) => Some(rule.get_state_names()),
+            _ => None,
+        }
+    }
+
+    // Whether a cell state read from a file can occur in the automaton.
+    pub fn is_valid_state(&self, state: u8) -> bool {
+        match self {
+            Automaton::LifeLike => Cell::is_aging_state(state),
+            Automaton::Continuous(_) | Automaton::ReactionDiffusion(_) => true,
+            _ => {
+                let num_states = self.get_num_states();
+                get_cell_state(get_state_index(state, num_states), num_states) == state
+            }
+        }
+    }
+
+    // Closest state the automaton can have, for cells of another automaton.
+    pub fn get_valid_state(&self, state: u8) -> u8 {
+        match self {
+            Automaton::LifeLike => Cell::get_closest_aging_state(state),
+            Automaton::Continuous(_) | Automaton::ReactionDiffusion(_) => state,
+            _ => {
+                let num_states = self.get_num_states();
+                get_cell_state(get_state_index(state, num_states), num_states)
+            }
+        }
+    }
+
+    // Automata keeping their own values next to the cell states, their cells do not count generations.
+    pub fn has_cell_values(&self) -> bool {
+        matches!(
+            self,
+            Automaton::Continuous(_) | Automaton::ReactionDiffusion(_) | Automaton::ForestFire(_) | Automaton::Sandpile(_) | Automaton::LatticeGas(_)
+        )
+    }
+
+    // Only life-like cells leave traces, the states of other automata are all saved and drawn.
+    pub fn has_traces(&self) -> bool {
+        matches!(self, Automaton::LifeLike)
+    }
+}
+
+// States of multi-state automata are spread over the cell state, so the highest state is alive.
+pub fn get_cell_state(index: u8, num_states: u8) -> u8 {
+    (index.min(num_states - 1) as u32 * 255 / (num_states as u32 - 1)) as u8
+}
+
+pub fn get_state_index(cell_state: u8, num_states: u8) -> u8 {
+    ((cell_state as u32 * (num_states as u32 - 1) + 127) / 255) as u8
+}
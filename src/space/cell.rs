@@ -1,3 +1,5 @@
+use super::automaton::{get_cell_state, get_state_index};
+
 const ALIVE_STATE: u8 = 255;
 const AGING_STEP: u8 = 17;
 
@@ -7,6 +9,7 @@ pub struct Cell {
     pub x: u16,
     pub y: u16,
     state: u8,
+    generations_alive: u32,
 }
 
 #[allow(dead_code)]
@@ -14,7 +17,8 @@ impl Cell {
     pub fn new(x: u16, y: u16, state: u8) -> Cell {
         Cell {
             x, y,
-            state
+            state,
+            generations_alive: 0,
         }
     }
 
@@ -26,17 +30,50 @@ impl Cell {
         self.state = state;
     }
 
+    // State of a multi-state automaton with num_states states, the highest one is alive.
+    pub fn get_state_index(&self, num_states: u8) -> u8 {
+        get_state_index(self.state, num_states)
+    }
+
+    pub fn set_state_index(&mut self, index: u8, num_states: u8) {
+        if index == 0 {
+            self.kill();
+        } else {
+            self.state = get_cell_state(index, num_states);
+        }
+    }
+
+    pub fn get_generations_alive(&self) -> u32 {
+        self.generations_alive
+    }
+
+    pub fn set_generations_alive(&mut self, generations_alive: u32) {
+        self.generations_alive = generations_alive;
+    }
+
     pub fn revive(&mut self) {
+        if !self.is_alive() {
+            self.generations_alive = 0;
+        }
         self.state = ALIVE_STATE;
     }
 
     pub fn kill(&mut self) {
         self.state = 0;
+        self.generations_alive = 0;
+    }
+
+    pub fn survive(&mut self) {
+        if self.is_alive() {
+            self.generations_alive = self.generations_alive.saturating_add(1);
+        }
     }
 
+    // Steps down to the next state of the aging grid, so states of other automata fade out as well.
     pub fn age(&mut self) {
         if self.get_state() > 0 {
-            self.set_state(self.get_state() - AGING_STEP);
+            self.set_state((self.get_state() - 1) / AGING_STEP * AGING_STEP);
+            self.generations_alive = 0;
         }
     }
 
@@ -44,4 +81,13 @@ impl Cell {
         self.state == ALIVE_STATE
     }
 
+    // Alive and aging cells lose AGING_STEP per generation, starting at ALIVE_STATE.
+    pub fn is_aging_state(state: u8) -> bool {
+        state.is_multiple_of(AGING_STEP)
+    }
+
+    pub fn get_closest_aging_state(state: u8) -> u8 {
+        ((state as u32 + AGING_STEP as u32 / 2) / AGING_STEP as u32 * AGING_STEP as u32) as u8
+    }
+
 }
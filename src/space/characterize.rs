@@ -0,0 +1,120 @@
+use super::object::{self, Pattern};
+use super::soup::Region;
+use super::Space;
+
+const MAX_PADDING: u16 = 64; // empty cells around the pattern, spaceships leaving them are not characterized
+
+// Alive cells of one generation, the bounding box is given in the coordinates of the original space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phase {
+    pub population: usize,
+    pub x: i32,
+    pub y: i32,
+    pub width: u16,
+    pub height: u16,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Characterization {
+    pub period: Option<usize>, // None if the pattern did not return to itself
+    pub dx: i32, // displacement per period
+    pub dy: i32,
+    pub phases: Vec<Phase>, // one per generation of the period, otherwise of all generations run
+}
+
+impl Characterization {
+    pub fn get_velocity(&self) -> Option<String> {
+        self.period.map(|period| object::get_velocity(self.dx, self.dy, period))
+    }
+
+    pub fn get_population_range(&self) -> (usize, usize) {
+        let min = self.phases.iter().map(|phase| phase.population).min().unwrap_or(0);
+        let max = self.phases.iter().map(|phase| phase.population).max().unwrap_or(0);
+        (min, max)
+    }
+
+    pub fn get_report(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        match self.period {
+            Some(period) => {
+                lines.push(format!("period: {}", period));
+                lines.push(format!("displacement: ({}, {})", self.dx, self.dy));
+                lines.push(format!("velocity: {}", self.get_velocity().unwrap()));
+            }
+            None => lines.push(format!("no period within {} generations", self.phases.len().saturating_sub(1))),
+        }
+        let (min, max) = self.get_population_range();
+        lines.push(format!("population: {} to {}", min, max));
+        if self.period.is_some() {
+            for (time, phase) in self.phases.iter().enumerate() {
+                lines.push(format!(
+                    "phase {}: population {}, {}x{} at ({}, {})",
+                    time, phase.population, phase.width, phase.height, phase.x, phase.y
+                ));
+            }
+        }
+        lines
+    }
+}
+
+impl Space {
+    // Runs the alive cells of the region on their own until they return to themselves up to translation.
+    // The phases are saved as states of the copy, the normalized alive lists are compared to the first one.
+    pub fn characterize(&self, region: Option<Region>, max_generations: usize) -> Characterization {
+        let region = self.clip_region(region.unwrap_or(Region::new(0, 0, self.x_dim(), self.y_dim())));
+        let cells: Vec<(u16, u16)> = self.get_alive_cells().iter()
+            .filter(|cell| cell.x >= region.x && cell.x < region.x + region.width && cell.y >= region.y && cell.y < region.y + region.height)
+            .map(|cell| (cell.x, cell.y))
+            .collect();
+        if cells.is_empty() {
+            return Characterization { period: None, dx: 0, dy: 0, phases: vec![] };
+        }
+        let pattern = Pattern::new(&cells);
+        let x_min = cells.iter().map(|cell| cell.0).min().unwrap();
+        let y_min = cells.iter().map(|cell| cell.1).min().unwrap();
+        let padding = (max_generations.min(2 * MAX_PADDING as usize) / 2) as u16 + 2;
+        let mut space = Space::new(pattern.width() + 2 * padding, pattern.height() + 2 * padding);
+        space.rule = self.rule;
+        for &(x, y) in pattern.get_cells() {
+            space.revive_cell(x + padding, y + padding);
+        }
+        let offset = (x_min as i32 - padding as i32, y_min as i32 - padding as i32);
+        space.save_state(0);
+        let mut phases = vec![get_phase(space.states_hash_map.get(&0).unwrap(), offset)];
+        for time in 1..=max_generations {
+            space.compute_conways_game_of_life_multithreaded();
+            space.save_state(time);
+            let alive_tuples = space.states_hash_map.get(&time).unwrap();
+            let touches_edge = alive_tuples.iter()
+                .any(|alive_tuple| alive_tuple.0 == 0 || alive_tuple.1 == 0 || alive_tuple.0 == space.x_dim() - 1 || alive_tuple.1 == space.y_dim() - 1);
+            if alive_tuples.is_empty() || touches_edge {
+                break;
+            }
+            let phase = get_phase(alive_tuples, offset);
+            if get_normalized(alive_tuples) == pattern {
+                return Characterization { period: Some(time), dx: phase.x - phases[0].x, dy: phase.y - phases[0].y, phases };
+            }
+            phases.push(phase);
+        }
+        Characterization { period: None, dx: 0, dy: 0, phases }
+    }
+}
+
+fn get_normalized(alive_tuples: &[(u16, u16, u8, u32)]) -> Pattern {
+    let positions: Vec<(u16, u16)> = alive_tuples.iter().map(|alive_tuple| (alive_tuple.0, alive_tuple.1)).collect();
+    Pattern::new(&positions)
+}
+
+fn get_phase(alive_tuples: &[(u16, u16, u8, u32)], offset: (i32, i32)) -> Phase {
+    let x_min = alive_tuples.iter().map(|alive_tuple| alive_tuple.0).min().unwrap_or(0);
+    let y_min = alive_tuples.iter().map(|alive_tuple| alive_tuple.1).min().unwrap_or(0);
+    let x_max = alive_tuples.iter().map(|alive_tuple| alive_tuple.0).max().unwrap_or(0);
+    let y_max = alive_tuples.iter().map(|alive_tuple| alive_tuple.1).max().unwrap_or(0);
+    Phase {
+        population: alive_tuples.len(),
+        x: x_min as i32 + offset.0,
+        y: y_min as i32 + offset.1,
+        width: x_max - x_min + 1,
+        height: y_max - y_min + 1,
+    }
+}
@@ -0,0 +1,301 @@
+use std::fmt;
+use rayon::prelude::*;
+use super::rule::{impl_eq_for_float_parameters, Boundary, RuleParseError};
+use super::values::CellValues;
+use super::Space;
+
+const MAX_RINGS: usize = 4;
+const SMOOTH_LIFE_ALPHA_N: f32 = 0.028; // widths of the sigmoid steps of SmoothLife
+const SMOOTH_LIFE_ALPHA_M: f32 = 0.147;
+
+pub const PRESETS: [(&str, &str); 3] = [
+    ("orbium", "lenia:R=13,mu=0.15,sigma=0.015,dt=0.1,b=1"),
+    ("hydrogeminium", "lenia:R=18,mu=0.26,sigma=0.036,dt=0.5,b=0.5/1/0.667"),
+    ("smoothlife", "smoothlife:r=4,b=0.278/0.365,d=0.267/0.445,dt=0.05"),
+];
+
+// Lenia: the values grow by dt * G(K * A), with a kernel K of concentric rings and the growth G(u) = 2 exp(-(u - mu)² / 2 sigma²) - 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LeniaRule {
+    pub radius: u8,
+    pub mu: f32,
+    pub sigma: f32,
+    pub dt: f32,
+    rings: [f32; MAX_RINGS], // peak heights of the kernel rings, from the center outwards
+    num_rings: u8,
+}
+
+// SmoothLife with smooth time stepping: the filling m of the inner disk and n of the ring around it up to
+// three times its radius select the birth or death interval, the values move towards the transition by dt.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SmoothLifeRule {
+    pub inner_radius: u8,
+    pub birth: (f32, f32),
+    pub death: (f32, f32),
+    pub dt: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ContinuousRule {
+    Lenia(LeniaRule),
+    SmoothLife(SmoothLifeRule),
+}
+
+impl LeniaRule {
+    pub fn new(radius: u8, mu: f32, sigma: f32, dt: f32, rings: &[f32]) -> Option<LeniaRule> {
+        let is_valid = radius > 0 && sigma > 0. && dt > 0. && dt <= 1. && (1..=MAX_RINGS).contains(&rings.len())
+            && [mu, sigma, dt].iter().chain(rings).all(|value| value.is_finite() && *value >= 0.);
+        if !is_valid {
+            return None;
+        }
+        let mut ring_array = [0.; MAX_RINGS];
+        ring_array[..rings.len()].copy_from_slice(rings);
+        Some(LeniaRule { radius, mu, sigma, dt, rings: ring_array, num_rings: rings.len() as u8 })
+    }
+
+    pub fn get_rings(&self) -> &[f32] {
+        &self.rings[..self.num_rings as usize]
+    }
+
+    pub fn get_growth(&self, potential: f32) -> f32 {
+        2. * (-(potential - self.mu).powi(2) / (2. * self.sigma * self.sigma)).exp() - 1.
+    }
+
+    // Ring shells of the smooth bump exp(4 - 1 / (r (1 - r))), normalized to a sum of one.
+    pub fn get_kernel(&self) -> Vec<(i32, i32, f32)> {
+        let radius = self.radius as i32;
+        let rings = self.get_rings();
+        get_disk_kernel(radius, |distance| {
+            let ring_position = distance * rings.len() as f32;
+            let ring = ring_position.floor() as usize;
+            let local = ring_position.fract();
+            if ring >= rings.len() || local <= 0. {
+                return 0.;
+            }
+            rings[ring] * (4. - 1. / (local * (1. - local))).exp()
+        })
+    }
+}
+
+impl SmoothLifeRule {
+    // Transition towards which a cell with the given ring and disk fillings moves, between 0 and 1.
+    pub fn get_transition(&self, ring_filling: f32, disk_filling: f32) -> f32 {
+        let aliveness = sigmoid(disk_filling, 0.5, SMOOTH_LIFE_ALPHA_M);
+        let lower = self.birth.0 * (1. - aliveness) + self.death.0 * aliveness;
+        let upper = self.birth.1 * (1. - aliveness) + self.death.1 * aliveness;
+        sigmoid(ring_filling, lower, SMOOTH_LIFE_ALPHA_N) * (1. - sigmoid(ring_filling, upper, SMOOTH_LIFE_ALPHA_N))
+    }
+
+    pub fn get_disk_kernel(&self) -> Vec<(i32, i32, f32)> {
+        get_disk_kernel(3 * self.inner_radius as i32, |distance| if distance <= 1. / 3. { 1. } else { 0. })
+    }
+
+    pub fn get_ring_kernel(&self) -> Vec<(i32, i32, f32)> {
+        get_disk_kernel(3 * self.inner_radius as i32, |distance| if distance > 1. / 3. && distance <= 1. { 1. } else { 0. })
+    }
+}
+
+impl ContinuousRule {
+    // Accepts "lenia:R=13,mu=0.15,sigma=0.015,dt=0.1,b=1/0.5" and "smoothlife:r=4,b=0.278/0.365,d=0.267/0.445,dt=0.05".
+    pub fn parse(rulestring: &str) -> Result<ContinuousRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let (kind, parameters) = rulestring.split_once(':').ok_or_else(error)?;
+        let get = |key: &str| get_parameter(parameters, key).ok_or_else(error);
+        let get_single = |key: &str| -> Result<f32, RuleParseError> {
+            get(key)?.first().copied().ok_or_else(error)
+        };
+        let get_pair = |key: &str| -> Result<(f32, f32), RuleParseError> {
+            match get(key)?[..] {
+                [lower, upper] if lower <= upper => Ok((lower, upper)),
+                _ => Err(error()),
+            }
+        };
+        let get_radius = |key: &str| -> Result<u8, RuleParseError> {
+            let radius = get_single(key)?;
+            ((1. ..=40.).contains(&radius) && radius.fract() == 0.).then_some(radius as u8).ok_or_else(error)
+        };
+        match kind {
+            "lenia" => {
+                let rule = LeniaRule::new(get_radius("R")?, get_single("mu")?, get_single("sigma")?, get_single("dt")?, &get("b")?);
+                rule.map(ContinuousRule::Lenia).ok_or_else(error)
+            }
+            "smoothlife" => {
+                let dt = get_single("dt")?;
+                if dt <= 0. || dt > 1. {
+                    return Err(error());
+                }
+                let rule = SmoothLifeRule { inner_radius: get_radius("r")?, birth: get_pair("b")?, death: get_pair("d")?, dt };
+                Ok(ContinuousRule::SmoothLife(rule))
+            }
+            _ => Err(error()),
+        }
+    }
+}
+
+impl fmt::Display for ContinuousRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ContinuousRule::Lenia(rule) => {
+                let rings: Vec<String> = rule.get_rings().iter().map(|ring| ring.to_string()).collect();
+                write!(f, "lenia:R={},mu={},sigma={},dt={},b={}", rule.radius, rule.mu, rule.sigma, rule.dt, rings.join("/"))
+            }
+            ContinuousRule::SmoothLife(rule) => write!(
+                f, "smoothlife:r={},b={}/{},d={}/{},dt={}",
+                rule.inner_radius, rule.birth.0, rule.birth.1, rule.death.0, rule.death.1, rule.dt
+            ),
+        }
+    }
+}
+
+impl_eq_for_float_parameters!(ContinuousRule, |rule| match rule {
+    ContinuousRule::Lenia(rule) => [rule.radius as f32, rule.mu, rule.sigma, rule.dt, rule.num_rings as f32].into_iter().chain(rule.rings).collect(),
+    ContinuousRule::SmoothLife(rule) => vec![rule.inner_radius as f32, rule.birth.0, rule.birth.1, rule.death.0, rule.death.1, rule.dt],
+});
+
+// Grid of values between 0 and 1, stored row by row.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ContinuousGrid {
+    x_dim: u16,
+    y_dim: u16,
+    values: Vec<f32>,
+}
+
+impl ContinuousGrid {
+    pub fn new(x_dim: u16, y_dim: u16) -> ContinuousGrid {
+        ContinuousGrid::filled(x_dim, y_dim, 0.)
+    }
+
+    pub fn filled(x_dim: u16, y_dim: u16, value: f32) -> ContinuousGrid {
+        ContinuousGrid { x_dim, y_dim, values: vec![value.clamp(0., 1.); x_dim as usize * y_dim as usize] }
+    }
+
+    pub fn x_dim(&self) -> u16 {
+        self.x_dim
+    }
+
+    pub fn y_dim(&self) -> u16 {
+        self.y_dim
+    }
+
+    pub fn values(&self) -> &[f32] {
+        &self.values
+    }
+
+    pub fn get_value(&self, x: u16, y: u16) -> f32 {
+        self.values[y as usize * self.x_dim as usize + x as usize]
+    }
+
+    pub fn set_value(&mut self, x: u16, y: u16, value: f32) {
+        self.values[y as usize * self.x_dim as usize + x as usize] = value.clamp(0., 1.);
+    }
+
+    // Grid of the given size with the values of the shared region, the background value elsewhere.
+    pub fn resized(&self, x_dim: u16, y_dim: u16, background: f32) -> ContinuousGrid {
+        let mut grid = ContinuousGrid::filled(x_dim, y_dim, background);
+        for y in 0..y_dim.min(self.y_dim) {
+            for x in 0..x_dim.min(self.x_dim) {
+                grid.set_value(x, y, self.get_value(x, y));
+            }
+        }
+        grid
+    }
+
+    // Weighted sums of the values around every position, outside a bounded grid the values are zero.
+    pub fn convolve(&self, kernel: &[(i32, i32, f32)], boundary: Boundary) -> Vec<f32> {
+        let (x_dim, y_dim) = (self.x_dim as i32, self.y_dim as i32);
+        (0..self.values.len())
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index as i32 % x_dim, index as i32 / x_dim);
+                kernel.iter()
+                    .filter_map(|&(dx, dy, weight)| {
+                        let (mut kernel_x, mut kernel_y) = (x + dx, y + dy);
+                        if boundary == Boundary::Toroidal {
+                            kernel_x = kernel_x.rem_euclid(x_dim);
+                            kernel_y = kernel_y.rem_euclid(y_dim);
+                        }
+                        let is_inside = kernel_x >= 0 && kernel_y >= 0 && kernel_x < x_dim && kernel_y < y_dim;
+                        is_inside.then(|| weight * self.values[(kernel_y * x_dim + kernel_x) as usize])
+                    })
+                    .sum()
+            })
+            .collect()
+    }
+
+    pub fn step(&mut self, rule: &ContinuousRule, boundary: Boundary) {
+        let changes: Vec<f32> = match rule {
+            ContinuousRule::Lenia(rule) => {
+                let potentials = self.convolve(&rule.get_kernel(), boundary);
+                potentials.par_iter().map(|&potential| rule.dt * rule.get_growth(potential)).collect()
+            }
+            ContinuousRule::SmoothLife(rule) => {
+                let disk_fillings = self.convolve(&rule.get_disk_kernel(), boundary);
+                let ring_fillings = self.convolve(&rule.get_ring_kernel(), boundary);
+                ring_fillings.par_iter().zip(disk_fillings.par_iter())
+                    .map(|(&ring_filling, &disk_filling)| rule.dt * (2. * rule.get_transition(ring_filling, disk_filling) - 1.))
+                    .collect()
+            }
+        };
+        self.values.par_iter_mut().zip(changes.par_iter()).for_each(|(value, change)| *value = (*value + change).clamp(0., 1.));
+    }
+}
+
+impl Space {
+    // The values are kept in the grid of the space, the cell states show them rounded to 255 steps.
+    // Cells changed from outside, by painting or switching the automaton, overwrite the grid value before the step.
+    pub(crate) fn compute_continuous_generation(&mut self, rule: &ContinuousRule) {
+        let mut grid = match std::mem::take(&mut self.values) {
+            CellValues::Continuous(grid) if (grid.x_dim(), grid.y_dim()) == (self.x_dim(), self.y_dim()) => grid,
+            _ => ContinuousGrid::new(self.x_dim(), self.y_dim()),
+        };
+        for cell in self.flat() {
+            if get_cell_state(grid.get_value(cell.x, cell.y)) != cell.get_state() {
+                grid.set_value(cell.x, cell.y, cell.get_state() as f32 / 255.);
+            }
+        }
+        grid.step(rule, self.boundary);
+        for cell in self.flat_mut() {
+            let state = get_cell_state(grid.get_value(cell.x, cell.y));
+            if state == 0 {
+                cell.kill();
+            } else {
+                cell.set_state(state);
+            }
+        }
+        self.values = CellValues::Continuous(grid);
+    }
+}
+
+// Finite values of a "key=value/value..." entry in a comma separated parameter list.
+pub(crate) fn get_parameter(parameters: &str, key: &str) -> Option<Vec<f32>> {
+    let value = parameters.split(',').find_map(|parameter| parameter.strip_prefix(key)?.strip_prefix('='))?;
+    value.split('/').map(|number| number.parse::<f32>().ok().filter(|number| number.is_finite())).collect()
+}
+
+fn get_cell_state(value: f32) -> u8 {
+    (value * 255.).round() as u8
+}
+
+fn sigmoid(x: f32, center: f32, width: f32) -> f32 {
+    1. / (1. + (-(x - center) * 4. / width).exp())
+}
+
+// Offsets within the radius weighted by the shape of the relative distance, normalized to a sum of one.
+fn get_disk_kernel(radius: i32, shape: impl Fn(f32) -> f32) -> Vec<(i32, i32, f32)> {
+    let mut kernel = Vec::new();
+    for dy in -radius..=radius {
+        for dx in -radius..=radius {
+            let distance = ((dx * dx + dy * dy) as f32).sqrt() / radius as f32;
+            let weight = shape(distance);
+            if weight > 0. {
+                kernel.push((dx, dy, weight));
+            }
+        }
+    }
+    let sum: f32 = kernel.iter().map(|entry| entry.2).sum();
+    for entry in &mut kernel {
+        entry.2 /= sum;
+    }
+    kernel
+}
@@ -0,0 +1,205 @@
+use std::fmt;
+use std::sync::OnceLock;
+use rand::Rng;
+use rayon::prelude::*;
+use super::cell::Cell;
+use super::rule::RuleParseError;
+use super::Space;
+
+pub const MAX_STATES: u8 = 16;
+const MAX_RANGE: u8 = 10;
+const MAX_NOISE: usize = 2; // added to the threshold at random by rock-paper-scissors
+
+const STATE_NAMES: [&str; MAX_STATES as usize] = [
+    "state 0", "state 1", "state 2", "state 3", "state 4", "state 5", "state 6", "state 7",
+    "state 8", "state 9", "state 10", "state 11", "state 12", "state 13", "state 14", "state 15",
+];
+const EXCITABLE_STATE_NAMES: [&str; MAX_STATES as usize] = [
+    "resting", "excited", "refractory 1", "refractory 2", "refractory 3", "refractory 4", "refractory 5", "refractory 6",
+    "refractory 7", "refractory 8", "refractory 9", "refractory 10", "refractory 11", "refractory 12", "refractory 13", "refractory 14",
+];
+
+pub const PRESETS: [(&str, &str); 5] = [
+    ("313", "cyclic:R1/T3/C3/NM"),
+    ("CCA", "cyclic:R1/T1/C14/NN"),
+    ("cyclic spirals", "cyclic:R3/T5/C8/NM"),
+    ("rock-paper-scissors", "rps:R1/T3/C3/NM"),
+    ("greenberg-hastings", "gh:R1/T1/C5/NN"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Neighborhood {
+    #[default]
+    Moore,
+    VonNeumann,
+}
+
+impl Neighborhood {
+    pub fn get_offsets(&self, range: u8) -> Vec<(i32, i32)> {
+        let range = range as i32;
+        let mut offsets = Vec::new();
+        for dy in -range..=range {
+            for dx in -range..=range {
+                let is_inside = match self {
+                    Neighborhood::Moore => true,
+                    Neighborhood::VonNeumann => dx.abs() + dy.abs() <= range,
+                };
+                if is_inside && (dx, dy) != (0, 0) {
+                    offsets.push((dx, dy));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum CyclicKind {
+    #[default]
+    Cyclic, // a cell advances to the next state if enough neighbours are in it
+    RockPaperScissors, // the same with a random extra threshold, so fronts move unevenly
+    GreenbergHastings, // excitable medium: only resting cells wait for excited neighbours, the others always advance
+}
+
+// Cyclic automaton in the notation of MCell: range, threshold, number of states and neighbourhood,
+// like R1/T3/C3/NM for Moore or R1/T1/C14/NN for von Neumann neighbourhoods. Greenberg-Hastings media
+// have a resting, an excited and C - 2 refractory states.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CyclicRule {
+    pub kind: CyclicKind,
+    pub range: u8,
+    pub threshold: u8,
+    pub num_states: u8,
+    pub neighborhood: Neighborhood,
+}
+
+impl CyclicRule {
+    pub fn parse(rulestring: &str) -> Result<CyclicRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let (kind, parameters) = match rulestring.split_once(':') {
+            Some(("cyclic", parameters)) => (CyclicKind::Cyclic, parameters),
+            Some(("rps", parameters)) => (CyclicKind::RockPaperScissors, parameters),
+            Some(("gh", parameters)) => (CyclicKind::GreenbergHastings, parameters),
+            _ => return Err(error()),
+        };
+        let (mut range, mut threshold, mut num_states, mut neighborhood) = (None, None, None, Neighborhood::default());
+        for part in parameters.split('/') {
+            let mut chars = part.chars();
+            let (key, value) = (chars.next(), chars.as_str());
+            match key {
+                Some('R') => range = Some(value.parse::<u8>().map_err(|_| error())?),
+                Some('T') => threshold = Some(value.parse::<u8>().map_err(|_| error())?),
+                Some('C') => num_states = Some(value.parse::<u8>().map_err(|_| error())?),
+                Some('N') => neighborhood = match value {
+                    "M" => Neighborhood::Moore,
+                    "N" => Neighborhood::VonNeumann,
+                    _ => return Err(error()),
+                },
+                _ => return Err(error()),
+            }
+        }
+        let rule = CyclicRule {
+            kind,
+            range: range.ok_or_else(error)?,
+            threshold: threshold.ok_or_else(error)?,
+            num_states: num_states.ok_or_else(error)?,
+            neighborhood,
+        };
+        let num_neighbors = rule.neighborhood.get_offsets(rule.range.min(MAX_RANGE)).len();
+        let is_valid = (1..=MAX_RANGE).contains(&rule.range) && (2..=MAX_STATES).contains(&rule.num_states)
+            && rule.threshold > 0 && rule.threshold as usize <= num_neighbors;
+        is_valid.then_some(rule).ok_or_else(error)
+    }
+
+    pub fn get_successor(&self, state: u8) -> u8 {
+        (state + 1) % self.num_states
+    }
+
+    // Evenly spaced hues, the first state is dark as it is drawn as the background.
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        static PALETTES: OnceLock<Vec<Vec<(f32, f32, f32)>>> = OnceLock::new();
+        let palettes = PALETTES.get_or_init(|| {
+            (0..=MAX_STATES).map(|num_states| {
+                (0..num_states).map(|state| {
+                    if state == 0 {
+                        (0., 0., 0.)
+                    } else {
+                        get_hue_color((state - 1) as f32 / (num_states - 1) as f32)
+                    }
+                }).collect()
+            }).collect()
+        });
+        &palettes[self.num_states as usize]
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        match (self.kind, self.num_states) {
+            (CyclicKind::RockPaperScissors, 3) => &["rock", "paper", "scissors"],
+            (CyclicKind::GreenbergHastings, _) => &EXCITABLE_STATE_NAMES[..self.num_states as usize],
+            _ => &STATE_NAMES[..self.num_states as usize],
+        }
+    }
+}
+
+impl fmt::Display for CyclicRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            CyclicKind::Cyclic => "cyclic",
+            CyclicKind::RockPaperScissors => "rps",
+            CyclicKind::GreenbergHastings => "gh",
+        };
+        let neighborhood = match self.neighborhood {
+            Neighborhood::Moore => "M",
+            Neighborhood::VonNeumann => "N",
+        };
+        write!(f, "{}:R{}/T{}/C{}/N{}", kind, self.range, self.threshold, self.num_states, neighborhood)
+    }
+}
+
+impl Space {
+    pub(crate) fn compute_cyclic_generation(&mut self, rule: &CyclicRule) {
+        let num_states = rule.num_states;
+        let offsets = rule.neighborhood.get_offsets(rule.range);
+        // the random thresholds are drawn up front from the seeded generator, so the result does not depend on the threads
+        let noise: Vec<u8> = match rule.kind {
+            CyclicKind::Cyclic | CyclicKind::GreenbergHastings => vec![],
+            CyclicKind::RockPaperScissors => {
+                let num_cells = self.x_dim() as usize * self.y_dim() as usize;
+                (0..num_cells).map(|_| self.rng.gen_range(0..=MAX_NOISE) as u8).collect()
+            }
+        };
+        let changes: Vec<(u16, u16, u8)> = self.flat()
+            .par_iter()
+            .filter_map(|cell: &&Cell| {
+                let state = cell.get_state_index(num_states);
+                let successor = rule.get_successor(state);
+                if rule.kind == CyclicKind::GreenbergHastings && state != 0 {
+                    return Some((cell.x, cell.y, successor));
+                }
+                let num_successors = offsets.iter()
+                    .filter_map(|&(dx, dy)| self.get_neighbor_position(cell.x, cell.y, dx, dy))
+                    .filter(|&(x, y)| self.get_cell(x, y).unwrap().get_state_index(num_states) == successor)
+                    .count();
+                let extra = noise.get(cell.x as usize * self.y_dim() as usize + cell.y as usize).copied().unwrap_or(0);
+                (num_successors >= (rule.threshold + extra) as usize).then_some((cell.x, cell.y, successor))
+            })
+            .collect();
+        for (x, y, state) in changes {
+            self.get_cell_mut(x, y).unwrap().set_state_index(state, num_states);
+        }
+    }
+}
+
+fn get_hue_color(hue: f32) -> (f32, f32, f32) {
+    let sector = hue * 6.;
+    let fraction = sector.fract();
+    match sector as u32 % 6 {
+        0 => (1., fraction, 0.),
+        1 => (1. - fraction, 1., 0.),
+        2 => (0., 1., fraction),
+        3 => (0., 1. - fraction, 1.),
+        4 => (fraction, 0., 1.),
+        _ => (1., 0., 1. - fraction),
+    }
+}
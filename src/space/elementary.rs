@@ -0,0 +1,170 @@
+use std::fmt;
+use super::automaton::{get_cell_state, get_state_index};
+use super::rule::RuleParseError;
+use super::Space;
+
+const MAX_COLORS: u8 = 10;
+const MAX_RADIUS: u8 = 5;
+const TOTALISTIC_KINDS: [(u8, u8); 5] = [(3, 1), (4, 1), (2, 2), (3, 2), (2, 3)]; // (colors, radius) offered by next_kind
+
+// Rule of a one dimensional automaton, shown as a space-time diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinearRule {
+    Elementary(u8), // Wolfram code of the 2 color rules with radius 1
+    Totalistic { colors: u8, radius: u8, code: u64 }, // digit s of the code in base colors is the color for the neighbourhood sum s
+}
+
+impl LinearRule {
+    pub fn totalistic(colors: u8, radius: u8, code: u64) -> Option<LinearRule> {
+        if !(2..=MAX_COLORS).contains(&colors) || !(1..=MAX_RADIUS).contains(&radius) {
+            return None;
+        }
+        match Self::get_num_codes(colors, radius) {
+            Some(num_codes) if code >= num_codes => None,
+            _ => Some(LinearRule::Totalistic { colors, radius, code }),
+        }
+    }
+
+    // Accepts Wolfram codes as "W30" or "30" and totalistic rules as "k3r1c777".
+    pub fn parse(rulestring: &str) -> Result<LinearRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        if let Ok(code) = rulestring.strip_prefix(['W', 'w']).unwrap_or(rulestring).parse::<u8>() {
+            return Ok(LinearRule::Elementary(code));
+        }
+        let parameters = rulestring.strip_prefix(['K', 'k']).ok_or_else(error)?.to_lowercase();
+        let (colors, parameters) = parameters.split_once('r').ok_or_else(error)?;
+        let (radius, code) = parameters.split_once('c').ok_or_else(error)?;
+        let colors = colors.parse().map_err(|_| error())?;
+        let radius = radius.parse().map_err(|_| error())?;
+        let code = code.parse().map_err(|_| error())?;
+        LinearRule::totalistic(colors, radius, code).ok_or_else(error)
+    }
+
+    // None if there are more codes than fit into an u64.
+    fn get_num_codes(colors: u8, radius: u8) -> Option<u64> {
+        let num_sums = (2 * radius as u32 + 1) * (colors as u32 - 1) + 1;
+        (colors as u64).checked_pow(num_sums)
+    }
+
+    pub fn get_num_colors(&self) -> u8 {
+        match self {
+            LinearRule::Elementary(_) => 2,
+            LinearRule::Totalistic { colors, .. } => *colors,
+        }
+    }
+
+    pub fn get_radius(&self) -> u8 {
+        match self {
+            LinearRule::Elementary(_) => 1,
+            LinearRule::Totalistic { radius, .. } => *radius,
+        }
+    }
+
+    pub fn get_code(&self) -> u64 {
+        match self {
+            LinearRule::Elementary(code) => *code as u64,
+            LinearRule::Totalistic { code, .. } => *code,
+        }
+    }
+
+    fn get_code_count(&self) -> Option<u64> {
+        match self {
+            LinearRule::Elementary(_) => Some(256),
+            LinearRule::Totalistic { colors, radius, .. } => Self::get_num_codes(*colors, *radius),
+        }
+    }
+
+    pub fn next(&self) -> LinearRule {
+        self.with_code(self.get_code().wrapping_add(1))
+    }
+
+    pub fn previous(&self) -> LinearRule {
+        match self.get_code().checked_sub(1) {
+            Some(code) => self.with_code(code),
+            None => self.with_code(self.get_code_count().map_or(u64::MAX, |count| count - 1)),
+        }
+    }
+
+    // Cycles from the elementary rules through the totalistic kinds, keeping the code if possible.
+    pub fn next_kind(&self) -> LinearRule {
+        let kind = match self {
+            LinearRule::Elementary(_) => Some(TOTALISTIC_KINDS[0]),
+            LinearRule::Totalistic { colors, radius, .. } => TOTALISTIC_KINDS.iter()
+                .position(|kind| *kind == (*colors, *radius))
+                .and_then(|index| TOTALISTIC_KINDS.get(index + 1).copied()),
+        };
+        match kind {
+            Some((colors, radius)) => LinearRule::Totalistic { colors, radius, code: 0 }.with_code(self.get_code()),
+            None => LinearRule::Elementary(0).with_code(self.get_code()),
+        }
+    }
+
+    // Same kind of rule with another code, wrapped around the number of codes.
+    pub fn with_code(&self, code: u64) -> LinearRule {
+        match *self {
+            LinearRule::Elementary(_) => LinearRule::Elementary((code % 256) as u8),
+            LinearRule::Totalistic { colors, radius, .. } => {
+                let code = Self::get_num_codes(colors, radius).map_or(code, |num_codes| code % num_codes);
+                LinearRule::Totalistic { colors, radius, code }
+            }
+        }
+    }
+
+    // Colors of the cells from x - radius to x + radius.
+    pub fn get_next_color(&self, neighbourhood: &[u8]) -> u8 {
+        match self {
+            LinearRule::Elementary(code) => {
+                let index = neighbourhood.iter().fold(0, |index, &color| 2 * index + color.min(1));
+                (code >> index) & 1
+            }
+            LinearRule::Totalistic { colors, code, .. } => {
+                let sum: u32 = neighbourhood.iter().map(|&color| color as u32).sum();
+                (*colors as u64).checked_pow(sum).map_or(0, |place| (code / place) % *colors as u64) as u8
+            }
+        }
+    }
+}
+
+impl fmt::Display for LinearRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LinearRule::Elementary(code) => write!(f, "W{}", code),
+            LinearRule::Totalistic { colors, radius, code } => write!(f, "k{}r{}c{}", colors, radius, code),
+        }
+    }
+}
+
+impl Space {
+    // Generation t is written into row t. Once the rows are full, the earlier generations scroll up and the bottom row holds the newest one.
+    pub(crate) fn compute_linear_generation(&mut self, rule: &LinearRule) {
+        let num_colors = rule.get_num_colors();
+        let bottom = self.y_dim() - 1;
+        let current_row = self.displayed_time.min(bottom as usize) as u16;
+        let current_colors: Vec<u8> = (0..self.x_dim())
+            .map(|x| get_state_index(self.get_cell(x, current_row).unwrap().get_state(), num_colors))
+            .collect();
+        let next_row = if current_row < bottom {
+            current_row + 1
+        } else {
+            for y in 0..bottom {
+                for x in 0..self.x_dim() {
+                    let below = self.get_cell(x, y + 1).unwrap().clone();
+                    let cell = self.get_cell_mut(x, y).unwrap();
+                    cell.set_state(below.get_state());
+                    cell.set_generations_alive(below.get_generations_alive());
+                }
+            }
+            bottom
+        };
+        let radius = rule.get_radius() as i32;
+        for x in 0..self.x_dim() {
+            let neighbourhood: Vec<u8> = (-radius..=radius)
+                .map(|dx| self.get_neighbor_position(x, next_row, dx, 0).map_or(0, |(neighbor_x, _)| current_colors[neighbor_x as usize]))
+                .collect();
+            let cell = self.get_cell_mut(x, next_row).unwrap();
+            cell.kill();
+            cell.set_state(get_cell_state(rule.get_next_color(&neighbourhood), num_colors));
+        }
+    }
+}
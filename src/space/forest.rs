@@ -0,0 +1,177 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::{error::Error, fmt, fs, path::Path};
+use rand::Rng;
+use rayon::prelude::*;
+use super::cell::Cell;
+use super::continuous::get_parameter;
+use super::rule::{impl_eq_for_float_parameters, RuleParseError};
+use super::values::{CellValues, ValueGrid};
+use super::Space;
+
+const NUM_STATES: u8 = 3;
+const EMPTY: u8 = 0;
+const TREE: u8 = 1;
+const FIRE: u8 = 2;
+const FIRE_OFFSETS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+
+pub const PRESETS: [(&str, &str); 1] = [("forest fire", "forestfire:p=0.02,f=0.0002")];
+
+// Drossel-Schwabl forest fire: fires burn down in one generation and spread to the neighbouring trees,
+// trees grow on empty cells with probability p and are struck by lightning with probability f.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ForestFireRule {
+    pub growth: f32,
+    pub lightning: f32,
+}
+
+impl ForestFireRule {
+    // Accepts "forestfire:p=0.02,f=0.0002".
+    pub fn parse(rulestring: &str) -> Result<ForestFireRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let parameters = rulestring.strip_prefix("forestfire:").ok_or_else(error)?;
+        let get = |key: &str| -> Result<f32, RuleParseError> {
+            get_parameter(parameters, key).and_then(|values| values.first().copied()).filter(|value| (0. ..=1.).contains(value)).ok_or_else(error)
+        };
+        Ok(ForestFireRule { growth: get("p")?, lightning: get("f")? })
+    }
+
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        &[(0., 0., 0.), (0.1, 0.6, 0.1), (1., 0.4, 0.)]
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        &["empty", "tree", "fire"]
+    }
+}
+
+impl fmt::Display for ForestFireRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "forestfire:p={},f={}", self.growth, self.lightning)
+    }
+}
+
+impl_eq_for_float_parameters!(ForestFireRule, |rule| vec![rule.growth, rule.lightning]);
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct FireGeneration {
+    pub(crate) burning: HashMap<u32, usize>, // trees burnt so far by the fires still burning
+    pub(crate) burnt_out: Vec<usize>, // sizes of the fires which burnt out in the generation
+}
+
+// Sizes of the fires, as the number of trees each lightning strike burnt down, kept by time so stepping
+// through the history shows the fires up to the displayed generation. The numbers of the fires burning
+// the cells are kept in the values of the space, so fires are told apart in the history too.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FireStatistics {
+    pub(crate) generations: BTreeMap<usize, FireGeneration>,
+}
+
+impl FireStatistics {
+    // Number of fires burnt out up to the time per size.
+    pub fn get_histogram(&self, time: usize) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for (_, generation) in self.generations.range(..=time) {
+            for &size in &generation.burnt_out {
+                *histogram.entry(size).or_insert(0) += 1;
+            }
+        }
+        histogram
+    }
+
+    pub fn get_num_fires(&self, time: usize) -> usize {
+        self.generations.range(..=time).map(|(_, generation)| generation.burnt_out.len()).sum()
+    }
+
+    pub fn get_num_burning_fires(&self, time: usize) -> usize {
+        self.generations.get(&time).map_or(0, |generation| generation.burning.len())
+    }
+
+    pub fn write_histogram_csv(&self, file_str: &str, time: usize) -> Result<(), Box<dyn Error>> {
+        let mut content = String::from("size,count\n");
+        for (size, count) in &self.get_histogram(time) {
+            content.push_str(&format!("{},{}\n", size, count));
+        }
+        fs::write(Path::new(file_str), content)?;
+        Ok(())
+    }
+}
+
+enum Ignition {
+    Spread(u32), // from the burning neighbour with the lowest fire number
+    Lightning,
+}
+
+impl Space {
+    // Fires are numbered from 1, painted fires have the number 0.
+    pub(crate) fn compute_forest_fire_generation(&mut self, rule: &ForestFireRule) {
+        let mut fires = match std::mem::take(&mut self.values) {
+            CellValues::Fires(fires) if (fires.x_dim(), fires.y_dim()) == (self.x_dim(), self.y_dim()) => fires,
+            _ => ValueGrid::new(self.x_dim(), self.y_dim()),
+        };
+        // the random numbers are drawn up front from the seeded generator, so the result does not depend on the threads
+        let num_cells = self.x_dim() as usize * self.y_dim() as usize;
+        let chances: Vec<f32> = (0..num_cells).map(|_| self.rng.gen()).collect();
+        let changes: Vec<(u16, u16, u8, Option<Ignition>)> = self.flat()
+            .par_iter()
+            .filter_map(|cell: &&Cell| {
+                let chance = chances[cell.x as usize * self.y_dim() as usize + cell.y as usize];
+                match cell.get_state_index(NUM_STATES) {
+                    FIRE => Some((cell.x, cell.y, EMPTY, None)),
+                    TREE => {
+                        let burning_neighbor = FIRE_OFFSETS.iter()
+                            .filter_map(|&(dx, dy)| self.get_neighbor_position(cell.x, cell.y, dx, dy))
+                            .map(|(x, y)| self.get_cell(x, y).unwrap())
+                            .filter(|neighbor| neighbor.get_state_index(NUM_STATES) == FIRE)
+                            .map(|neighbor| fires.get_value(neighbor.x, neighbor.y))
+                            .min();
+                        match burning_neighbor {
+                            Some(fire) => Some((cell.x, cell.y, FIRE, Some(Ignition::Spread(fire)))),
+                            None if chance < rule.lightning => Some((cell.x, cell.y, FIRE, Some(Ignition::Lightning))),
+                            None => None,
+                        }
+                    }
+                    EMPTY if chance < rule.growth => Some((cell.x, cell.y, TREE, None)),
+                    _ => None,
+                }
+            })
+            .collect();
+        // new fires get numbers above those of the fires burning in the space, which may come from the history
+        let highest_fire = self.flat().iter()
+            .filter(|cell| cell.get_state_index(NUM_STATES) == FIRE)
+            .map(|cell| fires.get_value(cell.x, cell.y))
+            .max();
+        let time = self.displayed_time;
+        let statistics = &mut self.fire_statistics;
+        // generations computed before from another state are replaced
+        statistics.generations.retain(|&generation_time, _| generation_time <= time);
+        let mut burning = statistics.generations.get(&time).map(|generation| generation.burning.clone()).unwrap_or_default();
+        let mut next_fire = highest_fire.into_iter().chain(burning.keys().copied()).max().map_or(1, |fire| fire + 1);
+        let mut burning_fires = HashSet::new();
+        let mut updates = Vec::new();
+        for (x, y, state, ignition) in changes {
+            let fire = match ignition {
+                Some(Ignition::Spread(fire)) => fire,
+                Some(Ignition::Lightning) => {
+                    next_fire += 1;
+                    next_fire - 1
+                }
+                None => 0,
+            };
+            if state == FIRE {
+                *burning.entry(fire).or_insert(0) += 1;
+                burning_fires.insert(fire);
+            }
+            updates.push((x, y, state, fire));
+        }
+        // fires without burning cells in the new generation are burnt out
+        let burnt_out_fires: Vec<u32> = burning.keys().filter(|fire| !burning_fires.contains(fire)).copied().collect();
+        let burnt_out = burnt_out_fires.iter().map(|fire| burning.remove(fire).unwrap()).collect();
+        statistics.generations.insert(time + 1, FireGeneration { burning, burnt_out });
+        for (x, y, state, fire) in updates {
+            self.get_cell_mut(x, y).unwrap().set_state_index(state, NUM_STATES);
+            fires.set_value(x, y, fire);
+        }
+        self.values = CellValues::Fires(fires);
+    }
+}
@@ -1,5 +1,6 @@
 use std::{fs, path::Path};
 
+use super::rule::Rule;
 use super::Space;
 
 pub fn write_to_file(file_str: &str, space: &Space) -> Result<(), Box<dyn std::error::Error>> {
@@ -17,29 +18,88 @@ pub fn write_to_file(file_str: &str, space: &Space) -> Result<(), Box<dyn std::e
         }
     }
     let file_path = Path::new(file_str);
-    fs::write(file_path, content).expect("Failed to write to file");
+    fs::write(file_path, content)?;
     Ok(())
 }
 
 pub fn read_from_file(file_str: &str) -> Result<Space, Box<dyn std::error::Error>> {
     let file_path = Path::new(file_str);
-    let content = fs::read_to_string(file_path).expect("Failed to read file");
+    let content = fs::read_to_string(file_path)?;
+    if file_path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("rle")) {
+        return read_rle(&content);
+    }
     let lines: Vec<&str> = content.lines().collect();
-    let x_dim = lines[0].len() as u16;
-    let y_dim = lines.len() as u16;
-    let mut space = Space::new(x_dim, y_dim);
-    let mut y = 0;
-    for line in lines {
-        let mut x = 0;
-        for c in line.chars() {
+    let x_dim = lines.first().map_or(0, |line| line.len());
+    if x_dim == 0 || x_dim > u16::MAX as usize || lines.len() > u16::MAX as usize {
+        return Err("Space file is empty or too large!".into());
+    }
+    if lines.iter().any(|line| line.len() != x_dim) {
+        return Err("Lines of the space file differ in length!".into());
+    }
+    let mut space = Space::new(x_dim as u16, lines.len() as u16);
+    for (y, line) in lines.iter().enumerate() {
+        for (x, c) in line.chars().enumerate() {
             if c == '1' {
-                space.revive_cell(x, y);
+                space.revive_cell(x as u16, y as u16);
             }
-            x += 1;
         }
-        y += 1;
     }
     space.save_state(space.displayed_time);
     Ok(space)
 
 }
+
+// Pattern in run length encoding with a header like "x = 3, y = 3, rule = B3/S23" or a Hensel rulestring
+// like "rule = B2-a/S12". Comment lines start with '#', 'b' and '.' are dead cells, other letters alive ones.
+pub fn read_rle(content: &str) -> Result<Space, Box<dyn std::error::Error>> {
+    let mut lines = content.lines().map(str::trim).filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let header = lines.next().ok_or("Missing RLE header!")?;
+    let mut dims = (None, None);
+    let mut rule = Rule::default();
+    for entry in header.split(',') {
+        let (key, value) = entry.split_once('=').ok_or_else(|| format!("Invalid RLE header '{}'!", header))?;
+        match key.trim() {
+            "x" => dims.0 = value.trim().parse::<u16>().ok().filter(|&x_dim| x_dim > 0),
+            "y" => dims.1 = value.trim().parse::<u16>().ok().filter(|&y_dim| y_dim > 0),
+            "rule" => rule = Rule::parse(value)?,
+            _ => {}
+        }
+    }
+    let (Some(x_dim), Some(y_dim)) = dims else {
+        return Err(format!("Invalid RLE header '{}'!", header).into());
+    };
+    let mut space = Space::new(x_dim, y_dim);
+    space.rule = rule;
+    let (mut x, mut y, mut count) = (0u32, 0u32, 0u32);
+    // runs may end at the border of the pattern, cells are only placed inside it
+    let advance = |position: u32, run: u32, dim: u16| -> Result<u32, &str> {
+        position.checked_add(run).filter(|&end| end <= dim as u32).ok_or("RLE pattern exceeds its size!")
+    };
+    for c in lines.flat_map(str::chars) {
+        if let Some(digit) = c.to_digit(10) {
+            count = count.checked_mul(10).and_then(|count| count.checked_add(digit)).ok_or("Invalid RLE run length!")?;
+            continue;
+        }
+        let run = count.max(1);
+        count = 0;
+        match c {
+            '!' => break,
+            '$' => (x, y) = (0, advance(y, run, y_dim)?),
+            'b' | '.' => x = advance(x, run, x_dim)?,
+            _ if c.is_ascii_alphabetic() => {
+                let end = advance(x, run, x_dim)?;
+                if y >= y_dim as u32 {
+                    return Err("RLE pattern exceeds its size!".into());
+                }
+                for cell_x in x..end {
+                    space.revive_cell(cell_x as u16, y as u16);
+                }
+                x = end;
+            }
+            _ if c.is_whitespace() => {}
+            _ => return Err(format!("Invalid RLE character '{}'!", c).into()),
+        }
+    }
+    space.save_state(space.displayed_time);
+    Ok(space)
+}
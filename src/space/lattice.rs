@@ -0,0 +1,256 @@
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+use super::automaton::get_cell_state;
+use super::values::{CellValues, ValueGrid};
+use super::Space;
+
+const OBSTACLE: u8 = 1 << 6;
+const HALF_SQRT_3: f32 = 0.866_025_4;
+
+// Lattice gas: every cell holds at most one particle per direction, kept as bits in the cell values.
+// A generation lets the particles of each cell collide, then moves them on to the neighbour they head to.
+// Particles leaving a bounded space or running into an obstacle bounce back the way they came.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GasModel {
+    Hpp, // square grid, head-on pairs turn by 90 degrees
+    Fhp, // hexagonal grid with every other row shifted by half a cell, head-on pairs turn by 60 degrees either way
+}
+
+impl GasModel {
+    pub const ALL: [GasModel; 2] = [GasModel::Hpp, GasModel::Fhp];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GasModel::Hpp => "hpp",
+            GasModel::Fhp => "fhp",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GasModel> {
+        GasModel::ALL.iter().find(|model| model.name() == name).copied()
+    }
+
+    pub fn get_num_directions(&self) -> u32 {
+        match self {
+            GasModel::Hpp => 4,
+            GasModel::Fhp => 6,
+        }
+    }
+
+    // Empty cells, cells with 1 to all particles and obstacles.
+    pub fn get_num_states(&self) -> u8 {
+        self.get_num_directions() as u8 + 2
+    }
+
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        match self {
+            GasModel::Hpp => &[(0., 0., 0.), (0.1, 0.2, 0.5), (0.2, 0.4, 0.8), (0.4, 0.7, 1.), (0.8, 0.95, 1.), (0.6, 0.45, 0.3)],
+            GasModel::Fhp => &[
+                (0., 0., 0.), (0.1, 0.15, 0.45), (0.15, 0.3, 0.65), (0.2, 0.45, 0.85),
+                (0.4, 0.65, 1.), (0.6, 0.8, 1.), (0.85, 0.95, 1.), (0.6, 0.45, 0.3),
+            ],
+        }
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        match self {
+            GasModel::Hpp => &["empty", "1 particle", "2 particles", "3 particles", "4 particles", "obstacle"],
+            GasModel::Fhp => &["empty", "1 particle", "2 particles", "3 particles", "4 particles", "5 particles", "6 particles", "obstacle"],
+        }
+    }
+
+    // Offset of the neighbour in the direction. HPP goes east, south, west, north. FHP goes east, south east,
+    // south west, west, north west, north east, where the diagonal neighbours depend on the row.
+    pub fn get_offset(&self, direction: u32, y: u16) -> (i32, i32) {
+        match self {
+            GasModel::Hpp => [(1, 0), (0, 1), (-1, 0), (0, -1)][direction as usize],
+            GasModel::Fhp if y.is_multiple_of(2) => [(1, 0), (0, 1), (-1, 1), (-1, 0), (-1, -1), (0, -1)][direction as usize],
+            GasModel::Fhp => [(1, 0), (1, 1), (0, 1), (-1, 0), (0, -1), (1, -1)][direction as usize],
+        }
+    }
+
+    pub fn get_velocity(&self, direction: u32) -> (f32, f32) {
+        match self {
+            GasModel::Hpp => [(1., 0.), (0., 1.), (-1., 0.), (0., -1.)][direction as usize],
+            GasModel::Fhp => [(1., 0.), (0.5, HALF_SQRT_3), (-0.5, HALF_SQRT_3), (-1., 0.), (-0.5, -HALF_SQRT_3), (0.5, -HALF_SQRT_3)][direction as usize],
+        }
+    }
+
+    // Particles only in the directions of the model.
+    pub fn is_valid_particles(&self, particles: u8) -> bool {
+        particles & !OBSTACLE < 1 << self.get_num_directions()
+    }
+
+    fn get_opposite(&self, direction: u32) -> u32 {
+        (direction + self.get_num_directions() / 2) % self.get_num_directions()
+    }
+
+    fn rotate(&self, particles: u8, steps: u32) -> u8 {
+        let num_directions = self.get_num_directions();
+        let mask = (1 << num_directions) - 1;
+        (particles << steps | particles >> (num_directions - steps)) & mask
+    }
+
+    // Particles of a cell after the collision, mass and momentum stay the same. FHP pairs turn clockwise
+    // if the coin is true. Obstacles send all particles back.
+    pub fn collide(&self, particles: u8, coin: bool) -> u8 {
+        let num_directions = self.get_num_directions();
+        if particles & OBSTACLE != 0 {
+            return OBSTACLE | self.rotate(particles & !OBSTACLE, num_directions / 2);
+        }
+        match self {
+            GasModel::Hpp => match particles {
+                0b0101 => 0b1010,
+                0b1010 => 0b0101,
+                _ => particles,
+            },
+            GasModel::Fhp => match particles {
+                0b010101 => 0b101010,
+                0b101010 => 0b010101,
+                0b001001 | 0b010010 | 0b100100 => self.rotate(particles, if coin { 1 } else { num_directions - 1 }),
+                _ => particles,
+            },
+        }
+    }
+}
+
+pub fn count_particles(particles: u8) -> u32 {
+    (particles & !OBSTACLE).count_ones()
+}
+
+// The state shows the number of particles, or the obstacle.
+fn get_particles_state(particles: u8, model: GasModel) -> u8 {
+    let index = if particles & OBSTACLE != 0 { model.get_num_states() - 1 } else { particles.count_ones() as u8 };
+    get_cell_state(index, model.get_num_states())
+}
+
+impl Space {
+    pub(crate) fn compute_lattice_gas_generation(&mut self, model: &GasModel) {
+        let model = *model;
+        let y_dim = self.y_dim() as usize;
+        let mut particles = self.take_particles();
+        // the coins of the FHP collisions are drawn up front from the seeded generator, so the result does not depend on the threads
+        let coins: Vec<bool> = match model {
+            GasModel::Hpp => vec![],
+            GasModel::Fhp => (0..self.x_dim() as usize * y_dim).map(|_| self.rng.gen()).collect(),
+        };
+        let collided: Vec<u8> = self.flat()
+            .par_iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                // killed cells have lost their particles
+                let cell_particles = if cell.get_state() == 0 { 0 } else { particles.get_value(cell.x, cell.y) };
+                model.collide(cell_particles, coins.get(index).copied().unwrap_or(false))
+            })
+            .collect();
+        let moved: Vec<u8> = self.flat()
+            .par_iter()
+            .enumerate()
+            .map(|(index, cell)| {
+                let mut cell_particles = collided[index] & OBSTACLE;
+                for direction in 0..model.get_num_directions() {
+                    let opposite = model.get_opposite(direction);
+                    let (dx, dy) = model.get_offset(opposite, cell.y);
+                    // particles arrive from the neighbour behind them, or bounce back from the edge
+                    let arrives = match self.get_neighbor_position(cell.x, cell.y, dx, dy) {
+                        Some((x, y)) => collided[x as usize * y_dim + y as usize] & 1 << direction != 0,
+                        None => collided[index] & 1 << opposite != 0,
+                    };
+                    if arrives {
+                        cell_particles |= 1 << direction;
+                    }
+                }
+                cell_particles
+            })
+            .collect();
+        for (cell, cell_particles) in self.flat_mut().into_iter().zip(moved) {
+            cell.set_state(get_particles_state(cell_particles, model));
+            particles.set_value(cell.x, cell.y, cell_particles);
+        }
+        self.values = CellValues::Particles(particles);
+    }
+
+    // Paints a state of the legend: an obstacle, or as many particles in random directions.
+    pub fn paint_lattice_gas(&mut self, x: u16, y: u16, state: u8, model: GasModel) {
+        let particles = if state >= model.get_num_states() - 1 {
+            OBSTACLE
+        } else {
+            let mut directions: Vec<u32> = (0..model.get_num_directions()).collect();
+            directions.shuffle(&mut self.rng);
+            directions.iter().take(state as usize).fold(0, |particles, direction| particles | 1 << direction)
+        };
+        self.set_particles(x, y, particles, model);
+    }
+
+    pub fn get_particles(&self, x: u16, y: u16) -> u8 {
+        match (&self.values, self.get_cell(x, y)) {
+            (CellValues::Particles(particles), Ok(cell)) if cell.get_state() > 0 => particles.get_value(x, y),
+            _ => 0,
+        }
+    }
+
+    pub fn set_particles(&mut self, x: u16, y: u16, particles: u8, model: GasModel) {
+        if x >= self.x_dim() || y >= self.y_dim() {
+            return;
+        }
+        let mut grid = self.take_particles();
+        grid.set_value(x, y, particles);
+        self.values = CellValues::Particles(grid);
+        self.get_cell_mut(x, y).unwrap().set_state(get_particles_state(particles, model));
+    }
+
+    pub fn get_num_particles(&self) -> usize {
+        self.flat().iter().map(|cell| count_particles(self.get_particles(cell.x, cell.y)) as usize).sum()
+    }
+
+    // Shows the particles in the cell states, cells without particles are empty.
+    pub(crate) fn show_particles(&mut self, model: GasModel) {
+        let particles = self.take_particles();
+        for cell in self.flat_mut() {
+            cell.set_state(get_particles_state(particles.get_value(cell.x, cell.y), model));
+        }
+        self.values = CellValues::Particles(particles);
+    }
+
+    // Random particles in every direction with the probability, for soups.
+    pub(crate) fn get_random_particles(&mut self, model: GasModel, probability: f64) -> u8 {
+        (0..model.get_num_directions()).filter(|_| self.rng.gen_bool(probability)).fold(0, |particles, direction| particles | 1 << direction)
+    }
+
+    // Mean velocity of the particles in square blocks of the space, as block centers in cells and
+    // velocities in cells per generation. Obstacles count as cells without particles.
+    pub fn get_velocity_field(&self, model: GasModel, block_size: u16) -> Vec<((f32, f32), (f32, f32))> {
+        let block_size = block_size.max(1);
+        let mut field = Vec::new();
+        for block_x in (0..self.x_dim()).step_by(block_size as usize) {
+            for block_y in (0..self.y_dim()).step_by(block_size as usize) {
+                let (x_end, y_end) = (block_x.saturating_add(block_size).min(self.x_dim()), block_y.saturating_add(block_size).min(self.y_dim()));
+                let mut momentum = (0., 0.);
+                for x in block_x..x_end {
+                    for y in block_y..y_end {
+                        let particles = self.get_particles(x, y);
+                        if particles & OBSTACLE != 0 {
+                            continue;
+                        }
+                        for direction in (0..model.get_num_directions()).filter(|direction| particles & 1 << direction != 0) {
+                            let velocity = model.get_velocity(direction);
+                            momentum = (momentum.0 + velocity.0, momentum.1 + velocity.1);
+                        }
+                    }
+                }
+                let num_cells = ((x_end - block_x) * (y_end - block_y)) as f32;
+                let center = ((block_x + x_end) as f32 / 2., (block_y + y_end) as f32 / 2.);
+                field.push((center, (momentum.0 / num_cells, momentum.1 / num_cells)));
+            }
+        }
+        field
+    }
+
+    fn take_particles(&mut self) -> ValueGrid<u8> {
+        match std::mem::take(&mut self.values) {
+            CellValues::Particles(particles) if (particles.x_dim(), particles.y_dim()) == (self.x_dim(), self.y_dim()) => particles,
+            _ => ValueGrid::new(self.x_dim(), self.y_dim()),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+use std::fmt;
+use rayon::prelude::*;
+use super::automaton::Automaton;
+use super::rule::{Boundary, RuleParseError};
+use super::Space;
+
+// Bits of a 2x2 block: top left, top right, bottom left, bottom right.
+const TOP_LEFT: u8 = 1;
+const TOP_RIGHT: u8 = 2;
+const BOTTOM_LEFT: u8 = 4;
+const BOTTOM_RIGHT: u8 = 8;
+const FULL_BLOCK: u8 = 15;
+
+pub const PRESETS: [&str; 3] = ["critters", "tron", "billiardball"];
+
+// Partitioning automaton: the space is cut into 2x2 blocks, shifted by one cell on every other generation,
+// and the table maps the cells of each block to new ones. Tables which are permutations are reversible.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BlockRule {
+    table: [u8; 16],
+}
+
+impl BlockRule {
+    pub fn new(table: [u8; 16]) -> Option<BlockRule> {
+        table.iter().all(|&block| block <= FULL_BLOCK).then_some(BlockRule { table })
+    }
+
+    fn from_fn(block_fn: impl Fn(u8) -> u8) -> BlockRule {
+        let mut table = [0; 16];
+        for (block, new_block) in table.iter_mut().enumerate() {
+            *new_block = block_fn(block as u8);
+        }
+        BlockRule { table }
+    }
+
+    // Blocks with two alive cells stay, the others are inverted and those with three alive cells also turned around.
+    pub fn critters() -> BlockRule {
+        BlockRule::from_fn(|block| match block.count_ones() {
+            2 => block,
+            3 => rotate_half(!block & FULL_BLOCK),
+            _ => !block & FULL_BLOCK,
+        })
+    }
+
+    // Empty and full blocks are inverted, the others stay.
+    pub fn tron() -> BlockRule {
+        BlockRule::from_fn(|block| match block {
+            0 => FULL_BLOCK,
+            FULL_BLOCK => 0,
+            _ => block,
+        })
+    }
+
+    // Single balls move on to the opposite corner, two balls meeting head on leave on the other diagonal.
+    pub fn billiard_ball() -> BlockRule {
+        BlockRule::from_fn(|block| match block {
+            _ if block.count_ones() == 1 => rotate_half(block),
+            0b1001 => 0b0110,
+            0b0110 => 0b1001,
+            _ => block,
+        })
+    }
+
+    pub fn from_name(name: &str) -> Option<BlockRule> {
+        match name {
+            "critters" => Some(BlockRule::critters()),
+            "tron" => Some(BlockRule::tron()),
+            "billiardball" => Some(BlockRule::billiard_ball()),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> Option<&'static str> {
+        PRESETS.iter().find(|name| BlockRule::from_name(name) == Some(*self)).copied()
+    }
+
+    // Accepts "margolus:critters" or the 16 new blocks, like "margolus:15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0".
+    pub fn parse(rulestring: &str) -> Result<BlockRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let parameters = rulestring.strip_prefix("margolus:").ok_or_else(error)?;
+        if let Some(rule) = BlockRule::from_name(parameters) {
+            return Ok(rule);
+        }
+        let blocks: Vec<u8> = parameters.split(',').map(|block| block.trim().parse().map_err(|_| error())).collect::<Result<_, _>>()?;
+        let table: [u8; 16] = blocks.try_into().map_err(|_| error())?;
+        BlockRule::new(table).ok_or_else(error)
+    }
+
+    pub fn get_block(&self, block: u8) -> u8 {
+        self.table[block as usize]
+    }
+
+    // Table undoing the rule, if every block has exactly one origin.
+    pub fn inverse(&self) -> Option<BlockRule> {
+        let mut table = [u8::MAX; 16];
+        for (block, &new_block) in self.table.iter().enumerate() {
+            if table[new_block as usize] != u8::MAX {
+                return None;
+            }
+            table[new_block as usize] = block as u8;
+        }
+        Some(BlockRule { table })
+    }
+
+    pub fn is_reversible(&self) -> bool {
+        self.inverse().is_some()
+    }
+}
+
+impl fmt::Display for BlockRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.name() {
+            Some(name) => write!(f, "margolus:{}", name),
+            None => {
+                let blocks: Vec<String> = self.table.iter().map(|block| block.to_string()).collect();
+                write!(f, "margolus:{}", blocks.join(","))
+            }
+        }
+    }
+}
+
+impl Space {
+    // Updates the blocks of the partition of the displayed time, odd times shift the blocks by one cell.
+    pub(crate) fn compute_block_generation(&mut self, rule: &BlockRule) {
+        let phase = (self.displayed_time % 2) as u16;
+        self.apply_block_rule(rule, phase);
+    }
+
+    // Computes the generation before the displayed one from the inverse of a reversible block rule.
+    // Returns false if the automaton is not reversible or the displayed time is the first one.
+    pub fn compute_previous_generation(&mut self) -> bool {
+        let Automaton::Margolus(rule) = self.automaton else {
+            return false;
+        };
+        let Some(inverse) = rule.inverse() else {
+            return false;
+        };
+        if self.displayed_time == 0 {
+            return false;
+        }
+        self.displayed_time -= 1;
+        let phase = (self.displayed_time % 2) as u16;
+        self.apply_block_rule(&inverse, phase);
+        true
+    }
+
+    fn apply_block_rule(&mut self, rule: &BlockRule, phase: u16) {
+        let block_positions = self.get_block_positions(phase);
+        let changes: Vec<((u16, u16), bool)> = block_positions
+            .par_iter()
+            .flat_map_iter(|positions| {
+                let block = positions.iter().enumerate()
+                    .filter(|(_, &(x, y))| self.check_cell_is_alive(x, y))
+                    .fold(0, |block, (bit, _)| block | 1 << bit);
+                let new_block = rule.get_block(block);
+                positions.iter().enumerate()
+                    .filter(move |(bit, _)| (block ^ new_block) & 1 << bit != 0)
+                    .map(move |(bit, &position)| (position, new_block & 1 << bit != 0))
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        for ((x, y), is_alive) in changes {
+            let cell = self.get_cell_mut(x, y).unwrap();
+            if is_alive {
+                cell.revive();
+            } else {
+                cell.kill();
+            }
+        }
+    }
+
+    // Cells of the blocks in the order of their bits. Blocks reaching over the edge wrap around a toroidal
+    // space of even size, otherwise the cells there are left out of the partition and stay as they are.
+    fn get_block_positions(&self, phase: u16) -> Vec<[(u16, u16); 4]> {
+        let (x_dim, y_dim) = (self.x_dim(), self.y_dim());
+        let wraps_x = self.boundary == Boundary::Toroidal && x_dim % 2 == 0;
+        let wraps_y = self.boundary == Boundary::Toroidal && y_dim % 2 == 0;
+        let mut blocks = Vec::new();
+        for y in (phase..y_dim).step_by(2) {
+            if y + 1 >= y_dim && !wraps_y {
+                continue;
+            }
+            for x in (phase..x_dim).step_by(2) {
+                if x + 1 >= x_dim && !wraps_x {
+                    continue;
+                }
+                let (right, bottom) = ((x + 1) % x_dim, (y + 1) % y_dim);
+                blocks.push([(x, y), (right, y), (x, bottom), (right, bottom)]);
+            }
+        }
+        blocks
+    }
+}
+
+fn rotate_half(block: u8) -> u8 {
+    let mut rotated = 0;
+    for (bit, opposite) in [(TOP_LEFT, BOTTOM_RIGHT), (TOP_RIGHT, BOTTOM_LEFT), (BOTTOM_LEFT, TOP_RIGHT), (BOTTOM_RIGHT, TOP_LEFT)] {
+        if block & bit != 0 {
+            rotated |= opposite;
+        }
+    }
+    rotated
+}
@@ -0,0 +1,103 @@
+use rayon::prelude::*;
+use super::cell::Cell;
+use super::Space;
+
+// Multi-state automata on the Moore neighbourhood, known by name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum NamedRule {
+    WireWorld,
+    BriansBrain,
+}
+
+const WIREWORLD_EMPTY: u8 = 0;
+const WIREWORLD_CONDUCTOR: u8 = 1;
+const WIREWORLD_TAIL: u8 = 2;
+const WIREWORLD_HEAD: u8 = 3;
+
+const BRIANS_BRAIN_OFF: u8 = 0;
+const BRIANS_BRAIN_DYING: u8 = 1;
+const BRIANS_BRAIN_ON: u8 = 2;
+
+impl NamedRule {
+    pub const ALL: [NamedRule; 2] = [NamedRule::WireWorld, NamedRule::BriansBrain];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            NamedRule::WireWorld => "wireworld",
+            NamedRule::BriansBrain => "briansbrain",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<NamedRule> {
+        NamedRule::ALL.iter().find(|rule| rule.name() == name).copied()
+    }
+
+    // Names of the states, the index is the state.
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        match self {
+            NamedRule::WireWorld => &["empty", "conductor", "tail", "head"],
+            NamedRule::BriansBrain => &["off", "dying", "on"],
+        }
+    }
+
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        match self {
+            NamedRule::WireWorld => &[(0., 0., 0.), (0.9, 0.6, 0.1), (0.9, 0.2, 0.1), (0.2, 0.5, 1.)],
+            NamedRule::BriansBrain => &[(0., 0., 0.), (0.1, 0.3, 0.9), (1., 1., 1.)],
+        }
+    }
+
+    pub fn get_num_states(&self) -> u8 {
+        self.get_state_names().len() as u8
+    }
+
+    pub fn get_next_state(&self, state: u8, neighbour_states: &[u8]) -> u8 {
+        match self {
+            NamedRule::WireWorld => match state {
+                WIREWORLD_HEAD => WIREWORLD_TAIL,
+                WIREWORLD_TAIL => WIREWORLD_CONDUCTOR,
+                WIREWORLD_CONDUCTOR => {
+                    let num_heads = neighbour_states.iter().filter(|&&neighbour_state| neighbour_state == WIREWORLD_HEAD).count();
+                    if num_heads == 1 || num_heads == 2 {
+                        WIREWORLD_HEAD
+                    } else {
+                        WIREWORLD_CONDUCTOR
+                    }
+                }
+                _ => WIREWORLD_EMPTY,
+            },
+            NamedRule::BriansBrain => match state {
+                BRIANS_BRAIN_ON => BRIANS_BRAIN_DYING,
+                BRIANS_BRAIN_DYING => BRIANS_BRAIN_OFF,
+                _ => {
+                    let num_on = neighbour_states.iter().filter(|&&neighbour_state| neighbour_state == BRIANS_BRAIN_ON).count();
+                    if num_on == 2 {
+                        BRIANS_BRAIN_ON
+                    } else {
+                        BRIANS_BRAIN_OFF
+                    }
+                }
+            },
+        }
+    }
+}
+
+impl Space {
+    pub(crate) fn compute_multi_state_generation(&mut self, rule: &NamedRule) {
+        let num_states = rule.get_num_states();
+        let changes: Vec<(u16, u16, u8)> = self.flat()
+            .par_iter()
+            .filter_map(|cell: &&Cell| {
+                let state = cell.get_state_index(num_states);
+                let neighbour_states: Vec<u8> = self.get_neighbors_vec(cell).iter()
+                    .map(|neighbor| neighbor.get_state_index(num_states))
+                    .collect();
+                let next_state = rule.get_next_state(state, &neighbour_states);
+                (next_state != state).then_some((cell.x, cell.y, next_state))
+            })
+            .collect();
+        for (x, y, state) in changes {
+            self.get_cell_mut(x, y).unwrap().set_state_index(state, num_states);
+        }
+    }
+}
@@ -0,0 +1,329 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Mutex, OnceLock};
+use super::rule::Rule;
+use super::Space;
+
+pub const MAX_PERIOD: usize = 30; // longest period detected by classify
+const OBJECT_DISTANCE: i32 = 2; // alive cells up to this Chebyshev distance belong to the same object
+
+const KNOWN_OBJECTS: [(&str, &[&str]); 20] = [
+    ("block", &["11", "11"]),
+    ("beehive", &["0110", "1001", "0110"]),
+    ("loaf", &["0110", "1001", "0101", "0010"]),
+    ("boat", &["110", "101", "010"]),
+    ("ship", &["110", "101", "011"]),
+    ("tub", &["010", "101", "010"]),
+    ("pond", &["0110", "1001", "1001", "0110"]),
+    ("long boat", &["1100", "1010", "0101", "0010"]),
+    ("barge", &["0100", "1010", "0101", "0010"]),
+    ("mango", &["01100", "10010", "01001", "00110"]),
+    ("eater", &["1100", "1010", "0010", "0011"]),
+    ("bi-block", &["11011", "11011"]),
+    ("blinker", &["111"]),
+    ("toad", &["0111", "1110"]),
+    ("beacon", &["1100", "1100", "0011", "0011"]),
+    ("traffic light", &["0011100", "0000000", "1000001", "1000001", "1000001", "0000000", "0011100"]),
+    ("pentadecathlon", &["0010000100", "1101111011", "0010000100"]),
+    ("glider", &["010", "001", "111"]),
+    ("lightweight spaceship", &["01001", "10000", "10001", "11110"]),
+    ("middleweight spaceship", &["000100", "010001", "100000", "100001", "111110"]),
+];
+
+// Alive cells of an object, moved so the bounding box starts at (0, 0) and sorted by rows.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Pattern {
+    cells: Vec<(u16, u16)>,
+}
+
+impl Pattern {
+    pub fn new(cells: &[(u16, u16)]) -> Pattern {
+        let x_min = cells.iter().map(|cell| cell.0).min().unwrap_or(0);
+        let y_min = cells.iter().map(|cell| cell.1).min().unwrap_or(0);
+        let mut cells: Vec<(u16, u16)> = cells.iter().map(|&(x, y)| (x - x_min, y - y_min)).collect();
+        cells.sort_by_key(|&(x, y)| (y, x));
+        cells.dedup();
+        Pattern { cells }
+    }
+
+    // Rows of 0 and 1 as in the space files, e.g. ["010", "001", "111"].
+    pub fn from_rows(rows: &[&str]) -> Pattern {
+        let mut cells = Vec::new();
+        for (y, row) in rows.iter().enumerate() {
+            for (x, c) in row.chars().enumerate() {
+                if c == '1' {
+                    cells.push((x as u16, y as u16));
+                }
+            }
+        }
+        Pattern::new(&cells)
+    }
+
+    pub fn get_cells(&self) -> &[(u16, u16)] {
+        &self.cells
+    }
+
+    pub fn width(&self) -> u16 {
+        self.cells.iter().map(|cell| cell.0 + 1).max().unwrap_or(0)
+    }
+
+    pub fn height(&self) -> u16 {
+        self.cells.iter().map(|cell| cell.1 + 1).max().unwrap_or(0)
+    }
+
+    // One of the 8 rotations and reflections of the square, 0 is the identity.
+    pub fn transformed(&self, transformation: usize) -> Pattern {
+        let (width, height) = (self.width(), self.height());
+        let cells: Vec<(u16, u16)> = self.cells.iter().map(|&(x, y)| {
+            let (mirror_x, mirror_y) = (width - 1 - x, height - 1 - y);
+            match transformation % 8 {
+                0 => (x, y),
+                1 => (mirror_y, x),
+                2 => (mirror_x, mirror_y),
+                3 => (y, mirror_x),
+                4 => (mirror_x, y),
+                5 => (x, mirror_y),
+                6 => (y, x),
+                _ => (mirror_y, mirror_x),
+            }
+        }).collect();
+        Pattern::new(&cells)
+    }
+
+    // Same pattern for all rotations and reflections.
+    pub fn canonical(&self) -> Pattern {
+        (0..8).map(|transformation| self.transformed(transformation)).min().unwrap()
+    }
+
+    pub fn to_rows(&self) -> Vec<String> {
+        let mut rows = vec![vec!['0'; self.width() as usize]; self.height() as usize];
+        for &(x, y) in &self.cells {
+            rows[y as usize][x as usize] = '1';
+        }
+        rows.iter().map(|row| row.iter().collect()).collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ObjectKind {
+    StillLife,
+    Oscillator { period: usize },
+    Spaceship { period: usize, dx: i32, dy: i32 }, // displacement per period
+    Unknown, // dies, grows or does not repeat within MAX_PERIOD generations
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Classification {
+    pub kind: ObjectKind,
+    pub key: Pattern, // smallest canonical pattern of all phases
+    pub name: Option<&'static str>, // only for known objects of Conway's Game of Life
+}
+
+impl Classification {
+    // Name of known objects, otherwise an apgsearch-like code, e.g. "xs7_0110/1001/0101/0011".
+    pub fn get_code(&self) -> String {
+        if let Some(name) = self.name {
+            return name.to_string();
+        }
+        let prefix = match self.kind {
+            ObjectKind::StillLife => format!("xs{}", self.key.get_cells().len()),
+            ObjectKind::Oscillator { period } => format!("xp{}", period),
+            ObjectKind::Spaceship { period, .. } => format!("xq{}", period),
+            ObjectKind::Unknown => "zz".to_string(),
+        };
+        format!("{}_{}", prefix, self.key.to_rows().join("/"))
+    }
+}
+
+impl ObjectKind {
+    pub fn name(&self) -> String {
+        match self {
+            ObjectKind::StillLife => "still life".to_string(),
+            ObjectKind::Oscillator { period } => format!("p{} oscillator", period),
+            ObjectKind::Spaceship { period, dx, dy } => format!("{} spaceship", get_velocity(*dx, *dy, *period)),
+            ObjectKind::Unknown => "unknown".to_string(),
+        }
+    }
+}
+
+// Speed and direction of a displacement per period, e.g. "c/4 diagonal", "c/2 orthogonal" or "(2,1)c/6".
+pub fn get_velocity(dx: i32, dy: i32, period: usize) -> String {
+    let (dx, dy) = (dx.unsigned_abs() as usize, dy.unsigned_abs() as usize);
+    let distance = dx.max(dy);
+    if distance == 0 {
+        return "stationary".to_string();
+    }
+    let direction = if dx == 0 || dy == 0 {
+        "orthogonal"
+    } else if dx == dy {
+        "diagonal"
+    } else {
+        return format!("({},{})c/{}", dx.max(dy), dx.min(dy), period);
+    };
+    let divisor = get_greatest_common_divisor(distance, period);
+    let (distance, period) = (distance / divisor, period / divisor);
+    if distance == 1 {
+        format!("c/{} {}", period, direction)
+    } else {
+        format!("{}c/{} {}", distance, period, direction)
+    }
+}
+
+fn get_greatest_common_divisor(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        get_greatest_common_divisor(b, a % b)
+    }
+}
+
+// Runs the pattern on its own until it repeats, possibly displaced.
+pub fn classify(pattern: &Pattern, rule: Rule) -> Classification {
+    let (kind, key) = evolve(pattern, rule);
+    let name = if rule == Rule::conways_game_of_life() {
+        get_known_objects().get(&key).copied()
+    } else {
+        None
+    };
+    Classification { kind, key, name }
+}
+
+fn evolve(pattern: &Pattern, rule: Rule) -> (ObjectKind, Pattern) {
+    let padding = MAX_PERIOD as u16 / 2 + 2;
+    let mut space = Space::new(pattern.width() + 2 * padding, pattern.height() + 2 * padding);
+    space.rule = rule;
+    for &(x, y) in pattern.get_cells() {
+        space.revive_cell(x + padding, y + padding);
+    }
+    let mut key = pattern.canonical();
+    for period in 1..=MAX_PERIOD {
+        space.compute_conways_game_of_life_single_threaded();
+        let alive_positions: Vec<(u16, u16)> = space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+        let touches_edge = alive_positions.iter().any(|&(x, y)| x == 0 || y == 0 || x == space.x_dim() - 1 || y == space.y_dim() - 1);
+        if alive_positions.is_empty() || touches_edge {
+            break;
+        }
+        let phase = Pattern::new(&alive_positions);
+        if phase == *pattern {
+            let dx = alive_positions.iter().map(|cell| cell.0).min().unwrap() as i32 - padding as i32;
+            let dy = alive_positions.iter().map(|cell| cell.1).min().unwrap() as i32 - padding as i32;
+            let kind = match (period, dx, dy) {
+                (1, 0, 0) => ObjectKind::StillLife,
+                (_, 0, 0) => ObjectKind::Oscillator { period },
+                _ => ObjectKind::Spaceship { period, dx, dy },
+            };
+            return (kind, key);
+        }
+        key = key.min(phase.canonical());
+    }
+    (ObjectKind::Unknown, pattern.canonical())
+}
+
+fn get_known_objects() -> &'static HashMap<Pattern, &'static str> {
+    static KNOWN_KEYS: OnceLock<HashMap<Pattern, &'static str>> = OnceLock::new();
+    KNOWN_KEYS.get_or_init(|| {
+        KNOWN_OBJECTS.iter()
+            .map(|(name, rows)| (evolve(&Pattern::from_rows(rows), Rule::conways_game_of_life()).1, *name))
+            .collect()
+    })
+}
+
+// Remembers the classification of every canonical pattern, so recurring objects are only run once.
+#[derive(Debug, Default)]
+pub struct Classifier {
+    cache: Mutex<HashMap<(Rule, Pattern), Classification>>,
+}
+
+impl Classifier {
+    pub fn new() -> Classifier {
+        Classifier::default()
+    }
+
+    pub fn classify(&self, cells: &[(u16, u16)], rule: Rule) -> Classification {
+        let pattern = Pattern::new(cells);
+        let cache_key = (rule, pattern.canonical());
+        if let Some(classification) = self.cache.lock().unwrap().get(&cache_key) {
+            return classification.clone();
+        }
+        let classification = classify(&pattern, rule);
+        self.cache.lock().unwrap().insert(cache_key, classification.clone());
+        classification
+    }
+}
+
+// Object of a space with the bounding box of its current phase.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpaceObject {
+    pub cells: Vec<(u16, u16)>,
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+    pub classification: Classification,
+}
+
+impl SpaceObject {
+    // Name of known objects, otherwise the kind, e.g. "p3 oscillator".
+    pub fn get_label(&self) -> String {
+        match self.classification.name {
+            Some(name) => name.to_string(),
+            None => self.classification.kind.name(),
+        }
+    }
+}
+
+impl Space {
+    // Separates the alive cells into objects and classifies them by the rule of the space.
+    // The bounding box of an object crossing the edge of a torus starts at its first cell and sticks out of the space.
+    pub fn get_objects(&self, classifier: &Classifier) -> Vec<SpaceObject> {
+        self.separate_unwrapped_objects().into_iter().map(|unwrapped_cells| {
+            let x_min = unwrapped_cells.iter().map(|cell| cell.0).min().unwrap();
+            let y_min = unwrapped_cells.iter().map(|cell| cell.1).min().unwrap();
+            let width = (unwrapped_cells.iter().map(|cell| cell.0).max().unwrap() - x_min + 1) as u16;
+            let height = (unwrapped_cells.iter().map(|cell| cell.1).max().unwrap() - y_min + 1) as u16;
+            let pattern_cells: Vec<(u16, u16)> = unwrapped_cells.iter().map(|&(x, y)| ((x - x_min) as u16, (y - y_min) as u16)).collect();
+            let classification = classifier.classify(&pattern_cells, self.rule);
+            let cells = unwrapped_cells.iter().map(|&(x, y)| self.wrap_position(x, y)).collect();
+            let (x, y) = self.wrap_position(x_min, y_min);
+            SpaceObject { cells, x, y, width, height, classification }
+        }).collect()
+    }
+
+    // Groups the alive cells into objects, cells up to OBJECT_DISTANCE apart belong to the same object.
+    pub fn separate_objects(&self) -> Vec<Vec<(u16, u16)>> {
+        self.separate_unwrapped_objects().into_iter()
+            .map(|unwrapped_cells| unwrapped_cells.iter().map(|&(x, y)| self.wrap_position(x, y)).collect())
+            .collect()
+    }
+
+    // Objects with positions relative to the first cell found, so objects crossing the edge of a torus stay in one piece.
+    // Their positions can be negative or beyond the size of the space.
+    fn separate_unwrapped_objects(&self) -> Vec<Vec<(i32, i32)>> {
+        let mut visited: HashSet<(u16, u16)> = HashSet::new();
+        let mut objects = Vec::new();
+        for cell in self.get_alive_cells() {
+            if !visited.insert((cell.x, cell.y)) {
+                continue;
+            }
+            let mut object = Vec::new();
+            let mut stack = vec![((cell.x, cell.y), (cell.x as i32, cell.y as i32))];
+            while let Some(((x, y), unwrapped_position)) = stack.pop() {
+                object.push(unwrapped_position);
+                for dx in -OBJECT_DISTANCE..=OBJECT_DISTANCE {
+                    for dy in -OBJECT_DISTANCE..=OBJECT_DISTANCE {
+                        if let Some(position) = self.get_neighbor_position(x, y, dx, dy) {
+                            if self.check_cell_is_alive(position.0, position.1) && visited.insert(position) {
+                                stack.push((position, (unwrapped_position.0 + dx, unwrapped_position.1 + dy)));
+                            }
+                        }
+                    }
+                }
+            }
+            objects.push(object);
+        }
+        objects
+    }
+
+    fn wrap_position(&self, x: i32, y: i32) -> (u16, u16) {
+        (x.rem_euclid(self.x_dim() as i32) as u16, y.rem_euclid(self.y_dim() as i32) as u16)
+    }
+}
@@ -0,0 +1,174 @@
+use std::fmt;
+use rayon::prelude::*;
+use super::continuous::{get_parameter, ContinuousGrid};
+use super::rule::{impl_eq_for_float_parameters, RuleParseError};
+use super::values::CellValues;
+use super::Space;
+
+const EMPTY_CONCENTRATIONS: (f32, f32) = (1., 0.);
+const PAINTED_CONCENTRATIONS: (f32, f32) = (0.5, 0.25);
+// Weights of the 3x3 Laplacian stencil: the center, orthogonal and diagonal neighbours.
+const LAPLACIAN_WEIGHTS: (f32, f32, f32) = (-1., 0.2, 0.05);
+
+pub const PRESETS: [(&str, &str); 3] = [
+    ("mitosis", "grayscott:F=0.0367,k=0.0649,Du=1,Dv=0.5,steps=10"),
+    ("coral", "grayscott:F=0.0545,k=0.062,Du=1,Dv=0.5,steps=10"),
+    ("spots", "grayscott:F=0.03,k=0.062,Du=1,Dv=0.5,steps=10"),
+];
+
+// Gray-Scott model: u + 2v -> 3v, u is fed at rate F and v is removed at rate F + k.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GrayScottRule {
+    pub feed: f32,
+    pub kill: f32,
+    pub diffusion_u: f32,
+    pub diffusion_v: f32,
+    pub steps: u8, // time steps of the model per generation
+}
+
+impl GrayScottRule {
+    // Accepts "grayscott:F=0.0367,k=0.0649,Du=1,Dv=0.5,steps=10".
+    pub fn parse(rulestring: &str) -> Result<GrayScottRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let parameters = rulestring.strip_prefix("grayscott:").ok_or_else(error)?;
+        let get = |key: &str| -> Result<f32, RuleParseError> {
+            get_parameter(parameters, key).and_then(|values| values.first().copied()).filter(|&value| value >= 0.).ok_or_else(error)
+        };
+        let steps = get("steps")?;
+        if !(1. ..=100.).contains(&steps) || steps.fract() != 0. {
+            return Err(error());
+        }
+        let rule = GrayScottRule { feed: get("F")?, kill: get("k")?, diffusion_u: get("Du")?, diffusion_v: get("Dv")?, steps: steps as u8 };
+        // the explicit update is only stable for diffusion rates up to 1 with this stencil
+        if rule.feed > 1. || rule.kill > 1. || rule.diffusion_u > 1. || rule.diffusion_v > 1. {
+            return Err(error());
+        }
+        Ok(rule)
+    }
+
+    pub fn get_change(&self, (u, v): (f32, f32), (laplacian_u, laplacian_v): (f32, f32)) -> (f32, f32) {
+        let reaction = u * v * v;
+        (
+            self.diffusion_u * laplacian_u - reaction + self.feed * (1. - u),
+            self.diffusion_v * laplacian_v + reaction - (self.feed + self.kill) * v,
+        )
+    }
+}
+
+impl fmt::Display for GrayScottRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "grayscott:F={},k={},Du={},Dv={},steps={}", self.feed, self.kill, self.diffusion_u, self.diffusion_v, self.steps)
+    }
+}
+
+impl_eq_for_float_parameters!(GrayScottRule, |rule| vec![rule.feed, rule.kill, rule.diffusion_u, rule.diffusion_v, rule.steps as f32]);
+
+// Chemical drawn with the colormap, u is drawn as 1 - u so the empty background stays dark.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Channel {
+    #[default]
+    V,
+    U,
+    Blend,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::V, Channel::U, Channel::Blend];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Channel::V => "v",
+            Channel::U => "u",
+            Channel::Blend => "blend",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Channel> {
+        Channel::ALL.iter().find(|channel| channel.name() == name).copied()
+    }
+
+    pub fn next(&self) -> Channel {
+        Channel::ALL[(Channel::ALL.iter().position(|channel| channel == self).unwrap() + 1) % Channel::ALL.len()]
+    }
+
+    pub fn get_value(&self, (u, v): (f32, f32)) -> f32 {
+        match self {
+            Channel::V => v,
+            Channel::U => 1. - u,
+            Channel::Blend => (v + 1. - u) / 2.,
+        }
+    }
+}
+
+impl Space {
+    // The concentrations are kept in the grids of the space, the cell states show v rounded to 255 steps.
+    pub(crate) fn compute_reaction_generation(&mut self, rule: &GrayScottRule) {
+        let x_dim = self.x_dim() as i32;
+        let (mut u, mut v) = self.take_chemicals();
+        for _ in 0..rule.steps {
+            let concentrations: Vec<(f32, f32)> = (0..u.values().len())
+                .into_par_iter()
+                .map(|index| {
+                    let (x, y) = (index as i32 % x_dim, index as i32 / x_dim);
+                    let center = (u.values()[index], v.values()[index]);
+                    let mut laplacian = (LAPLACIAN_WEIGHTS.0 * center.0, LAPLACIAN_WEIGHTS.0 * center.1);
+                    for (dx, dy) in [(-1, -1), (0, -1), (1, -1), (-1, 0), (1, 0), (-1, 1), (0, 1), (1, 1)] {
+                        let weight = if dx == 0 || dy == 0 { LAPLACIAN_WEIGHTS.1 } else { LAPLACIAN_WEIGHTS.2 };
+                        // a bounded space has no flux across its edges, outside neighbours take the center value
+                        let neighbor = match self.get_neighbor_position(x as u16, y as u16, dx, dy) {
+                            Some((neighbor_x, neighbor_y)) => (u.get_value(neighbor_x, neighbor_y), v.get_value(neighbor_x, neighbor_y)),
+                            None => center,
+                        };
+                        laplacian.0 += weight * neighbor.0;
+                        laplacian.1 += weight * neighbor.1;
+                    }
+                    let change = rule.get_change(center, laplacian);
+                    (center.0 + change.0, center.1 + change.1)
+                })
+                .collect();
+            for (index, (next_u, next_v)) in concentrations.into_iter().enumerate() {
+                let (x, y) = ((index as i32 % x_dim) as u16, (index as i32 / x_dim) as u16);
+                u.set_value(x, y, next_u);
+                v.set_value(x, y, next_v);
+            }
+        }
+        for cell in self.flat_mut() {
+            let state = (v.get_value(cell.x, cell.y) * 255.).round() as u8;
+            if state == 0 {
+                cell.kill();
+            } else {
+                cell.set_state(state);
+            }
+        }
+        self.values = CellValues::Chemicals(u, v);
+    }
+
+    // Adds some of chemical v to a cell, which starts the reaction around it.
+    pub fn paint_chemical(&mut self, x: u16, y: u16) {
+        if x >= self.x_dim() || y >= self.y_dim() {
+            return;
+        }
+        let (mut u, mut v) = self.take_chemicals();
+        u.set_value(x, y, PAINTED_CONCENTRATIONS.0);
+        v.set_value(x, y, PAINTED_CONCENTRATIONS.1);
+        self.get_cell_mut(x, y).unwrap().set_state((PAINTED_CONCENTRATIONS.1 * 255.).round() as u8);
+        self.values = CellValues::Chemicals(u, v);
+    }
+
+    // Concentrations of u and v at a position, the empty background has u = 1 and v = 0.
+    pub fn get_concentrations(&self, x: u16, y: u16) -> (f32, f32) {
+        match &self.values {
+            CellValues::Chemicals(u, v) => (u.get_value(x, y), v.get_value(x, y)),
+            _ => EMPTY_CONCENTRATIONS,
+        }
+    }
+
+    // Grids of the chemicals, new ones with the empty background if the space has none of its size.
+    fn take_chemicals(&mut self) -> (ContinuousGrid, ContinuousGrid) {
+        match std::mem::take(&mut self.values) {
+            CellValues::Chemicals(u, v) if (u.x_dim(), u.y_dim()) == (self.x_dim(), self.y_dim()) => (u, v),
+            _ => (ContinuousGrid::filled(self.x_dim(), self.y_dim(), EMPTY_CONCENTRATIONS.0), ContinuousGrid::new(self.x_dim(), self.y_dim())),
+        }
+    }
+}
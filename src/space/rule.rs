@@ -0,0 +1,277 @@
+use std::{error::Error, fmt};
+
+// Letters of Hensel's notation for 1 to 4 alive neighbours in their canonical order, each with one of its
+// configurations as the alive positions going around the cell, starting at an orthogonal neighbour.
+// 5 to 7 alive neighbours use the letters of the complementary configurations of 3 to 1.
+const HENSEL_LETTERS: [&[(char, &[usize])]; 5] = [
+    &[],
+    &[('c', &[1]), ('e', &[0])],
+    &[('c', &[1, 3]), ('e', &[0, 2]), ('k', &[0, 3]), ('a', &[0, 1]), ('i', &[0, 4]), ('n', &[1, 5])],
+    &[
+        ('c', &[1, 3, 5]), ('e', &[0, 2, 4]), ('k', &[0, 2, 5]), ('a', &[0, 1, 2]), ('i', &[0, 1, 7]),
+        ('n', &[0, 1, 3]), ('y', &[0, 3, 5]), ('q', &[0, 1, 5]), ('j', &[0, 1, 6]), ('r', &[0, 1, 4]),
+    ],
+    &[
+        ('c', &[1, 3, 5, 7]), ('e', &[0, 2, 4, 6]), ('k', &[0, 1, 3, 6]), ('a', &[0, 1, 2, 3]), ('i', &[0, 1, 3, 4]),
+        ('n', &[0, 1, 3, 7]), ('y', &[0, 1, 3, 5]), ('q', &[0, 1, 2, 5]), ('j', &[0, 1, 4, 6]), ('r', &[0, 1, 2, 4]),
+        ('t', &[0, 1, 4, 7]), ('w', &[0, 1, 5, 6]), ('z', &[0, 1, 4, 5]),
+    ],
+];
+
+// Life-like rules known by name. In the majority vote a cell takes the state of most of the nine cells
+// around and including it, Vichniac's twisted majority (anneal) swaps the outcomes of 4 and 5 alive cells,
+// which lets the boundaries between the domains move and straighten out.
+pub const NAMED_RULES: [(&str, &str); 3] = [("life", "B3/S23"), ("majority", "B5678/S45678"), ("anneal", "B4678/S35678")];
+
+// Set of the 256 configurations of alive neighbours, bit i of a configuration is the i-th neighbour around the cell.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+struct NeighborhoodSet([u64; 4]);
+
+impl NeighborhoodSet {
+    fn insert(&mut self, neighborhood: u8) {
+        self.0[neighborhood as usize / 64] |= 1 << (neighborhood % 64);
+    }
+
+    fn contains(&self, neighborhood: u8) -> bool {
+        self.0[neighborhood as usize / 64] & 1 << (neighborhood % 64) != 0
+    }
+}
+
+// Life-like rule, looked up by the configuration of alive neighbours, so isotropic non-totalistic rules can tell
+// apart configurations with the same number of alive neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rule {
+    birth: NeighborhoodSet,
+    survival: NeighborhoodSet,
+}
+
+impl Rule {
+    pub fn new(birth: &[usize], survival: &[usize]) -> Rule {
+        let mut rule = Rule { birth: NeighborhoodSet::default(), survival: NeighborhoodSet::default() };
+        for neighborhood in 0..=255u8 {
+            let num_alive_neighbors = neighborhood.count_ones() as usize;
+            if birth.contains(&num_alive_neighbors) {
+                rule.birth.insert(neighborhood);
+            }
+            if survival.contains(&num_alive_neighbors) {
+                rule.survival.insert(neighborhood);
+            }
+        }
+        rule
+    }
+
+    pub fn conways_game_of_life() -> Rule {
+        Rule::new(&[3], &[2, 3])
+    }
+
+    // Accepts "B3/S23" as well as the older survival/birth notation "23/3". Numbers of neighbours may be
+    // narrowed down with Hensel's letters, like "B2-a/S12" or "B3/S23-a4i". Named rules like "anneal" work too.
+    pub fn parse(rulestring: &str) -> Result<Rule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        if let Some((_, named_rulestring)) = NAMED_RULES.iter().find(|(name, _)| name.eq_ignore_ascii_case(rulestring)) {
+            return Rule::parse(named_rulestring);
+        }
+        let parts: Vec<&str> = rulestring.split('/').collect();
+        if parts.len() != 2 {
+            return Err(RuleParseError::new(rulestring));
+        }
+        let (birth_str, survival_str) = if parts[0].starts_with(['B', 'b']) && parts[1].starts_with(['S', 's']) {
+            (&parts[0][1..], &parts[1][1..])
+        } else if parts[0].starts_with(['S', 's']) && parts[1].starts_with(['B', 'b']) {
+            (&parts[1][1..], &parts[0][1..])
+        } else {
+            (parts[1], parts[0])
+        };
+        let birth = Self::parse_neighborhoods(birth_str).ok_or(RuleParseError::new(rulestring))?;
+        let survival = Self::parse_neighborhoods(survival_str).ok_or(RuleParseError::new(rulestring))?;
+        Ok(Rule { birth, survival })
+    }
+
+    // Digits, each optionally followed by the letters it is restricted to or, after a minus, the letters it excludes.
+    fn parse_neighborhoods(neighborhoods_str: &str) -> Option<NeighborhoodSet> {
+        let mut neighborhoods = NeighborhoodSet::default();
+        let mut chars = neighborhoods_str.chars().peekable();
+        while let Some(digit) = chars.next() {
+            let num_alive_neighbors = digit.to_digit(10).filter(|&d| d <= 8)? as usize;
+            let is_excluding = chars.next_if_eq(&'-').is_some();
+            let mut letters = Vec::new();
+            while let Some(letter) = chars.next_if(|c| c.is_ascii_lowercase()) {
+                get_letter_neighborhoods(num_alive_neighbors, letter)?;
+                letters.push(letter);
+            }
+            if is_excluding && letters.is_empty() {
+                return None;
+            }
+            for (letter, _) in get_letters(num_alive_neighbors) {
+                if letters.is_empty() || letters.contains(letter) != is_excluding {
+                    for neighborhood in get_letter_neighborhoods(num_alive_neighbors, *letter)? {
+                        neighborhoods.insert(neighborhood);
+                    }
+                }
+            }
+            if num_alive_neighbors == 0 || num_alive_neighbors == 8 {
+                neighborhoods.insert(if num_alive_neighbors == 0 { 0 } else { 255 });
+            }
+        }
+        Some(neighborhoods)
+    }
+
+    // Whether a dead cell with the alive neighbours of the bitmask is born.
+    pub fn is_born_with(&self, neighborhood: u8) -> bool {
+        self.birth.contains(neighborhood)
+    }
+
+    pub fn survives_with(&self, neighborhood: u8) -> bool {
+        self.survival.contains(neighborhood)
+    }
+
+    // Whether cells are born with any configuration of the number of alive neighbours.
+    pub fn is_born(&self, num_alive_neighbors: usize) -> bool {
+        (0..=255).any(|neighborhood: u8| neighborhood.count_ones() as usize == num_alive_neighbors && self.is_born_with(neighborhood))
+    }
+
+    pub fn survives(&self, num_alive_neighbors: usize) -> bool {
+        (0..=255).any(|neighborhood: u8| neighborhood.count_ones() as usize == num_alive_neighbors && self.survives_with(neighborhood))
+    }
+
+    fn write_neighborhoods(f: &mut fmt::Formatter, neighborhoods: &NeighborhoodSet) -> fmt::Result {
+        for num_alive_neighbors in 0..=8 {
+            let letters = get_letters(num_alive_neighbors);
+            if letters.is_empty() {
+                if neighborhoods.contains(((1u16 << num_alive_neighbors) - 1) as u8) {
+                    write!(f, "{}", num_alive_neighbors)?;
+                }
+                continue;
+            }
+            // isotropic rules contain all configurations of a letter or none
+            let (included, excluded): (Vec<char>, Vec<char>) = letters.iter()
+                .map(|(letter, _)| *letter)
+                .partition(|&letter| neighborhoods.contains(get_letter_neighborhoods(num_alive_neighbors, letter).unwrap()[0]));
+            if included.is_empty() {
+                continue;
+            }
+            write!(f, "{}", num_alive_neighbors)?;
+            if excluded.is_empty() {
+                continue;
+            }
+            if included.len() <= excluded.len() {
+                write!(f, "{}", included.iter().collect::<String>())?;
+            } else {
+                write!(f, "-{}", excluded.iter().collect::<String>())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Default for Rule {
+    fn default() -> Rule {
+        Rule::conways_game_of_life()
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "B")?;
+        Rule::write_neighborhoods(f, &self.birth)?;
+        write!(f, "/S")?;
+        Rule::write_neighborhoods(f, &self.survival)
+    }
+}
+
+fn get_letters(num_alive_neighbors: usize) -> &'static [(char, &'static [usize])] {
+    match num_alive_neighbors {
+        0..=4 => HENSEL_LETTERS[num_alive_neighbors],
+        5..=8 => HENSEL_LETTERS[8 - num_alive_neighbors],
+        _ => &[],
+    }
+}
+
+// Bitmasks of all rotations and reflections of the configuration of a letter.
+fn get_letter_neighborhoods(num_alive_neighbors: usize, letter: char) -> Option<Vec<u8>> {
+    let (_, positions) = get_letters(num_alive_neighbors).iter().find(|(known_letter, _)| *known_letter == letter)?;
+    let mask = positions.iter().fold(0u8, |mask, position| mask | 1 << position);
+    let mask = if num_alive_neighbors > 4 { !mask } else { mask };
+    let mut neighborhoods = Vec::new();
+    for is_reflected in [false, true] {
+        for rotation in (0..8).step_by(2) {
+            let neighborhood = (0..8)
+                .filter(|position| mask & 1 << position != 0)
+                .map(|position| if is_reflected { (8 - position) % 8 } else { position })
+                .fold(0u8, |neighborhood, position| neighborhood | 1 << ((position + rotation) % 8));
+            if !neighborhoods.contains(&neighborhood) {
+                neighborhoods.push(neighborhood);
+            }
+        }
+    }
+    Some(neighborhoods)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Boundary {
+    #[default]
+    Bounded,
+    Toroidal,
+}
+
+impl Boundary {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Boundary::Bounded => "bounded",
+            Boundary::Toroidal => "toroidal",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Boundary> {
+        match name {
+            "bounded" => Some(Boundary::Bounded),
+            "toroidal" => Some(Boundary::Toroidal),
+            _ => None,
+        }
+    }
+
+    pub fn next(&self) -> Boundary {
+        match self {
+            Boundary::Bounded => Boundary::Toroidal,
+            Boundary::Toroidal => Boundary::Bounded,
+        }
+    }
+}
+
+// Eq and Hash for rules with float parameters, given as a closure listing them. The parameters are finite,
+// so the rules are Eq. The hash takes the bits of the parameters, with -0 as 0 as the two compare equal.
+macro_rules! impl_eq_for_float_parameters {
+    ($rule:ty, $get_parameters:expr) => {
+        impl Eq for $rule {}
+
+        impl std::hash::Hash for $rule {
+            fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+                let get_parameters: fn(&$rule) -> Vec<f32> = $get_parameters;
+                for parameter in get_parameters(self) {
+                    (parameter + 0.).to_bits().hash(state);
+                }
+            }
+        }
+    };
+}
+
+pub(crate) use impl_eq_for_float_parameters;
+
+#[derive(Debug)]
+pub struct RuleParseError {
+    pub message: String,
+}
+
+impl RuleParseError {
+    pub(crate) fn new(rulestring: &str) -> RuleParseError {
+        RuleParseError { message: format!("Invalid rule '{}'!", rulestring) }
+    }
+}
+
+impl fmt::Display for RuleParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl Error for RuleParseError {}
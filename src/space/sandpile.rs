@@ -0,0 +1,261 @@
+use std::collections::BTreeMap;
+use std::{error::Error, fmt, fs, path::Path};
+use rand::Rng;
+use super::cell::Cell;
+use super::continuous::get_parameter;
+use super::rule::RuleParseError;
+use super::values::{CellValues, ValueGrid};
+use super::Space;
+
+const THRESHOLD: u32 = 4;
+const NUM_STATES: u8 = 5; // the stable heights 0 to 3 and toppling cells
+const TOPPLE_OFFSETS: [(i32, i32); 4] = [(0, -1), (1, 0), (0, 1), (-1, 0)];
+const MAX_GRAINS: u32 = 100_000;
+
+pub const PRESETS: [(&str, &str); 2] = [("sandpile", "sandpile:random,n=1"), ("sandpile pile", "sandpile:center,n=50")];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum GrainDrop {
+    Random,
+    Center,
+}
+
+impl GrainDrop {
+    pub const ALL: [GrainDrop; 2] = [GrainDrop::Random, GrainDrop::Center];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            GrainDrop::Random => "random",
+            GrainDrop::Center => "center",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GrainDrop> {
+        GrainDrop::ALL.iter().find(|drop| drop.name() == name).copied()
+    }
+}
+
+// Abelian sandpile of Bak, Tang and Wiesenfeld: every generation drops n grains one after the other,
+// cells holding four or more grains topple one to each orthogonal neighbour until the pile is stable.
+// Grains toppled over the edge are lost whatever the boundary, so every avalanche comes to an end.
+// The grains are kept in the cell values, the state shows the height up to toppling.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SandpileRule {
+    pub drop: GrainDrop,
+    pub grains: u32,
+}
+
+impl SandpileRule {
+    // Accepts "sandpile:random,n=1" or "sandpile:center,n=50".
+    pub fn parse(rulestring: &str) -> Result<SandpileRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let parameters = rulestring.strip_prefix("sandpile:").ok_or_else(error)?;
+        let (drop, parameters) = parameters.split_once(',').unwrap_or((parameters, "n=1"));
+        let grains = get_parameter(parameters, "n").and_then(|values| values.first().copied()).ok_or_else(error)?;
+        if !(0. ..=MAX_GRAINS as f32).contains(&grains) || grains.fract() != 0. {
+            return Err(error());
+        }
+        Ok(SandpileRule { drop: GrainDrop::from_name(drop).ok_or_else(error)?, grains: grains as u32 })
+    }
+
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        &[(0., 0., 0.), (0.15, 0.3, 0.7), (0.3, 0.7, 0.9), (0.95, 0.85, 0.3), (1., 0.2, 0.1)]
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        &["0 grains", "1 grain", "2 grains", "3 grains", "toppling"]
+    }
+}
+
+impl fmt::Display for SandpileRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "sandpile:{},n={}", self.drop.name(), self.grains)
+    }
+}
+
+// Heights of four and more grains all show as toppling.
+fn get_state_index(height: u32) -> u8 {
+    height.min(THRESHOLD) as u8
+}
+
+// Stored height of a cell, unless its state was edited to show another one.
+fn get_cell_height(cell: &Cell, stored: u32) -> u32 {
+    let state_index = cell.get_state_index(NUM_STATES);
+    if get_state_index(stored) == state_index { stored } else { state_index as u32 }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct AvalancheGeneration {
+    pub(crate) avalanches: Vec<(usize, usize)>, // size and duration of every avalanche
+    pub(crate) num_drops: usize,
+}
+
+// Distributions of the avalanches started by single grains: the size is the number of topplings,
+// the duration the number of waves in which all unstable cells topple at once. Grains which topple
+// nothing are counted as drops but not as avalanches. The drops since the last saved state are kept
+// apart and go to the generation it is saved as, so the statistics follow the history.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AvalancheStatistics {
+    pub(crate) generations: BTreeMap<usize, AvalancheGeneration>,
+    pending: AvalancheGeneration,
+}
+
+impl AvalancheStatistics {
+    // Number of avalanches up to the time per size.
+    pub fn get_size_histogram(&self, time: usize) -> BTreeMap<usize, usize> {
+        self.get_histogram(time, |&(size, _)| size)
+    }
+
+    pub fn get_duration_histogram(&self, time: usize) -> BTreeMap<usize, usize> {
+        self.get_histogram(time, |&(_, duration)| duration)
+    }
+
+    pub fn get_num_avalanches(&self, time: usize) -> usize {
+        self.generations.range(..=time).map(|(_, generation)| generation.avalanches.len()).sum()
+    }
+
+    pub fn get_num_drops(&self, time: usize) -> usize {
+        self.generations.range(..=time).map(|(_, generation)| generation.num_drops).sum()
+    }
+
+    pub fn get_largest_size(&self, time: usize) -> usize {
+        self.generations.range(..=time).flat_map(|(_, generation)| &generation.avalanches).map(|&(size, _)| size).max().unwrap_or(0)
+    }
+
+    // Both distributions in one table, with rows like "size,12,3" and "duration,4,7".
+    pub fn write_histogram_csv(&self, file_str: &str, time: usize) -> Result<(), Box<dyn Error>> {
+        let mut content = String::from("measure,value,count\n");
+        for (measure, histogram) in [("size", self.get_size_histogram(time)), ("duration", self.get_duration_histogram(time))] {
+            for (value, count) in histogram {
+                content.push_str(&format!("{},{},{}\n", measure, value, count));
+            }
+        }
+        fs::write(Path::new(file_str), content)?;
+        Ok(())
+    }
+
+    // Generations saved before at the time or later are replaced.
+    pub(crate) fn save(&mut self, time: usize) {
+        self.generations.retain(|&generation_time, _| generation_time < time);
+        let pending = std::mem::take(&mut self.pending);
+        if pending.num_drops > 0 {
+            self.generations.insert(time, pending);
+        }
+    }
+
+    fn get_histogram(&self, time: usize, measure: impl Fn(&(usize, usize)) -> usize) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for avalanche in self.generations.range(..=time).flat_map(|(_, generation)| &generation.avalanches) {
+            *histogram.entry(measure(avalanche)).or_insert(0) += 1;
+        }
+        histogram
+    }
+}
+
+impl Space {
+    pub(crate) fn compute_sandpile_generation(&mut self, rule: &SandpileRule) {
+        let mut heights = self.take_heights();
+        // grains dropped on the space while it was edited topple first
+        let unstable = (0..self.x_dim()).flat_map(|x| (0..self.y_dim()).map(move |y| (x, y))).collect();
+        relax(&mut heights, unstable);
+        for _ in 0..rule.grains {
+            let (x, y) = match rule.drop {
+                GrainDrop::Random => (self.rng.gen_range(0..self.x_dim()), self.rng.gen_range(0..self.y_dim())),
+                GrainDrop::Center => (self.x_dim() / 2, self.y_dim() / 2),
+            };
+            self.add_grain(&mut heights, x, y);
+        }
+        self.put_heights(heights);
+    }
+
+    // Adds a grain to the cell and lets the pile topple until it is stable again.
+    pub fn drop_grain(&mut self, x: u16, y: u16) {
+        if x >= self.x_dim() || y >= self.y_dim() {
+            return;
+        }
+        let mut heights = self.take_heights();
+        self.add_grain(&mut heights, x, y);
+        self.put_heights(heights);
+    }
+
+    pub fn get_height(&self, x: u16, y: u16) -> u32 {
+        let Ok(cell) = self.get_cell(x, y) else {
+            return 0;
+        };
+        match &self.values {
+            CellValues::Heights(heights) => get_cell_height(cell, heights.get_value(x, y)),
+            _ => get_cell_height(cell, 0),
+        }
+    }
+
+    // Sets the height without toppling, the pile relaxes with the next generation.
+    pub fn set_height(&mut self, x: u16, y: u16, height: u32) {
+        if x >= self.x_dim() || y >= self.y_dim() {
+            return;
+        }
+        let mut heights = self.take_heights();
+        heights.set_value(x, y, height);
+        self.put_heights(heights);
+    }
+
+    fn add_grain(&mut self, heights: &mut ValueGrid<u32>, x: u16, y: u16) {
+        heights.set_value(x, y, heights.get_value(x, y).saturating_add(1));
+        let (size, duration) = relax(heights, vec![(x, y)]);
+        let pending = &mut self.avalanche_statistics.pending;
+        pending.num_drops += 1;
+        if size > 0 {
+            pending.avalanches.push((size, duration));
+        }
+    }
+
+    // The heights of the cell values, those of cells painted or filled since they were stored come from the states.
+    fn take_heights(&mut self) -> ValueGrid<u32> {
+        let mut heights = match std::mem::take(&mut self.values) {
+            CellValues::Heights(heights) if (heights.x_dim(), heights.y_dim()) == (self.x_dim(), self.y_dim()) => heights,
+            _ => ValueGrid::new(self.x_dim(), self.y_dim()),
+        };
+        for cell in self.flat() {
+            heights.set_value(cell.x, cell.y, get_cell_height(cell, heights.get_value(cell.x, cell.y)));
+        }
+        heights
+    }
+
+    fn put_heights(&mut self, heights: ValueGrid<u32>) {
+        for cell in self.flat_mut() {
+            cell.set_state_index(get_state_index(heights.get_value(cell.x, cell.y)), NUM_STATES);
+        }
+        self.values = CellValues::Heights(heights);
+    }
+}
+
+// Topples all unstable cells of a wave at once, as often as their grains allow, which gives the same
+// stable pile as single topplings in any order. Returns the number of topplings and waves.
+fn relax(heights: &mut ValueGrid<u32>, mut unstable: Vec<(u16, u16)>) -> (usize, usize) {
+    let (x_dim, y_dim) = (heights.x_dim() as i32, heights.y_dim() as i32);
+    unstable.retain(|&(x, y)| heights.get_value(x, y) >= THRESHOLD);
+    let (mut size, mut duration) = (0, 0);
+    while !unstable.is_empty() {
+        duration += 1;
+        let topplings: Vec<(u16, u16, u32)> = unstable.iter().map(|&(x, y)| (x, y, heights.get_value(x, y) / THRESHOLD)).collect();
+        let mut touched = Vec::with_capacity(topplings.len() * 5);
+        for (x, y, num_topplings) in topplings {
+            heights.set_value(x, y, heights.get_value(x, y) - num_topplings * THRESHOLD);
+            size += num_topplings as usize;
+            touched.push((x, y));
+            for (dx, dy) in TOPPLE_OFFSETS {
+                let (neighbor_x, neighbor_y) = (x as i32 + dx, y as i32 + dy);
+                if neighbor_x < 0 || neighbor_y < 0 || neighbor_x >= x_dim || neighbor_y >= y_dim {
+                    continue;
+                }
+                let (neighbor_x, neighbor_y) = (neighbor_x as u16, neighbor_y as u16);
+                heights.set_value(neighbor_x, neighbor_y, heights.get_value(neighbor_x, neighbor_y).saturating_add(num_topplings));
+                touched.push((neighbor_x, neighbor_y));
+            }
+        }
+        touched.sort_unstable();
+        touched.dedup();
+        unstable = touched.into_iter().filter(|&(x, y)| heights.get_value(x, y) >= THRESHOLD).collect();
+    }
+    (size, duration)
+}
@@ -0,0 +1,140 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+use super::cell::Cell;
+use super::continuous::get_parameter;
+use super::rule::{impl_eq_for_float_parameters, RuleParseError};
+use super::Space;
+
+const NUM_STATES: u8 = 3;
+const EMPTY: u8 = 0;
+const NEIGHBOR_OFFSETS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+pub const PRESETS: [(&str, &str); 2] = [("schelling", "schelling:t=0.375"), ("schelling tolerant", "schelling:t=0.25")];
+
+// Schelling's segregation model: agents of two populations are unhappy if fewer than the tolerance of their
+// occupied neighbours belong to their own population. Every generation all unhappy agents move, one after
+// the other in random order, to random empty cells. Agents without neighbours are happy.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SchellingRule {
+    pub tolerance: f32,
+}
+
+impl SchellingRule {
+    // Accepts "schelling:t=0.375".
+    pub fn parse(rulestring: &str) -> Result<SchellingRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let parameters = rulestring.strip_prefix("schelling:").ok_or_else(error)?;
+        let tolerance = get_parameter(parameters, "t").and_then(|values| values.first().copied()).filter(|value| (0. ..=1.).contains(value));
+        Ok(SchellingRule { tolerance: tolerance.ok_or_else(error)? })
+    }
+
+    pub fn get_palette(&self) -> &'static [(f32, f32, f32)] {
+        &[(0., 0., 0.), (0.9, 0.3, 0.2), (0.2, 0.5, 0.9)]
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        &["empty", "red", "blue"]
+    }
+}
+
+impl fmt::Display for SchellingRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "schelling:t={}", self.tolerance)
+    }
+}
+
+impl_eq_for_float_parameters!(SchellingRule, |rule| vec![rule.tolerance]);
+
+// Segregation index and number of moving agents per generation, kept by time so stepping through the
+// history shows the values of the displayed generation.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SegregationStatistics {
+    pub(crate) indices: BTreeMap<usize, f32>,
+    pub(crate) moves: BTreeMap<usize, usize>,
+}
+
+impl SegregationStatistics {
+    pub fn get_index(&self, time: usize) -> Option<f32> {
+        self.indices.get(&time).copied()
+    }
+
+    pub fn get_indices(&self) -> &BTreeMap<usize, f32> {
+        &self.indices
+    }
+
+    pub fn get_moves(&self, time: usize) -> Option<usize> {
+        self.moves.get(&time).copied()
+    }
+}
+
+impl Space {
+    pub(crate) fn compute_schelling_generation(&mut self, rule: &SchellingRule) {
+        let time = self.displayed_time;
+        if let Some(index) = self.get_segregation_index() {
+            self.segregation_statistics.indices.insert(time, index);
+        }
+        let mut unhappy: Vec<(u16, u16)> = self.flat()
+            .par_iter()
+            .filter(|cell: &&&Cell| {
+                let (num_like, num_occupied) = self.count_like_neighbors(cell);
+                num_occupied > 0 && (num_like as f32) < rule.tolerance * num_occupied as f32
+            })
+            .map(|cell| (cell.x, cell.y))
+            .collect();
+        let mut empty: Vec<(u16, u16)> = self.flat().iter()
+            .filter(|cell| cell.get_state_index(NUM_STATES) == EMPTY)
+            .map(|cell| (cell.x, cell.y))
+            .collect();
+        unhappy.shuffle(&mut self.rng);
+        let num_moves = if empty.is_empty() { 0 } else { unhappy.len() };
+        if !empty.is_empty() {
+            for (x, y) in unhappy {
+                // the agent takes a random empty cell and leaves its own one empty for the next agents
+                let target = self.rng.gen_range(0..empty.len());
+                let (target_x, target_y) = std::mem::replace(&mut empty[target], (x, y));
+                let population = self.get_cell(x, y).unwrap().get_state_index(NUM_STATES);
+                self.get_cell_mut(x, y).unwrap().kill();
+                self.get_cell_mut(target_x, target_y).unwrap().set_state_index(population, NUM_STATES);
+            }
+        }
+        let statistics = &mut self.segregation_statistics;
+        // generations computed before from another state are replaced
+        statistics.indices.retain(|&index_time, _| index_time <= time);
+        statistics.moves.retain(|&move_time, _| move_time <= time);
+        statistics.moves.insert(time + 1, num_moves);
+        if let Some(index) = self.get_segregation_index() {
+            self.segregation_statistics.indices.insert(time + 1, index);
+        }
+    }
+
+    // Share of the occupied neighbours of the same population, averaged over the agents with neighbours.
+    // Well mixed populations of the same size give about one half, segregated ones almost one.
+    pub fn get_segregation_index(&self) -> Option<f32> {
+        let shares: Vec<f32> = self.flat()
+            .par_iter()
+            .filter(|cell| cell.get_state_index(NUM_STATES) != EMPTY)
+            .filter_map(|cell| {
+                let (num_like, num_occupied) = self.count_like_neighbors(cell);
+                (num_occupied > 0).then(|| num_like as f32 / num_occupied as f32)
+            })
+            .collect();
+        (!shares.is_empty()).then(|| shares.iter().sum::<f32>() / shares.len() as f32)
+    }
+
+    // Neighbours of the population of the cell and occupied neighbours, nothing for empty cells.
+    fn count_like_neighbors(&self, cell: &Cell) -> (usize, usize) {
+        let population = cell.get_state_index(NUM_STATES);
+        if population == EMPTY {
+            return (0, 0);
+        }
+        NEIGHBOR_OFFSETS.iter()
+            .filter_map(|&(dx, dy)| self.get_neighbor_position(cell.x, cell.y, dx, dy))
+            .map(|(x, y)| self.get_cell(x, y).unwrap().get_state_index(NUM_STATES))
+            .filter(|&neighbor| neighbor != EMPTY)
+            .fold((0, 0), |(num_like, num_occupied), neighbor| (num_like + (neighbor == population) as usize, num_occupied + 1))
+    }
+}
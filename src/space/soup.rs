@@ -0,0 +1,147 @@
+use rand::Rng;
+use super::automaton::{get_cell_state, Automaton};
+use super::Space;
+
+// Symmetry of a random soup, named after the symmetry groups used by apgsearch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Symmetry {
+    #[default]
+    C1, // none
+    C2, // 180 degree rotation
+    C4, // 90 degree rotation
+    D2, // mirrored at the vertical axis
+    D4, // mirrored at both axes
+    D8, // all rotations and reflections of the square
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 6] = [Symmetry::C1, Symmetry::C2, Symmetry::C4, Symmetry::D2, Symmetry::D4, Symmetry::D8];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::C1 => "C1",
+            Symmetry::C2 => "C2",
+            Symmetry::C4 => "C4",
+            Symmetry::D2 => "D2",
+            Symmetry::D4 => "D4",
+            Symmetry::D8 => "D8",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        Symmetry::ALL.iter().find(|symmetry| symmetry.name() == name).copied()
+    }
+
+    pub fn next(&self) -> Symmetry {
+        let index = Symmetry::ALL.iter().position(|symmetry| symmetry == self).unwrap();
+        Symmetry::ALL[(index + 1) % Symmetry::ALL.len()]
+    }
+
+    fn needs_square(&self) -> bool {
+        matches!(self, Symmetry::C4 | Symmetry::D8)
+    }
+
+    // All positions the given position is mapped to, within a region of the given size.
+    fn get_orbit(&self, x: u16, y: u16, width: u16, height: u16) -> Vec<(u16, u16)> {
+        let mirror_x = width - 1 - x;
+        let mirror_y = height - 1 - y;
+        match self {
+            Symmetry::C1 => vec![(x, y)],
+            Symmetry::C2 => vec![(x, y), (mirror_x, mirror_y)],
+            Symmetry::C4 => vec![(x, y), (mirror_y, x), (mirror_x, mirror_y), (y, mirror_x)],
+            Symmetry::D2 => vec![(x, y), (mirror_x, y)],
+            Symmetry::D4 => vec![(x, y), (mirror_x, y), (x, mirror_y), (mirror_x, mirror_y)],
+            Symmetry::D8 => vec![
+                (x, y), (mirror_y, x), (mirror_x, mirror_y), (y, mirror_x),
+                (mirror_x, y), (x, mirror_y), (y, x), (mirror_y, mirror_x),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Region {
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region {
+    pub fn new(x: u16, y: u16, width: u16, height: u16) -> Region {
+        Region { x, y, width, height }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SoupOptions {
+    pub density: f64, // probability of a cell to be alive, 0 to 1
+    pub region: Option<Region>, // whole space if None
+    pub symmetry: Symmetry,
+}
+
+impl Default for SoupOptions {
+    fn default() -> SoupOptions {
+        SoupOptions { density: 0.5, region: None, symmetry: Symmetry::C1 }
+    }
+}
+
+impl Space {
+    // Kills all cells of the region and revives them randomly with the given density and symmetry.
+    // C4 and D8 soups use the largest square fitting into the region.
+    pub fn fill_random(&mut self, options: &SoupOptions) {
+        let region = self.clip_region(options.region.unwrap_or(Region::new(0, 0, self.x_dim(), self.y_dim())));
+        let (mut width, mut height) = (region.width, region.height);
+        if options.symmetry.needs_square() {
+            width = width.min(height);
+            height = width;
+        }
+        let density = options.density.clamp(0., 1.);
+        for y in region.y..region.y + region.height {
+            for x in region.x..region.x + region.width {
+                self.get_cell_mut(x, y).unwrap().kill();
+            }
+        }
+        for y in 0..height {
+            for x in 0..width {
+                let orbit = options.symmetry.get_orbit(x, y, width, height);
+                // only the smallest position of each orbit draws a random number
+                if orbit.iter().any(|&(orbit_x, orbit_y)| (orbit_y, orbit_x) < (y, x)) {
+                    continue;
+                }
+                if self.rng.gen_bool(density) {
+                    // continuous automata get random values, sandpiles random heights, lattice gases random particles
+                    // and reaction-diffusion some chemical instead of alive cells
+                    let state: Option<u8> = match self.automaton.clone() {
+                        Automaton::Continuous(_) => Some(self.rng.gen_range(1..=255)),
+                        Automaton::Cyclic(rule) => Some(get_cell_state(self.rng.gen_range(0..rule.num_states), rule.num_states)),
+                        Automaton::ForestFire(_) => Some(get_cell_state(1, 3)), // a tree
+                        Automaton::Schelling(_) => Some(get_cell_state(self.rng.gen_range(1..=2), 3)), // an agent of either population
+                        Automaton::Sandpile(_) => Some(get_cell_state(self.rng.gen_range(1..=3), 5)), // a stable height
+                        Automaton::LatticeGas(model) => Some(self.get_random_particles(model, 0.5)),
+                        Automaton::Table(rule) => {
+                            let num_states = rule.get_table().get_num_states();
+                            Some(get_cell_state(self.rng.gen_range(1..num_states), num_states))
+                        }
+                        _ => None,
+                    };
+                    for (orbit_x, orbit_y) in orbit {
+                        let (x, y) = (region.x + orbit_x, region.y + orbit_y);
+                        match (self.automaton.clone(), state) {
+                            (Automaton::ReactionDiffusion(_), _) => self.paint_chemical(x, y),
+                            (Automaton::LatticeGas(model), Some(particles)) => self.set_particles(x, y, particles, model),
+                            (_, Some(state)) => self.get_cell_mut(x, y).unwrap().set_state(state),
+                            (_, None) => self.get_cell_mut(x, y).unwrap().revive(),
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    pub(crate) fn clip_region(&self, region: Region) -> Region {
+        let x = region.x.min(self.x_dim());
+        let y = region.y.min(self.y_dim());
+        Region::new(x, y, region.width.min(self.x_dim() - x), region.height.min(self.y_dim() - y))
+    }
+}
@@ -0,0 +1,414 @@
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, OnceLock};
+use std::{error::Error, fmt, fs, path::Path};
+use rayon::prelude::*;
+use super::cell::Cell;
+use super::Space;
+
+const MAX_STATES: u16 = 255;
+const MAX_ENTRIES: usize = 1 << 21; // transitions a table may expand to with its variables and symmetries
+// Golly colors the states above 0 with a gradient from red to yellow unless the file declares colors.
+const DEFAULT_GRADIENT: [u8; 6] = [255, 0, 0, 255, 255, 0];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum TableNeighborhood {
+    #[default]
+    Moore,
+    VonNeumann,
+}
+
+impl TableNeighborhood {
+    pub fn name(&self) -> &'static str {
+        match self {
+            TableNeighborhood::Moore => "Moore",
+            TableNeighborhood::VonNeumann => "vonNeumann",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TableNeighborhood> {
+        [TableNeighborhood::Moore, TableNeighborhood::VonNeumann].into_iter().find(|neighborhood| neighborhood.name() == name)
+    }
+
+    // Neighbours in the order of the transitions of Golly tables, clockwise from north.
+    pub fn get_offsets(&self) -> &'static [(i32, i32)] {
+        match self {
+            TableNeighborhood::Moore => &[(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)],
+            TableNeighborhood::VonNeumann => &[(0, -1), (1, 0), (0, 1), (-1, 0)],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Symmetry {
+    None,
+    Rotate4,
+    Rotate8,
+    ReflectHorizontal,
+    Rotate4Reflect,
+    Rotate8Reflect,
+    Permute, // only the number of neighbours in each state counts
+}
+
+impl Symmetry {
+    pub const ALL: [Symmetry; 7] = [
+        Symmetry::None, Symmetry::Rotate4, Symmetry::Rotate8, Symmetry::ReflectHorizontal,
+        Symmetry::Rotate4Reflect, Symmetry::Rotate8Reflect, Symmetry::Permute,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Symmetry::None => "none",
+            Symmetry::Rotate4 => "rotate4",
+            Symmetry::Rotate8 => "rotate8",
+            Symmetry::ReflectHorizontal => "reflect_horizontal",
+            Symmetry::Rotate4Reflect => "rotate4reflect",
+            Symmetry::Rotate8Reflect => "rotate8reflect",
+            Symmetry::Permute => "permute",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Symmetry> {
+        Symmetry::ALL.iter().find(|symmetry| symmetry.name() == name).copied()
+    }
+
+    // Orders of the neighbours a transition also applies to, as the positions in the transition they are taken from.
+    // Permuted neighbours are sorted instead. Rotations by 45 degrees need the eight neighbours of the Moore neighbourhood.
+    fn get_permutations(&self, num_neighbors: usize) -> Option<Vec<Vec<usize>>> {
+        let rotation_step = match self {
+            Symmetry::None | Symmetry::ReflectHorizontal | Symmetry::Permute => num_neighbors,
+            Symmetry::Rotate4 | Symmetry::Rotate4Reflect => num_neighbors / 4,
+            Symmetry::Rotate8 | Symmetry::Rotate8Reflect if num_neighbors == 8 => 1,
+            Symmetry::Rotate8 | Symmetry::Rotate8Reflect => return None,
+        };
+        let reflections: &[bool] = match self {
+            Symmetry::ReflectHorizontal | Symmetry::Rotate4Reflect | Symmetry::Rotate8Reflect => &[false, true],
+            _ => &[false],
+        };
+        let mut permutations = Vec::new();
+        for &is_reflected in reflections {
+            for shift in (0..num_neighbors).step_by(rotation_step) {
+                permutations.push((0..num_neighbors).map(|position| {
+                    let position = (position + shift) % num_neighbors;
+                    if is_reflected { (num_neighbors - position) % num_neighbors } else { position }
+                }).collect());
+            }
+        }
+        Some(permutations)
+    }
+}
+
+// Rule of a Golly .rule file, the transitions of its @TABLE are expanded into a lookup from the states
+// of a cell and its neighbours to the new state. Cells without a matching transition keep their state.
+#[derive(Debug, Clone)]
+pub struct RuleTable {
+    name: String,
+    num_states: u8,
+    neighborhood: TableNeighborhood,
+    symmetry: Symmetry,
+    transitions: HashMap<u128, u8>,
+    colors: Vec<(f32, f32, f32)>,
+    source: String,
+}
+
+impl RuleTable {
+    pub fn parse(content: &str) -> Result<RuleTable, Box<dyn Error>> {
+        let mut name: Option<String> = None;
+        let mut section = "";
+        let mut has_table = false;
+        let (mut num_states, mut neighborhood, mut symmetry) = (None, None, None);
+        let mut variables: HashMap<&str, Vec<u8>> = HashMap::new();
+        let mut transitions: HashMap<u128, u8> = HashMap::new();
+        let mut color_entries: Vec<(usize, Vec<u8>)> = Vec::new();
+        for (line_index, line) in content.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let error = || -> Box<dyn Error> { format!("Invalid rule table entry in line {}: '{}'", line_index + 1, line).into() };
+            if let Some(header) = line.strip_prefix('@') {
+                let mut tokens = header.split_whitespace();
+                section = tokens.next().unwrap_or("");
+                match section {
+                    "RULE" => name = Some(tokens.next().ok_or_else(error)?.to_string()),
+                    "TABLE" => has_table = true,
+                    _ => {}
+                }
+                continue;
+            }
+            match section {
+                "TABLE" => {
+                    if let Some((key, value)) = line.split_once(':') {
+                        match key.trim() {
+                            "n_states" => num_states = Some(value.trim().parse::<u16>().ok().filter(|num_states| (2..=MAX_STATES).contains(num_states)).ok_or_else(error)? as u8),
+                            "neighborhood" => neighborhood = Some(TableNeighborhood::from_name(value.trim()).ok_or_else(error)?),
+                            "symmetries" => symmetry = Some(Symmetry::from_name(value.trim()).ok_or_else(error)?),
+                            _ => return Err(error()),
+                        }
+                        continue;
+                    }
+                    let num_states = num_states.ok_or_else(error)?;
+                    if let Some(definition) = line.strip_prefix("var ") {
+                        let (variable, values) = definition.split_once('=').ok_or_else(error)?;
+                        let values = values.trim().strip_prefix('{').and_then(|values| values.strip_suffix('}')).ok_or_else(error)?;
+                        let mut states = Vec::new();
+                        for token in values.split(',') {
+                            states.extend(resolve_token(token.trim(), num_states, &variables).ok_or_else(error)?);
+                        }
+                        variables.insert(variable.trim(), states);
+                        continue;
+                    }
+                    let (Some(neighborhood), Some(symmetry)) = (neighborhood, symmetry) else {
+                        return Err(error());
+                    };
+                    let num_neighbors = neighborhood.get_offsets().len();
+                    let permutations = symmetry.get_permutations(num_neighbors).ok_or_else(error)?;
+                    // the commas may be left out if all states are single digits
+                    let tokens: Vec<&str> = if line.contains(',') {
+                        line.split(',').map(str::trim).collect()
+                    } else if line.is_ascii() {
+                        (0..line.len()).map(|index| &line[index..index + 1]).collect()
+                    } else {
+                        return Err(error());
+                    };
+                    if tokens.len() != num_neighbors + 2 {
+                        return Err(error());
+                    }
+                    add_transition(&tokens, num_states, &variables, &permutations, symmetry, &mut transitions).ok_or_else(error)?;
+                    if transitions.len() > MAX_ENTRIES {
+                        return Err("Rule table too large!".into());
+                    }
+                }
+                "COLORS" => {
+                    let values = line.split(|c: char| c.is_whitespace() || c == ',')
+                        .filter(|token| !token.is_empty())
+                        .map(|token| token.parse::<u8>())
+                        .collect::<Result<Vec<_>, _>>()
+                        .map_err(|_| error())?;
+                    if values.len() != 4 && values.len() != 6 {
+                        return Err(error());
+                    }
+                    color_entries.push((line_index, values));
+                }
+                _ => {} // descriptions, icons and other sections are not needed to run the rule
+            }
+        }
+        let name = name.ok_or("Rule file without @RULE name!")?;
+        if !has_table {
+            return Err("Only rule files with a @TABLE are supported!".into());
+        }
+        let num_states = num_states.ok_or("Rule table without n_states!")?;
+        let mut colors = vec![(0., 0., 0.); num_states as usize];
+        for (line_index, values) in std::iter::once((0, DEFAULT_GRADIENT.to_vec())).chain(color_entries) {
+            if let [state, red, green, blue] = values[..] {
+                let color = colors.get_mut(state as usize).ok_or(format!("Invalid color of state {} in line {}!", state, line_index + 1))?;
+                *color = (red as f32 / 255., green as f32 / 255., blue as f32 / 255.);
+            } else {
+                // a gradient over the states above 0
+                for (state, color) in colors.iter_mut().enumerate().skip(1) {
+                    let fraction = if num_states > 2 { (state - 1) as f32 / (num_states - 2) as f32 } else { 0. };
+                    let channel = |index: usize| (values[index] as f32 + (values[index + 3] as f32 - values[index] as f32) * fraction) / 255.;
+                    *color = (channel(0), channel(1), channel(2));
+                }
+            }
+        }
+        Ok(RuleTable {
+            name,
+            num_states,
+            neighborhood: neighborhood.ok_or("Rule table without neighborhood!")?,
+            symmetry: symmetry.ok_or("Rule table without symmetries!")?,
+            transitions,
+            colors,
+            source: content.to_string(),
+        })
+    }
+
+    pub fn get_name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn get_num_states(&self) -> u8 {
+        self.num_states
+    }
+
+    pub fn get_neighborhood(&self) -> TableNeighborhood {
+        self.neighborhood
+    }
+
+    pub fn get_symmetry(&self) -> Symmetry {
+        self.symmetry
+    }
+
+    pub fn get_num_transitions(&self) -> usize {
+        self.transitions.len()
+    }
+
+    pub fn get_source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn get_colors(&self) -> &[(f32, f32, f32)] {
+        &self.colors
+    }
+
+    // Neighbour states are given in the order of the offsets of the neighbourhood.
+    pub fn get_next_state(&self, state: u8, neighbors: &mut [u8]) -> u8 {
+        if self.symmetry == Symmetry::Permute {
+            neighbors.sort_unstable();
+        }
+        self.transitions.get(&get_key(state, neighbors)).copied().unwrap_or(state)
+    }
+}
+
+// Compiled table shared by the automata of the space, its snapshots and the worker.
+#[derive(Debug, Clone)]
+pub struct TableRule {
+    table: Arc<RuleTable>,
+}
+
+impl TableRule {
+    pub fn new(table: RuleTable) -> TableRule {
+        TableRule { table: Arc::new(table) }
+    }
+
+    pub fn get_table(&self) -> &RuleTable {
+        &self.table
+    }
+
+    pub fn get_state_names(&self) -> &'static [&'static str] {
+        static STATE_NAMES: OnceLock<Vec<&'static str>> = OnceLock::new();
+        let state_names = STATE_NAMES.get_or_init(|| {
+            (0..MAX_STATES).map(|state| &*Box::leak(format!("state {}", state).into_boxed_str())).collect()
+        });
+        &state_names[..self.get_table().num_states as usize]
+    }
+}
+
+// Tables are equal if they were loaded from the same file content.
+impl PartialEq for TableRule {
+    fn eq(&self, other: &TableRule) -> bool {
+        Arc::ptr_eq(&self.table, &other.table) || self.table.source == other.table.source
+    }
+}
+
+impl Eq for TableRule {}
+
+impl Hash for TableRule {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.table.source.hash(state);
+    }
+}
+
+impl fmt::Display for TableRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "table:{}", self.get_table().name)
+    }
+}
+
+pub fn read_rule_file(file_str: &str) -> Result<TableRule, Box<dyn Error>> {
+    let content = fs::read_to_string(Path::new(file_str))?;
+    Ok(TableRule::new(RuleTable::parse(&content)?))
+}
+
+impl Space {
+    pub(crate) fn compute_table_generation(&mut self, rule: &TableRule) {
+        let table = rule.get_table();
+        let num_states = table.num_states;
+        let offsets = table.neighborhood.get_offsets();
+        let changes: Vec<(u16, u16, u8)> = self.flat()
+            .par_iter()
+            .filter_map(|cell: &&Cell| {
+                let state = cell.get_state_index(num_states);
+                // cells outside of a bounded space are in state 0
+                let mut neighbors = [0; 8];
+                for (neighbor, &(dx, dy)) in neighbors.iter_mut().zip(offsets) {
+                    if let Some((x, y)) = self.get_neighbor_position(cell.x, cell.y, dx, dy) {
+                        *neighbor = self.get_cell(x, y).unwrap().get_state_index(num_states);
+                    }
+                }
+                let new_state = table.get_next_state(state, &mut neighbors[..offsets.len()]);
+                (new_state != state).then_some((cell.x, cell.y, new_state))
+            })
+            .collect();
+        for (x, y, state) in changes {
+            self.get_cell_mut(x, y).unwrap().set_state_index(state, num_states);
+        }
+    }
+}
+
+fn get_key(state: u8, neighbors: &[u8]) -> u128 {
+    neighbors.iter().fold(state as u128, |key, &neighbor| key << 8 | neighbor as u128)
+}
+
+fn resolve_token(token: &str, num_states: u8, variables: &HashMap<&str, Vec<u8>>) -> Option<Vec<u8>> {
+    match token.parse::<u8>() {
+        Ok(state) => (state < num_states).then(|| vec![state]),
+        Err(_) => variables.get(token).cloned(),
+    }
+}
+
+// Adds the lookup entries of a transition for every combination of its variables, a variable takes the same
+// value everywhere it appears. Entries of earlier transitions take precedence, as Golly uses the first match.
+fn add_transition(
+    tokens: &[&str], num_states: u8, variables: &HashMap<&str, Vec<u8>>, permutations: &[Vec<usize>], symmetry: Symmetry,
+    transitions: &mut HashMap<u128, u8>,
+) -> Option<()> {
+    enum Entry {
+        State(u8),
+        Variable(usize),
+    }
+    let mut bound: Vec<&str> = Vec::new();
+    let mut entries = Vec::new();
+    let (output, inputs) = tokens.split_last()?;
+    for &token in inputs {
+        if let Ok(state) = token.parse::<u8>() {
+            entries.push(Entry::State((state < num_states).then_some(state)?));
+            continue;
+        }
+        variables.get(token)?;
+        let index = bound.iter().position(|&variable| variable == token).unwrap_or_else(|| {
+            bound.push(token);
+            bound.len() - 1
+        });
+        entries.push(Entry::Variable(index));
+    }
+    // the new state is a state or one of the variables of the cells
+    let output = match output.parse::<u8>() {
+        Ok(state) => Entry::State((state < num_states).then_some(state)?),
+        Err(_) => Entry::Variable(bound.iter().position(|variable| variable == output)?),
+    };
+    let values: Vec<&Vec<u8>> = bound.iter().map(|variable| &variables[variable]).collect();
+    let num_combinations = values.iter().try_fold(1usize, |product, values| product.checked_mul(values.len()))?;
+    if num_combinations.checked_mul(permutations.len())? > MAX_ENTRIES {
+        return None;
+    }
+    let mut choice = vec![0; bound.len()];
+    loop {
+        let get_state = |entry: &Entry| match entry {
+            Entry::State(state) => *state,
+            Entry::Variable(index) => values[*index][choice[*index]],
+        };
+        let states: Vec<u8> = entries.iter().map(get_state).collect();
+        let new_state = get_state(&output);
+        for permutation in permutations {
+            let mut neighbors: Vec<u8> = permutation.iter().map(|&position| states[1 + position]).collect();
+            if symmetry == Symmetry::Permute {
+                neighbors.sort_unstable();
+            }
+            transitions.entry(get_key(states[0], &neighbors)).or_insert(new_state);
+        }
+        // next combination of the variable values
+        let mut index = 0;
+        loop {
+            if index == choice.len() {
+                return Some(());
+            }
+            choice[index] += 1;
+            if choice[index] < values[index].len() {
+                break;
+            }
+            choice[index] = 0;
+            index += 1;
+        }
+    }
+}
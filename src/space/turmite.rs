@@ -0,0 +1,238 @@
+use std::fmt;
+use super::rule::RuleParseError;
+use super::Space;
+
+const MAX_STATES: usize = 4;
+const MAX_COLORS: usize = 12;
+
+pub const PRESETS: [(&str, &str); 4] = [
+    ("Langton's ant", "RL"),
+    ("symmetric ant", "LLRR"),
+    ("square filler", "LRRRRRLLR"),
+    ("Fibonacci spiral", "{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Heading {
+    #[default]
+    North,
+    East,
+    South,
+    West,
+}
+
+impl Heading {
+    pub const ALL: [Heading; 4] = [Heading::North, Heading::East, Heading::South, Heading::West];
+
+    pub fn turned(&self, turn: Turn) -> Heading {
+        let quarter_turns = match turn {
+            Turn::None => 0,
+            Turn::Right => 1,
+            Turn::UTurn => 2,
+            Turn::Left => 3,
+        };
+        Heading::ALL[(*self as usize + quarter_turns) % 4]
+    }
+
+    pub fn get_offset(&self) -> (i32, i32) {
+        match self {
+            Heading::North => (0, -1),
+            Heading::East => (1, 0),
+            Heading::South => (0, 1),
+            Heading::West => (-1, 0),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Turn {
+    #[default]
+    None,
+    Right,
+    UTurn,
+    Left,
+}
+
+impl Turn {
+    // Turns as numbered by Golly: 1 no turn, 2 right, 4 u-turn, 8 left.
+    fn from_number(number: u8) -> Option<Turn> {
+        match number {
+            0 | 1 => Some(Turn::None),
+            2 => Some(Turn::Right),
+            4 => Some(Turn::UTurn),
+            8 => Some(Turn::Left),
+            _ => None,
+        }
+    }
+
+    fn to_number(self) -> u8 {
+        match self {
+            Turn::None => 1,
+            Turn::Right => 2,
+            Turn::UTurn => 4,
+            Turn::Left => 8,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Transition {
+    pub write_color: u8,
+    pub turn: Turn,
+    pub next_state: u8,
+}
+
+// Transition table of a turmite, indexed by the state of the ant and the color of the cell below it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TurmiteRule {
+    num_states: u8,
+    num_colors: u8,
+    transitions: [[Transition; MAX_COLORS]; MAX_STATES],
+}
+
+impl TurmiteRule {
+    pub fn langtons_ant() -> TurmiteRule {
+        TurmiteRule::parse("RL").unwrap()
+    }
+
+    // Accepts turn strings of multi-color ants like "RL" or "LLRR", and turmite tables
+    // like "{{{1,2,0},{0,8,0}}}" with {write color, turn, next state} per state and color.
+    pub fn parse(rulestring: &str) -> Result<TurmiteRule, RuleParseError> {
+        let rulestring = rulestring.trim();
+        let error = || RuleParseError::new(rulestring);
+        let mut rule = TurmiteRule { num_states: 1, num_colors: 0, transitions: [[Transition::default(); MAX_COLORS]; MAX_STATES] };
+        if rulestring.starts_with('{') {
+            let numbers: Vec<u8> = rulestring.split(|c: char| !c.is_ascii_digit()).filter(|number| !number.is_empty())
+                .map(|number| number.parse().map_err(|_| error()))
+                .collect::<Result<_, _>>()?;
+            let num_states = rulestring.matches("}}").count(); // the transitions of each state close with "}}"
+            if num_states == 0 || num_states > MAX_STATES || numbers.is_empty() || !numbers.len().is_multiple_of(3 * num_states) {
+                return Err(error());
+            }
+            let num_colors = numbers.len() / (3 * num_states);
+            if !(2..=MAX_COLORS).contains(&num_colors) {
+                return Err(error());
+            }
+            rule.num_states = num_states as u8;
+            rule.num_colors = num_colors as u8;
+            for (index, transition) in numbers.chunks(3).enumerate() {
+                let (write_color, turn, next_state) = (transition[0], transition[1], transition[2]);
+                if write_color as usize >= num_colors || next_state as usize >= num_states {
+                    return Err(error());
+                }
+                let turn = Turn::from_number(turn).ok_or_else(error)?;
+                rule.transitions[index / num_colors][index % num_colors] = Transition { write_color, turn, next_state };
+            }
+        } else {
+            let num_colors = rulestring.len();
+            if !(2..=MAX_COLORS).contains(&num_colors) {
+                return Err(error());
+            }
+            rule.num_colors = num_colors as u8;
+            for (color, c) in rulestring.chars().enumerate() {
+                let turn = match c.to_ascii_uppercase() {
+                    'L' => Turn::Left,
+                    'R' => Turn::Right,
+                    'N' => Turn::None,
+                    'U' => Turn::UTurn,
+                    _ => return Err(error()),
+                };
+                rule.transitions[0][color] = Transition { write_color: ((color + 1) % num_colors) as u8, turn, next_state: 0 };
+            }
+        }
+        Ok(rule)
+    }
+
+    pub fn get_num_colors(&self) -> u8 {
+        self.num_colors
+    }
+
+    pub fn get_transition(&self, state: u8, color: u8) -> Transition {
+        self.transitions[state as usize % MAX_STATES][color as usize % MAX_COLORS]
+    }
+
+    // Single state ants, which only step to the next color, have a turn string.
+    fn get_turn_string(&self) -> Option<String> {
+        if self.num_states != 1 {
+            return None;
+        }
+        let mut turn_string = String::new();
+        for color in 0..self.num_colors {
+            let transition = self.get_transition(0, color);
+            if transition.write_color != (color + 1) % self.num_colors {
+                return None;
+            }
+            turn_string.push(match transition.turn {
+                Turn::None => 'N',
+                Turn::Right => 'R',
+                Turn::UTurn => 'U',
+                Turn::Left => 'L',
+            });
+        }
+        Some(turn_string)
+    }
+}
+
+impl fmt::Display for TurmiteRule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(turn_string) = self.get_turn_string() {
+            return write!(f, "{}", turn_string);
+        }
+        let states: Vec<String> = (0..self.num_states).map(|state| {
+            let colors: Vec<String> = (0..self.num_colors).map(|color| {
+                let transition = self.get_transition(state, color);
+                format!("{{{},{},{}}}", transition.write_color, transition.turn.to_number(), transition.next_state)
+            }).collect();
+            format!("{{{}}}", colors.join(","))
+        }).collect();
+        write!(f, "{{{}}}", states.join(","))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Ant {
+    pub x: u16,
+    pub y: u16,
+    pub heading: Heading,
+    pub state: u8,
+}
+
+impl Ant {
+    pub fn new(x: u16, y: u16) -> Ant {
+        Ant { x, y, heading: Heading::North, state: 0 }
+    }
+}
+
+impl Space {
+    // Moves the ants one after another, ants leaving a bounded space are removed.
+    pub(crate) fn compute_turmite_generation(&mut self, rule: &TurmiteRule) {
+        let num_colors = rule.get_num_colors();
+        let mut ants = std::mem::take(&mut self.ants);
+        ants.retain_mut(|ant| {
+            let cell = self.get_cell_mut(ant.x, ant.y).unwrap();
+            let transition = rule.get_transition(ant.state, cell.get_state_index(num_colors));
+            cell.set_state_index(transition.write_color, num_colors);
+            ant.heading = ant.heading.turned(transition.turn);
+            ant.state = transition.next_state;
+            let (dx, dy) = ant.heading.get_offset();
+            match self.get_neighbor_position(ant.x, ant.y, dx, dy) {
+                Some((x, y)) => {
+                    (ant.x, ant.y) = (x, y);
+                    true
+                }
+                None => false,
+            }
+        });
+        self.ants = ants;
+    }
+
+    pub fn add_ant(&mut self, x: u16, y: u16) {
+        if x < self.x_dim() && y < self.y_dim() && !self.ants.iter().any(|ant| (ant.x, ant.y) == (x, y)) {
+            self.ants.push(Ant::new(x, y));
+        }
+    }
+
+    pub fn remove_ants(&mut self, x: u16, y: u16) {
+        self.ants.retain(|ant| (ant.x, ant.y) != (x, y));
+    }
+}
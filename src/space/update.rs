@@ -0,0 +1,166 @@
+use std::fmt;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use rayon::prelude::*;
+use super::{CellAction, Space};
+
+pub const PROBABILITIES: [f32; 4] = [1., 0.9, 0.75, 0.5];
+pub const RATES: [f32; 5] = [0.5, 0.25, 0.1, 0.75, 1.];
+pub const BLOCK_SIZES: [u16; 4] = [8, 4, 16, 2];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum UpdateScheme {
+    #[default]
+    Synchronous, // all cells at once from the previous generation
+    RandomSequential, // one cell after the other in a new random order every generation
+    Asynchronous, // each cell at once with the probability of the rate, the others keep their state
+    BlockSequential, // square blocks one after the other, the cells of a block at once
+}
+
+impl UpdateScheme {
+    pub const ALL: [UpdateScheme; 4] = [
+        UpdateScheme::Synchronous, UpdateScheme::RandomSequential, UpdateScheme::Asynchronous, UpdateScheme::BlockSequential,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            UpdateScheme::Synchronous => "synchronous",
+            UpdateScheme::RandomSequential => "random-sequential",
+            UpdateScheme::Asynchronous => "asynchronous",
+            UpdateScheme::BlockSequential => "block-sequential",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<UpdateScheme> {
+        UpdateScheme::ALL.iter().find(|scheme| scheme.name() == name).copied()
+    }
+
+    pub fn next(&self) -> UpdateScheme {
+        UpdateScheme::ALL[(UpdateScheme::ALL.iter().position(|scheme| scheme == self).unwrap() + 1) % UpdateScheme::ALL.len()]
+    }
+}
+
+// How life-like rules are applied. Births and deaths happen with the probability, the random numbers
+// come from the seeded generator of the space, so runs can be repeated.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UpdateOptions {
+    pub scheme: UpdateScheme,
+    pub probability: f32,
+    pub rate: f32, // share of cells updated per generation by the asynchronous scheme
+    pub block_size: u16,
+}
+
+impl UpdateOptions {
+    pub fn next_probability(&self) -> f32 {
+        let index = PROBABILITIES.iter().position(|&probability| probability == self.probability).map_or(0, |index| index + 1);
+        PROBABILITIES[index % PROBABILITIES.len()]
+    }
+
+    pub fn next_rate(&self) -> f32 {
+        let index = RATES.iter().position(|&rate| rate == self.rate).map_or(0, |index| index + 1);
+        RATES[index % RATES.len()]
+    }
+
+    pub fn next_block_size(&self) -> u16 {
+        let index = BLOCK_SIZES.iter().position(|&block_size| block_size == self.block_size).map_or(0, |index| index + 1);
+        BLOCK_SIZES[index % BLOCK_SIZES.len()]
+    }
+
+    // Cycles the rate of the asynchronous or the block size of the block-sequential scheme.
+    pub fn next_parameter(&self) -> UpdateOptions {
+        match self.scheme {
+            UpdateScheme::Asynchronous => UpdateOptions { rate: self.next_rate(), ..*self },
+            UpdateScheme::BlockSequential => UpdateOptions { block_size: self.next_block_size(), ..*self },
+            _ => *self,
+        }
+    }
+}
+
+impl Default for UpdateOptions {
+    fn default() -> UpdateOptions {
+        UpdateOptions { scheme: UpdateScheme::default(), probability: 1., rate: 0.5, block_size: 8 }
+    }
+}
+
+impl fmt::Display for UpdateOptions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.scheme.name())?;
+        match self.scheme {
+            UpdateScheme::Asynchronous => write!(f, " rate {}", self.rate)?,
+            UpdateScheme::BlockSequential => write!(f, " blocks {}", self.block_size)?,
+            _ => {}
+        }
+        if self.probability < 1. {
+            write!(f, " p {}", self.probability)?;
+        }
+        Ok(())
+    }
+}
+
+impl Space {
+    pub(crate) fn compute_life_like_generation(&mut self) {
+        let options = self.update;
+        if options.scheme == UpdateScheme::Synchronous && options.probability >= 1. {
+            self.compute_conways_game_of_life_multithreaded();
+            return;
+        }
+        let x_dim = self.x_dim();
+        let positions: Vec<(u16, u16)> = (0..self.y_dim()).flat_map(|y| (0..x_dim).map(move |x| (x, y))).collect();
+        match options.scheme {
+            UpdateScheme::Synchronous => self.update_synchronously(&positions),
+            UpdateScheme::RandomSequential => {
+                let mut positions = positions;
+                positions.shuffle(&mut self.rng);
+                for (x, y) in positions {
+                    if let Some(action) = Self::get_action_by_conways_game_of_life_rules(self, self.get_cell(x, y).unwrap()) {
+                        self.apply_action(x, y, action);
+                    }
+                }
+            }
+            UpdateScheme::Asynchronous => {
+                let rate = options.rate.clamp(0., 1.) as f64;
+                let positions: Vec<(u16, u16)> = positions.into_iter().filter(|_| self.rng.gen_bool(rate)).collect();
+                self.update_synchronously(&positions);
+            }
+            UpdateScheme::BlockSequential => {
+                let (x_dim, y_dim, block_size) = (self.x_dim(), self.y_dim(), options.block_size.max(1));
+                for block_y in (0..y_dim).step_by(block_size as usize) {
+                    for block_x in (0..x_dim).step_by(block_size as usize) {
+                        let block: Vec<(u16, u16)> = (block_y..block_y.saturating_add(block_size).min(y_dim))
+                            .flat_map(|y| (block_x..block_x.saturating_add(block_size).min(x_dim)).map(move |x| (x, y)))
+                            .collect();
+                        self.update_synchronously(&block);
+                    }
+                }
+            }
+        }
+    }
+
+    // Applies the rule to the cells from their current neighbourhoods, then changes them all.
+    fn update_synchronously(&mut self, positions: &[(u16, u16)]) {
+        let actions: Vec<(u16, u16, CellAction)> = positions
+            .par_iter()
+            .filter_map(|&(x, y)| Self::get_action_by_conways_game_of_life_rules(self, self.get_cell(x, y).unwrap()).map(|action| (x, y, action)))
+            .collect();
+        for (x, y, action) in actions {
+            self.apply_action(x, y, action);
+        }
+    }
+
+    // Births and deaths are left out with the complementary probability, traces fade anyway.
+    fn apply_action(&mut self, x: u16, y: u16, action: CellAction) {
+        let is_transition = match action {
+            CellAction::Revive => true,
+            CellAction::Age => self.check_cell_is_alive(x, y),
+            CellAction::Survive => false,
+        };
+        if is_transition && self.update.probability < 1. && !self.rng.gen_bool(self.update.probability.clamp(0., 1.) as f64) {
+            return;
+        }
+        match action {
+            CellAction::Age => self.let_cell_age(x, y),
+            CellAction::Survive => self.let_cell_survive(x, y),
+            CellAction::Revive => self.revive_cell(x, y),
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use super::automaton::Automaton;
+use super::continuous::ContinuousGrid;
+use super::lattice;
+
+// Values of automata that do not fit into the cell states, kept next to the cells and saved with every state.
+// The cell states only hold what is drawn.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum CellValues {
+    #[default]
+    Empty,
+    Continuous(ContinuousGrid),
+    Chemicals(ContinuousGrid, ContinuousGrid), // concentrations of u and v
+    Fires(ValueGrid<u32>), // numbers of the fires burning the cells
+    Heights(ValueGrid<u32>), // grains of sandpile cells
+    Particles(ValueGrid<u8>), // directions of the lattice gas particles as bits, and the obstacle bit
+}
+
+// Grid of whole numbers, stored row by row.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ValueGrid<T> {
+    x_dim: u16,
+    y_dim: u16,
+    values: Vec<T>,
+}
+
+impl CellValues {
+    pub fn is_empty(&self) -> bool {
+        matches!(self, CellValues::Empty)
+    }
+
+    // Whether the automaton computes its generations from these values.
+    pub fn belong_to(&self, automaton: &Automaton) -> bool {
+        match self {
+            CellValues::Empty => true,
+            CellValues::Continuous(_) => matches!(automaton, Automaton::Continuous(_)),
+            CellValues::Chemicals(..) => matches!(automaton, Automaton::ReactionDiffusion(_)),
+            CellValues::Fires(_) => matches!(automaton, Automaton::ForestFire(_)),
+            CellValues::Heights(_) => matches!(automaton, Automaton::Sandpile(_)),
+            // particles in directions the model does not have would get lost
+            CellValues::Particles(particles) => match automaton {
+                Automaton::LatticeGas(model) => particles.values().iter().all(|&particles| model.is_valid_particles(particles)),
+                _ => false,
+            },
+        }
+    }
+
+    // Values of the region shared with a space of the given size.
+    pub fn resized(&self, x_dim: u16, y_dim: u16) -> CellValues {
+        match self {
+            CellValues::Empty => CellValues::Empty,
+            CellValues::Continuous(grid) => CellValues::Continuous(grid.resized(x_dim, y_dim, 0.)),
+            CellValues::Chemicals(u, v) => CellValues::Chemicals(u.resized(x_dim, y_dim, 1.), v.resized(x_dim, y_dim, 0.)),
+            CellValues::Fires(fires) => CellValues::Fires(fires.resized(x_dim, y_dim)),
+            CellValues::Heights(heights) => CellValues::Heights(heights.resized(x_dim, y_dim)),
+            CellValues::Particles(particles) => CellValues::Particles(particles.resized(x_dim, y_dim)),
+        }
+    }
+
+    // Values at a position as shown by the inspector.
+    pub fn get_description(&self, x: u16, y: u16) -> Option<String> {
+        match self {
+            CellValues::Empty => None,
+            CellValues::Continuous(grid) => Some(format!("value: {:.3}", grid.get_value(x, y))),
+            CellValues::Chemicals(u, v) => Some(format!("u: {:.3}  v: {:.3}", u.get_value(x, y), v.get_value(x, y))),
+            CellValues::Fires(fires) => (fires.get_value(x, y) > 0).then(|| format!("fire: {}", fires.get_value(x, y))),
+            CellValues::Heights(heights) => Some(format!("grains: {}", heights.get_value(x, y))),
+            CellValues::Particles(particles) => Some(format!("particles: {}", lattice::count_particles(particles.get_value(x, y)))),
+        }
+    }
+}
+
+impl<T: Copy + Default> ValueGrid<T> {
+    pub fn new(x_dim: u16, y_dim: u16) -> ValueGrid<T> {
+        ValueGrid { x_dim, y_dim, values: vec![T::default(); x_dim as usize * y_dim as usize] }
+    }
+
+    pub fn x_dim(&self) -> u16 {
+        self.x_dim
+    }
+
+    pub fn y_dim(&self) -> u16 {
+        self.y_dim
+    }
+
+    pub fn values(&self) -> &[T] {
+        &self.values
+    }
+
+    pub fn get_value(&self, x: u16, y: u16) -> T {
+        self.values[y as usize * self.x_dim as usize + x as usize]
+    }
+
+    pub fn set_value(&mut self, x: u16, y: u16, value: T) {
+        self.values[y as usize * self.x_dim as usize + x as usize] = value;
+    }
+
+    // Grid of the given size with the values of the shared region, the default value elsewhere.
+    pub fn resized(&self, x_dim: u16, y_dim: u16) -> ValueGrid<T> {
+        let mut grid = ValueGrid::new(x_dim, y_dim);
+        for y in 0..y_dim.min(self.y_dim) {
+            for x in 0..x_dim.min(self.x_dim) {
+                grid.set_value(x, y, self.get_value(x, y));
+            }
+        }
+        grid
+    }
+}
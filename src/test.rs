@@ -1,8 +1,34 @@
 #[cfg(test)]
 
 mod tests {
-    use std::time::Instant;
-    use crate::space::{Space, io};
+    use std::time::{Duration, Instant};
+    use crate::animation::{self, AnimationOptions};
+    use crate::colormap::{Colormap, Gradient};
+    use crate::render::{self, RenderOptions};
+    use crate::session;
+    use crate::simulation::{Command, Simulation};
+    use crate::space::{CellFate, Space, io};
+    use crate::space::rule::{Boundary, Rule};
+    use crate::space::automaton::{self, Automaton};
+    use crate::space::cell::Cell;
+    use crate::space::characterize::Phase;
+    use crate::space::continuous::{ContinuousGrid, ContinuousRule};
+    use crate::space::cyclic::{CyclicKind, CyclicRule, Neighborhood};
+    use crate::space::elementary::LinearRule;
+    use crate::space::forest::ForestFireRule;
+    use crate::space::lattice::GasModel;
+    use crate::space::margolus::BlockRule;
+    use crate::space::multistate::NamedRule;
+    use crate::space::reaction::{Channel, GrayScottRule};
+    use crate::space::sandpile::{GrainDrop, SandpileRule};
+    use crate::space::schelling::SchellingRule;
+    use crate::space::table::{self, RuleTable, TableRule};
+    use crate::space::turmite::{Ant, Heading, TurmiteRule};
+    use crate::space::update::{UpdateOptions, UpdateScheme, RATES};
+    use crate::space::values::{CellValues, ValueGrid};
+    use crate::space::soup::{Region, SoupOptions, Symmetry};
+    use crate::space::object::{self, Classifier, ObjectKind, Pattern};
+    use crate::census::{self, SearchOptions};
 
     #[test]
     fn space_works() {
@@ -230,6 +256,41 @@ mod tests {
         assert_eq!(4, space.get_num_alive_cells());
     }
 
+    #[test]
+    fn io_read_rle_works() {
+        let space = io::read_rle("#N Glider\nx = 5, y = 4, rule = B3/S23-a4i\nbo$2bo$3o!\n").unwrap();
+        assert_eq!((5, 4), (space.x_dim(), space.y_dim()));
+        assert_eq!(Rule::parse("B3/S23-a4i").unwrap(), space.rule);
+        assert_eq!(vec![(0, 2), (1, 0), (1, 2), (2, 1), (2, 2)], space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect::<Vec<_>>());
+        let space = io::read_rle("x = 3, y = 2\n2o\n$.A!").unwrap();
+        assert_eq!(Rule::default(), space.rule);
+        assert_eq!(3, space.get_num_alive_cells());
+
+        let file_path = std::env::temp_dir().join("io_read_rle_works.rle");
+        std::fs::write(&file_path, "x = 4, y = 1, rule = B2-a/S12\n4o!").unwrap();
+        let space = io::read_from_file(file_path.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&file_path).unwrap();
+        assert_eq!(Rule::parse("B2-a/S12").unwrap(), space.rule);
+        assert_eq!(4, space.get_num_alive_cells());
+
+        assert!(io::read_rle("bo$2bo$3o!").is_err());
+        assert!(io::read_rle("x = 2, y = 2, rule = B9/S23\no!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\n3o!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\no$$o!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\n3bo!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\n4294967295bo!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\n4294967295$o!").is_err());
+        assert!(io::read_rle("x = 2, y = 2\n2b$2o$!").is_ok());
+
+        assert!(io::read_from_file(std::env::temp_dir().join("io_read_rle_works_missing.space").to_str().unwrap()).is_err());
+        let file_path = std::env::temp_dir().join("io_read_rle_works.space");
+        std::fs::write(&file_path, "010\n01\n").unwrap();
+        assert!(io::read_from_file(file_path.to_str().unwrap()).is_err());
+        std::fs::write(&file_path, "").unwrap();
+        assert!(io::read_from_file(file_path.to_str().unwrap()).is_err());
+        std::fs::remove_file(&file_path).unwrap();
+    }
+
     #[test]
     fn kill_all_cells_works() {
         let mut space = Space::new(200, 300);
@@ -283,4 +344,1636 @@ mod tests {
         println!("iterations per 1 s: {}", iterations);
         assert!(iterations >= 30);
     }
+
+    fn wait_for_time(simulation: &mut Simulation, time: usize) -> Space {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            let space = &simulation.poll().space;
+            if space.displayed_time == time {
+                return (**space).clone();
+            }
+        }
+        panic!("simulation did not reach time {}", time);
+    }
+
+    #[test]
+    fn background_simulation_works() {
+        let mut expected = io::read_from_file("resources/glider.space").unwrap();
+        let mut simulation = Simulation::new(expected.clone());
+        let mut num_alive_cells_of_gen_2 = 0;
+        for i in 1..=4 {
+            simulation.send(Command::StepForward);
+            expected.compute_conways_game_of_life_multithreaded();
+            if i == 2 {
+                num_alive_cells_of_gen_2 = expected.get_num_alive_cells();
+            }
+        }
+        let space = wait_for_time(&mut simulation, 4);
+        assert_eq!(expected.get_alive_cells(), space.get_alive_cells());
+        simulation.send(Command::StepBackward);
+        simulation.send(Command::StepBackward);
+        let space = wait_for_time(&mut simulation, 2);
+        assert_eq!(num_alive_cells_of_gen_2, space.get_num_alive_cells());
+        simulation.send(Command::KillAllCells);
+        simulation.send(Command::ReviveCell(1, 1));
+        simulation.send(Command::CommitEdit);
+        let space = wait_for_time(&mut simulation, 3);
+        assert_eq!(1, space.get_num_alive_cells());
+        simulation.send(Command::JumpToEnd);
+        simulation.send(Command::StepBackward);
+        let space = wait_for_time(&mut simulation, 2);
+        assert_eq!(num_alive_cells_of_gen_2, space.get_num_alive_cells());
+    }
+
+    #[test]
+    fn colormaps_work() {
+        let custom = Gradient::new((0., 0., 0.), (1., 1., 1.));
+        let color = (0.05, 0.15, 1.);
+        assert_eq!((0.05, 0.15, 1., 0.5), Colormap::Solid.map(0.5, &color, &custom));
+        assert_eq!((0., 0., 0., 1.), Colormap::Grayscale.map(0., &color, &custom));
+        assert_eq!((1., 1., 1., 1.), Colormap::Grayscale.map(1., &color, &custom));
+        assert_eq!((0.992, 0.906, 0.145, 1.), Colormap::Viridis.map(2., &color, &custom));
+        for colormap in Colormap::ALL {
+            let rgba = colormap.map(0.3, &color, &custom);
+            assert!([rgba.0, rgba.1, rgba.2, rgba.3].iter().all(|c| (0. ..=1.).contains(c)));
+        }
+        assert_eq!(Colormap::Solid, Colormap::Custom.next());
+    }
+
+    #[test]
+    fn custom_gradient_works() {
+        let mut gradient = Gradient::new((0., 0., 0.), (1., 1., 1.));
+        assert_eq!((0.5, 0.5, 0.5), gradient.sample(0.5));
+        gradient.add_stop(0.5, (1., 0., 0.));
+        assert_eq!(3, gradient.stops().len());
+        assert_eq!((1., 0., 0.), gradient.sample(0.5));
+        assert_eq!((0.5, 0., 0.), gradient.sample(0.25));
+        gradient.remove_stop_near(0.45);
+        assert_eq!((0.5, 0.5, 0.5), gradient.sample(0.5));
+        gradient.remove_stop_near(0.);
+        assert_eq!(2, gradient.stops().len());
+    }
+
+    #[test]
+    fn generations_alive_works() {
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 1, 1, 0, 0, 0, 1, 0, 0],
+            vec![0, 1, 1, 0, 0, 0, 1, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 1, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ]);
+        space.save_state(0);
+        for time in 1..=3 {
+            space.compute_conways_game_of_life_multithreaded();
+            space.save_state(time);
+        }
+        // the block is stable debris, the blinker center survives while its ends are reborn
+        assert_eq!(3, space.get_generations_alive(1, 1).unwrap());
+        assert_eq!(Some(0), space.get_birth_time(2, 2).unwrap());
+        assert_eq!(3, space.get_generations_alive(6, 2).unwrap());
+        assert_eq!(0, space.get_generations_alive(5, 2).unwrap());
+        assert_eq!(Some(3), space.get_birth_time(5, 2).unwrap());
+        assert_eq!(None, space.get_birth_time(0, 0).unwrap());
+        assert!(space.get_generations_alive(9, 0).is_err());
+        space.load_state(2).unwrap();
+        assert_eq!(2, space.get_generations_alive(1, 1).unwrap());
+        assert_eq!(0, space.get_generations_alive(6, 1).unwrap());
+        assert_eq!(Some(2), space.get_birth_time(6, 1).unwrap());
+    }
+
+    #[test]
+    fn cell_fate_works() {
+        let mut space = Space::build_from_array(&[
+            vec![0, 1, 0, 0],
+            vec![0, 1, 0, 0],
+            vec![0, 1, 0, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        assert_eq!(CellFate::Born, space.get_cell_fate(0, 1).unwrap());
+        assert_eq!(CellFate::Survives, space.get_cell_fate(1, 1).unwrap());
+        assert_eq!(CellFate::Dies, space.get_cell_fate(1, 0).unwrap());
+        assert_eq!(CellFate::StaysDead, space.get_cell_fate(3, 3).unwrap());
+        assert!(space.get_cell_fate(4, 0).is_err());
+        space.compute_conways_game_of_life_multithreaded();
+        assert!(space.check_cell_is_alive(0, 1));
+        assert!(!space.check_cell_is_alive(1, 0));
+        assert_eq!(CellFate::Born, space.get_cell_fate(1, 0).unwrap());
+        assert_eq!(CellFate::StaysDead, space.get_cell_fate(3, 0).unwrap());
+    }
+
+    #[test]
+    fn render_works() {
+        let mut space = Space::build_from_array(&[
+            vec![1, 0, 0],
+            vec![0, 0, 0],
+        ]);
+        let options = RenderOptions { cell_size: 4, color: (1., 0., 0.), ..RenderOptions::default() };
+        let image = render::render(&space, &options);
+        assert_eq!((12, 8), (image.width, image.height));
+        assert_eq!(12 * 8 * 4, image.pixels.len());
+        assert_eq!([255, 0, 0, 255], image.get_pixel(3, 3));
+        assert_eq!([0, 0, 0, 255], image.get_pixel(4, 0));
+        space.let_cell_age(0, 0);
+        let tracing = RenderOptions { tracing: true, ..options.clone() };
+        assert_eq!([238, 0, 0, 255], render::render(&space, &tracing).get_pixel(0, 0));
+        assert_eq!([0, 0, 0, 255], render::render(&space, &options).get_pixel(0, 0));
+        let grid = RenderOptions { grid_lines: true, ..options };
+        let image = render::render(&space, &grid);
+        assert_eq!([77, 77, 77, 255], image.get_pixel(4, 1));
+        assert_eq!([77, 77, 77, 255], image.get_pixel(11, 7));
+        assert_eq!([0, 0, 0, 255], image.get_pixel(5, 5));
+    }
+
+    #[test]
+    fn render_to_png_works() {
+        let space = io::read_from_file("resources/glider.space").unwrap();
+        let file_path = std::env::temp_dir().join("render_to_png_works.png");
+        let file_str = file_path.to_str().unwrap();
+        render::render_to_png(file_str, &space, &RenderOptions::default()).unwrap();
+        let bytes = std::fs::read(file_str).unwrap();
+        assert_eq!(&[0x89, b'P', b'N', b'G'], &bytes[0..4]);
+        std::fs::remove_file(file_str).unwrap();
+    }
+
+    #[test]
+    fn render_to_png_rejects_large_images() {
+        let space = Space::new(1000, 1000);
+        let options = RenderOptions { cell_size: 100_000, ..RenderOptions::default() };
+        assert_eq!(None, render::get_image_size(&space, &options));
+        let file_path = std::env::temp_dir().join("render_to_png_rejects_large_images.png");
+        assert!(render::render_to_png(file_path.to_str().unwrap(), &space, &options).is_err());
+        assert!(!file_path.exists());
+        assert_eq!(Some((10_000, 10_000)), render::get_image_size(&space, &RenderOptions { cell_size: 10, ..RenderOptions::default() }));
+    }
+
+    #[test]
+    fn animation_frames_work() {
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0],
+        ]);
+        space.save_state(0);
+        for time in 1..=3 {
+            space.compute_conways_game_of_life_multithreaded();
+            space.save_state(time);
+        }
+        let mut options = AnimationOptions::new(1, 3);
+        options.render.cell_size = 2;
+        let frames = animation::render_frames(&space, &options).unwrap();
+        assert_eq!(3, frames.len());
+        assert_eq!((12, 10), (frames[0].width, frames[0].height));
+        assert_ne!(frames[0], frames[1]);
+        assert_eq!(frames[0], frames[2]);
+        options.crop_to_bounding_box = true;
+        let frames = animation::render_frames(&space, &options).unwrap();
+        assert_eq!((6, 6), (frames[0].width, frames[0].height));
+        assert!(animation::render_frames(&space, &AnimationOptions::new(2, 4)).is_err());
+        assert!(animation::render_frames(&space, &AnimationOptions::new(3, 2)).is_err());
+    }
+
+    #[test]
+    fn export_animation_works() {
+        let mut space = io::read_from_file("resources/glider.space").unwrap();
+        for time in 1..=4 {
+            space.compute_conways_game_of_life_multithreaded();
+            space.save_state(time);
+        }
+        let options = AnimationOptions::new(0, 4);
+        let gif_path = std::env::temp_dir().join("export_animation_works.gif");
+        let gif_str = gif_path.to_str().unwrap();
+        animation::export_animation(gif_str, &space, &options).unwrap();
+        assert_eq!(b"GIF89a", &std::fs::read(gif_str).unwrap()[0..6]);
+        std::fs::remove_file(gif_str).unwrap();
+        let apng_path = std::env::temp_dir().join("export_animation_works.apng");
+        let apng_str = apng_path.to_str().unwrap();
+        animation::export_animation(apng_str, &space, &options).unwrap();
+        let bytes = std::fs::read(apng_str).unwrap();
+        assert_eq!(&[0x89, b'P', b'N', b'G'], &bytes[0..4]);
+        assert!(bytes.windows(4).any(|chunk| chunk == b"acTL"));
+        std::fs::remove_file(apng_str).unwrap();
+        assert!(animation::export_animation("export.mp4", &space, &options).is_err());
+    }
+
+    #[test]
+    fn rule_parsing_works() {
+        assert_eq!(Rule::conways_game_of_life(), Rule::parse("B3/S23").unwrap());
+        assert_eq!(Rule::conways_game_of_life(), Rule::parse("23/3").unwrap());
+        assert_eq!(Rule::conways_game_of_life(), Rule::parse("s23/b3").unwrap());
+        let high_life = Rule::parse("B36/S23").unwrap();
+        assert!(high_life.is_born(6));
+        assert!(!high_life.survives(6));
+        assert_eq!("B36/S23", high_life.to_string());
+        assert_eq!("B/S", Rule::parse("B/S").unwrap().to_string());
+        assert!(Rule::parse("B9/S23").is_err());
+        assert!(Rule::parse("B3S23").is_err());
+    }
+
+    #[test]
+    fn isotropic_rules_work() {
+        assert_eq!("B2-a/S12", Rule::parse("B2-a/S12").unwrap().to_string());
+        assert_eq!("B3/S23-a4i", Rule::parse("B3/S23-a4i").unwrap().to_string());
+        assert_eq!("B2ce/S", Rule::parse("B2ec/S").unwrap().to_string());
+        assert_eq!(Rule::parse("B2/S").unwrap(), Rule::parse("B2cekain/S").unwrap());
+        assert_eq!(Rule::parse("B35/S").unwrap(), Rule::parse("B3-c3c5-i5i/S").unwrap());
+        let count_births = |rulestring: &str| {
+            let rule = Rule::parse(rulestring).unwrap();
+            (0..=255).filter(|&neighborhood| rule.is_born_with(neighborhood)).count()
+        };
+        assert_eq!(4, count_births("B2c/S"));
+        assert_eq!(2, count_births("B2n/S"));
+        assert_eq!(8, count_births("B3q/S"));
+        assert_eq!(1, count_births("B4c/S"));
+        assert_eq!(4, count_births("B4w/S"));
+        assert_eq!(2, count_births("B6i/S"));
+        assert_eq!(70 - 4, count_births("B4-t/S"));
+        assert!(Rule::parse("B2x/S").is_err());
+        assert!(Rule::parse("B1k/S").is_err());
+        assert!(Rule::parse("B0c/S").is_err());
+        assert!(Rule::parse("B2-/S").is_err());
+
+        // orthogonally adjacent neighbours are no 2a configuration, diagonally adjacent ones give 2e births
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0],
+            vec![0, 1, 1, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        space.rule = Rule::parse("B2-a/S").unwrap();
+        space.compute_conways_game_of_life_multithreaded();
+        assert_eq!(0, space.get_num_alive_cells());
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0],
+            vec![0, 1, 0, 0],
+            vec![0, 0, 1, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        space.rule = Rule::parse("B2-a/S").unwrap();
+        space.compute_conways_game_of_life_multithreaded();
+        assert!(space.check_cell_is_alive(2, 1));
+        assert!(space.check_cell_is_alive(1, 2));
+        assert_eq!(2, space.get_num_alive_cells());
+    }
+
+    #[test]
+    fn rule_and_boundary_work() {
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0],
+            vec![1, 0, 0, 1],
+            vec![0, 0, 0, 0],
+        ]);
+        space.boundary = Boundary::Toroidal;
+        assert_eq!(Some((3, 0)), space.get_neighbor_position(0, 0, -1, 0));
+        assert_eq!(Some((0, 2)), space.get_neighbor_position(0, 0, 0, -1));
+        space.boundary = Boundary::Bounded;
+        assert_eq!(None, space.get_neighbor_position(0, 0, -1, 0));
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0, 0],
+            vec![1, 0, 0, 1, 1],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        space.boundary = Boundary::Toroidal;
+        space.compute_conways_game_of_life_multithreaded();
+        // the blinker wraps around the left and right edge
+        assert!(space.check_cell_is_alive(4, 0));
+        assert!(space.check_cell_is_alive(4, 2));
+        assert_eq!(3, space.get_num_alive_cells());
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0],
+            vec![0, 1, 1, 0],
+            vec![0, 0, 0, 0],
+        ]);
+        space.rule = Rule::parse("B2/S").unwrap();
+        space.compute_conways_game_of_life_multithreaded();
+        assert!(!space.check_cell_is_alive(1, 1));
+        assert!(space.check_cell_is_alive(1, 0));
+        assert!(space.check_cell_is_alive(2, 2));
+        assert_eq!(4, space.get_num_alive_cells());
+    }
+
+    #[test]
+    fn session_works() {
+        let mut space = io::read_from_file("resources/glider.space").unwrap();
+        space.rule = Rule::parse("B36/S23").unwrap();
+        space.boundary = Boundary::Toroidal;
+        for time in 1..=5 {
+            space.compute_conways_game_of_life_multithreaded();
+            space.save_state(time);
+        }
+        space.load_state(3).unwrap();
+        space.let_cell_age(0, 0);
+        space.update = UpdateOptions { scheme: UpdateScheme::Asynchronous, probability: 0.75, rate: 0.25, block_size: 4 };
+        let view = RenderOptions {
+            colormap: Colormap::Magma,
+            tracing: true,
+            channel: Channel::Blend,
+            custom_gradient: Gradient::from_stops(vec![(0., (0., 0., 0.)), (0.5, (1., 0., 0.)), (1., (1., 1., 1.))]).unwrap(),
+            ..RenderOptions::default()
+        };
+        let file_path = std::env::temp_dir().join("session_works.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &view).unwrap();
+        let (mut loaded_space, loaded_view) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(view, loaded_view);
+        assert_eq!(space.rule, loaded_space.rule);
+        assert_eq!(space.boundary, loaded_space.boundary);
+        assert_eq!(space.update, loaded_space.update);
+        assert_eq!(space.get_seed(), loaded_space.get_seed());
+        assert_eq!(3, loaded_space.displayed_time);
+        assert_eq!(space.states_hash_map, loaded_space.states_hash_map);
+        assert_eq!(space.get_cells_with_energy(), loaded_space.get_cells_with_energy());
+        loaded_space.load_state(2).unwrap();
+        space.load_state(2).unwrap();
+        assert!(space.load_state(6).is_err());
+        assert_eq!(2, space.displayed_time);
+        assert_eq!(space.get_alive_cells(), loaded_space.get_alive_cells());
+    }
+
+    #[test]
+    fn session_rejects_invalid_files() {
+        let file_path = std::env::temp_dir().join("session_rejects_invalid_files.casession");
+        let file_str = file_path.to_str().unwrap();
+        std::fs::write(file_str, "#CellularAutomata session\nversion 10\nsize 3 3\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 2\nsize 3 3\nautomaton W300\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 3\nsize 3 3\nant 3 1 0 0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\nstate 0 5,1,0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\ndisplayed_time 1\nstate 0 1,1,0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\nstate 0 1,1,0\nstate 2 1,1,2\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\ndisplayed_time 1\nstate 1 1,1,0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\ncell 1 1 127 0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 1\nsize 3 3\nstate 0 1,1,4\n").unwrap();
+        let (space, _) = session::read_session(file_str).unwrap();
+        assert_eq!(vec![(1, 1, 255, 4)], *space.states_hash_map.get(&0).unwrap());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 2\nsize 3 3\nautomaton k3r1c777\nstate 0 1,1,100,0\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 2\nsize 3 3\nautomaton k3r1c777\nstate 0 1,1,127,0\n").unwrap();
+        assert!(session::read_session(file_str).is_ok());
+        std::fs::write(file_str, "#CellularAutomata session\nversion 8\nsize 3 3\nautomaton hpp\ncell 1 1 51 0\nvalues particles 1,1,48\n").unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::remove_file(file_str).unwrap();
+        assert!(session::read_session("resources/glider.space").is_err());
+    }
+
+    #[test]
+    fn seeded_random_fills_are_reproducible() {
+        let mut space = Space::new(40, 30);
+        let mut other_space = Space::new(40, 30);
+        space.set_seed(42);
+        other_space.set_seed(42);
+        space.revive_random_cells(100);
+        other_space.revive_random_cells(100);
+        assert_eq!(space.get_alive_cells(), other_space.get_alive_cells());
+        let options = SoupOptions { density: 0.3, ..SoupOptions::default() };
+        space.set_seed(7);
+        space.fill_random(&options);
+        let alive_cells: Vec<(u16, u16)> = space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+        space.set_seed(7);
+        space.fill_random(&options);
+        let refilled_alive_cells: Vec<(u16, u16)> = space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+        assert_eq!(alive_cells, refilled_alive_cells);
+        assert_eq!(7, space.get_seed());
+        // 30% of 1200 cells, with a generous margin
+        assert!((250..=470).contains(&alive_cells.len()));
+    }
+
+    #[test]
+    fn random_fill_density_and_region_work() {
+        let mut space = Space::new(20, 20);
+        space.revive_cell(0, 0);
+        space.fill_random(&SoupOptions { density: 1., region: Some(Region::new(5, 6, 4, 3)), symmetry: Symmetry::C1 });
+        assert_eq!(13, space.get_num_alive_cells());
+        assert!(space.check_cell_is_alive(0, 0));
+        assert!(space.check_cell_is_alive(5, 6));
+        assert!(space.check_cell_is_alive(8, 8));
+        assert!(!space.check_cell_is_alive(9, 8));
+        space.fill_random(&SoupOptions { density: 0., region: Some(Region::new(15, 15, 10, 10)), symmetry: Symmetry::C1 });
+        space.fill_random(&SoupOptions { density: 0., region: Some(Region::new(5, 6, 2, 3)), symmetry: Symmetry::C1 });
+        assert_eq!(7, space.get_num_alive_cells());
+    }
+
+    #[test]
+    fn symmetric_soups_work() {
+        for symmetry in Symmetry::ALL {
+            let mut space = Space::new(16, 12);
+            space.set_seed(3);
+            space.fill_random(&SoupOptions { density: 0.5, region: Some(Region::new(2, 1, 10, 10)), symmetry });
+            let is_alive = |x: u16, y: u16| space.check_cell_is_alive(2 + x, 1 + y);
+            for y in 0..10 {
+                for x in 0..10 {
+                    let alive = is_alive(x, y);
+                    match symmetry {
+                        Symmetry::C1 => {}
+                        Symmetry::C2 => assert_eq!(alive, is_alive(9 - x, 9 - y)),
+                        Symmetry::C4 => assert_eq!(alive, is_alive(9 - y, x)),
+                        Symmetry::D2 => assert_eq!(alive, is_alive(9 - x, y)),
+                        Symmetry::D4 => {
+                            assert_eq!(alive, is_alive(9 - x, y));
+                            assert_eq!(alive, is_alive(x, 9 - y));
+                        }
+                        Symmetry::D8 => {
+                            assert_eq!(alive, is_alive(y, x));
+                            assert_eq!(alive, is_alive(9 - x, y));
+                        }
+                    }
+                }
+            }
+            assert!(space.get_num_alive_cells() > 0);
+            assert_eq!(Some(symmetry), Symmetry::from_name(symmetry.name()));
+        }
+        let mut space = Space::new(10, 6);
+        space.fill_random(&SoupOptions { density: 1., region: None, symmetry: Symmetry::C4 });
+        assert_eq!(36, space.get_num_alive_cells());
+    }
+
+    #[test]
+    fn object_classification_works() {
+        let glider = Pattern::from_rows(&["010", "001", "111"]);
+        let rotated_glider = Pattern::from_rows(&["011", "101", "001"]);
+        assert_eq!(glider.canonical(), rotated_glider.canonical());
+        assert_ne!(glider, rotated_glider);
+        let rule = Rule::conways_game_of_life();
+
+        let classification = object::classify(&Pattern::from_rows(&["11", "11"]), rule);
+        assert_eq!(ObjectKind::StillLife, classification.kind);
+        assert_eq!("block", classification.get_code());
+        let classification = object::classify(&Pattern::from_rows(&["1", "1", "1"]), rule);
+        assert_eq!(ObjectKind::Oscillator { period: 2 }, classification.kind);
+        assert_eq!("blinker", classification.get_code());
+        let classification = object::classify(&glider, rule);
+        assert_eq!(ObjectKind::Spaceship { period: 4, dx: 1, dy: 1 }, classification.kind);
+        assert_eq!("glider", classification.get_code());
+        let classification = object::classify(&rotated_glider, rule);
+        assert_eq!("glider", classification.get_code());
+        let r_pentomino = Pattern::from_rows(&["011", "110", "010"]);
+        assert_eq!(ObjectKind::Unknown, object::classify(&r_pentomino, rule).kind);
+
+        let classification = object::classify(&Pattern::from_rows(&["11", "11"]), Rule::parse("B36/S23").unwrap());
+        assert_eq!("xs4_11/11", classification.get_code());
+    }
+
+    #[test]
+    fn object_separation_works() {
+        let space = Space::build_from_array(&[
+            vec![1, 1, 0, 0, 0, 0, 0],
+            vec![1, 1, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 1, 1, 1],
+        ]);
+        assert_eq!(2, space.separate_objects().len());
+        let beacon = Space::build_from_array(&[
+            vec![1, 1, 0, 0],
+            vec![1, 0, 0, 0],
+            vec![0, 0, 0, 1],
+            vec![0, 0, 1, 1],
+        ]);
+        let objects = beacon.separate_objects();
+        assert_eq!(1, objects.len());
+        assert_eq!("beacon", object::classify(&Pattern::new(&objects[0]), Rule::conways_game_of_life()).get_code());
+    }
+
+    #[test]
+    fn census_works() {
+        let options = SearchOptions { num_soups: 3, soup_size: 6, margin: 10, max_generations: 400, ..SearchOptions::default() };
+        let mut space = Space::new(30, 30);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (15, 0), (16, 0), (15, 1), (16, 1)] {
+            space.revive_cell(10 + x, 10 + y);
+        }
+        let result = census::run_space(space, &options);
+        assert!(result.stabilized);
+        assert!(!result.is_notable());
+        let mut objects = result.objects.clone();
+        objects.sort();
+        assert_eq!(vec!["block", "glider"], objects);
+
+        let census = census::search(&options).unwrap();
+        assert_eq!(3, census.num_soups);
+        assert_eq!(census, census::search(&options).unwrap());
+        for seed in 0..3 {
+            let result = census::run_soup(seed, &options).unwrap();
+            for code in &result.objects {
+                assert!(census.counts.contains_key(code));
+            }
+        }
+        let report = census::format_report(&census, &options);
+        assert!(report.starts_with("Census of 3 soups (B3/S23, C1, 6x6, density 0.5, seeds 0 to 2)"));
+
+        let dir = std::env::temp_dir().join("census_works");
+        census::write_census(dir.to_str().unwrap(), &census, &options).unwrap();
+        assert_eq!(report, std::fs::read_to_string(dir.join("census.txt")).unwrap());
+        for result in &census.notable_soups {
+            let soup = io::read_from_file(dir.join(format!("soup_{}.txt", result.seed)).to_str().unwrap()).unwrap();
+            assert_eq!(census::build_soup(result.seed, &options).unwrap().get_alive_cells(), soup.get_alive_cells());
+        }
+        let large_soup = SearchOptions { soup_size: 60_000, margin: 3000, ..options.clone() };
+        assert!(census::build_soup(0, &large_soup).is_err());
+        assert!(census::search(&large_soup).is_err());
+    }
+
+    #[test]
+    fn space_objects_work() {
+        let space = Space::build_from_array(&[
+            vec![0, 1, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0, 0, 1, 1, 0],
+            vec![1, 1, 1, 0, 0, 0, 0, 1, 1, 0],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            vec![0, 0, 1, 1, 1, 0, 0, 0, 0, 0],
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+        ]);
+        let classifier = Classifier::new();
+        let mut objects = space.get_objects(&classifier);
+        objects.sort_by_key(|object| (object.y, object.x));
+        let labels: Vec<String> = objects.iter().map(|object| object.get_label()).collect();
+        assert_eq!(vec!["glider", "block", "blinker"], labels);
+        assert_eq!((0, 0, 3, 3), (objects[0].x, objects[0].y, objects[0].width, objects[0].height));
+        assert_eq!((7, 1, 2, 2), (objects[1].x, objects[1].y, objects[1].width, objects[1].height));
+        assert_eq!((2, 6, 3, 1), (objects[2].x, objects[2].y, objects[2].width, objects[2].height));
+        assert_eq!(ObjectKind::Oscillator { period: 2 }, objects[2].classification.kind);
+
+        // a glider crossing the corner of a torus is one object
+        let mut space = Space::new(8, 8);
+        space.boundary = Boundary::Toroidal;
+        for (x, y) in [(0, 6), (1, 7), (7, 0), (0, 0), (1, 0)] {
+            space.revive_cell(x, y);
+        }
+        let objects = space.get_objects(&classifier);
+        assert_eq!(1, objects.len());
+        assert_eq!("glider", objects[0].get_label());
+        assert_eq!((7, 6, 3, 3), (objects[0].x, objects[0].y, objects[0].width, objects[0].height));
+        assert_eq!(5, objects[0].cells.len());
+        let mut simulation = Simulation::new(space.clone());
+        simulation.send(Command::ShowObjects(true));
+        simulation.send(Command::StepForward);
+        wait_for_time(&mut simulation, 1);
+        assert_eq!("glider", simulation.poll().objects.as_ref().unwrap()[0].get_label());
+        simulation.send(Command::ShowObjects(false));
+        simulation.send(Command::StepBackward);
+        wait_for_time(&mut simulation, 0);
+        assert!(simulation.poll().objects.is_none());
+        space.boundary = Boundary::Bounded;
+        assert_eq!(3, space.separate_objects().len());
+
+        let mut space = Space::build_from_array(&[vec![0, 1, 1], vec![1, 1, 0], vec![0, 1, 0]]);
+        space.rule = Rule::parse("B36/S23").unwrap();
+        assert_eq!("unknown", space.get_objects(&classifier)[0].get_label());
+        let lightweight_spaceship = Pattern::from_rows(&["01001", "10000", "10001", "11110"]);
+        let classification = object::classify(&lightweight_spaceship, Rule::conways_game_of_life());
+        assert_eq!("c/2 orthogonal spaceship", classification.kind.name());
+        assert_eq!("c/4 diagonal", object::get_velocity(-1, 1, 4));
+        assert_eq!("2c/5 orthogonal", object::get_velocity(0, -2, 5));
+        assert_eq!("(2,1)c/6", object::get_velocity(1, -2, 6));
+        assert_eq!("p15 oscillator", ObjectKind::Oscillator { period: 15 }.name());
+    }
+
+    #[test]
+    fn characterization_works() {
+        let mut space = Space::new(30, 20);
+        for (x, y) in [(1, 0), (2, 1), (0, 2), (1, 2), (2, 2), (20, 10), (21, 10), (22, 10)] {
+            space.revive_cell(x + 3, y + 3);
+        }
+        let characterization = space.characterize(Some(Region::new(0, 0, 10, 10)), 100);
+        assert_eq!(Some(4), characterization.period);
+        assert_eq!((1, 1), (characterization.dx, characterization.dy));
+        assert_eq!(Some("c/4 diagonal".to_string()), characterization.get_velocity());
+        assert_eq!((5, 5), characterization.get_population_range());
+        assert_eq!(4, characterization.phases.len());
+        assert_eq!(Phase { population: 5, x: 3, y: 3, width: 3, height: 3 }, characterization.phases[0]);
+        let report = characterization.get_report();
+        assert_eq!("period: 4", report[0]);
+        assert_eq!("phase 0: population 5, 3x3 at (3, 3)", report[4]);
+
+        let characterization = space.characterize(Some(Region::new(20, 10, 10, 10)), 100);
+        assert_eq!(Some(2), characterization.period);
+        assert_eq!(Some("stationary".to_string()), characterization.get_velocity());
+        assert_eq!(Phase { population: 3, x: 24, y: 12, width: 1, height: 3 }, characterization.phases[1]);
+
+        let lightweight_spaceship = Space::build_from_array(&[
+            vec![0, 1, 0, 0, 1],
+            vec![1, 0, 0, 0, 0],
+            vec![1, 0, 0, 0, 1],
+            vec![1, 1, 1, 1, 0],
+        ]);
+        let characterization = lightweight_spaceship.characterize(None, 100);
+        assert_eq!(Some(4), characterization.period);
+        assert_eq!((-2, 0), (characterization.dx, characterization.dy));
+        assert_eq!((9, 12), characterization.get_population_range());
+
+        let r_pentomino = Space::build_from_array(&[vec![0, 1, 1], vec![1, 1, 0], vec![0, 1, 0]]);
+        let characterization = r_pentomino.characterize(None, 20);
+        assert_eq!(None, characterization.period);
+        assert_eq!("no period within 20 generations", characterization.get_report()[0]);
+        assert_eq!(None, Space::new(5, 5).characterize(None, 20).period);
+
+        // the worker characterizes the pattern and publishes the report with the space
+        let mut simulation = Simulation::new(space);
+        simulation.send(Command::Characterize(Some(Region::new(0, 0, 10, 10)), 100));
+        simulation.send(Command::StepForward);
+        wait_for_time(&mut simulation, 1);
+        assert_eq!("period: 4", simulation.poll().characterization.as_ref().unwrap()[0]);
+        simulation.send(Command::ClearCharacterization);
+        simulation.send(Command::StepBackward);
+        wait_for_time(&mut simulation, 0);
+        assert!(simulation.poll().characterization.is_none());
+    }
+
+    #[test]
+    fn elementary_automata_work() {
+        let mut space = Space::new(9, 5);
+        space.automaton = Automaton::Linear(LinearRule::Elementary(90));
+        space.revive_cell(4, 0);
+        for time in 1..=4 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        let expected_space = Space::build_from_array(&[
+            vec![0, 0, 0, 0, 1, 0, 0, 0, 0],
+            vec![0, 0, 0, 1, 0, 1, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0, 1, 0, 0],
+            vec![0, 1, 0, 1, 0, 1, 0, 1, 0],
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 1],
+        ]);
+        assert_eq!(expected_space.get_alive_cells(), space.get_alive_cells());
+        // the full rows scroll up
+        space.compute_next_generation();
+        space.save_state(5);
+        let expected_space = Space::build_from_array(&[
+            vec![0, 0, 0, 1, 0, 1, 0, 0, 0],
+            vec![0, 0, 1, 0, 0, 0, 1, 0, 0],
+            vec![0, 1, 0, 1, 0, 1, 0, 1, 0],
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 1],
+            vec![0, 1, 0, 0, 0, 0, 0, 1, 0],
+        ]);
+        assert_eq!(expected_space.get_alive_cells(), space.get_alive_cells());
+        space.load_state(1).unwrap();
+        assert_eq!(vec![(3, 1), (4, 0), (5, 1)], space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect::<Vec<_>>());
+
+        let mut space = Space::new(5, 2);
+        space.automaton = Automaton::Linear(LinearRule::Elementary(30));
+        space.boundary = Boundary::Toroidal;
+        space.revive_cell(0, 0);
+        space.compute_next_generation();
+        space.save_state(1);
+        let row = |space: &Space, y: u16| (0..5).map(|x| space.check_cell_is_alive(x, y)).collect::<Vec<bool>>();
+        assert_eq!(vec![true, true, false, false, true], row(&space, 1));
+        assert!(space.check_cell_is_alive(0, 0));
+        space.compute_next_generation();
+        assert_eq!(vec![true, true, false, false, true], row(&space, 0));
+        assert_eq!(vec![false, false, true, true, true], row(&space, 1));
+    }
+
+    #[test]
+    fn totalistic_linear_automata_work() {
+        // code 777 in base 3 is 1001210: color of the neighbourhood sums 0 to 6
+        let rule = LinearRule::parse("k3r1c777").unwrap();
+        assert_eq!(LinearRule::totalistic(3, 1, 777), Some(rule));
+        assert_eq!(0, rule.get_next_color(&[0, 0, 0]));
+        assert_eq!(1, rule.get_next_color(&[0, 1, 0]));
+        assert_eq!(2, rule.get_next_color(&[1, 1, 0]));
+        assert_eq!(1, rule.get_next_color(&[2, 0, 1]));
+        assert_eq!(1, rule.get_next_color(&[2, 2, 2]));
+        assert_eq!("k3r1c777", rule.to_string());
+        assert_eq!(Ok(LinearRule::Elementary(110)), LinearRule::parse("W110").map_err(|error| error.message));
+        assert_eq!(Ok(LinearRule::Elementary(110)), LinearRule::parse("110").map_err(|error| error.message));
+        assert!(LinearRule::parse("k3r1c2187").is_err());
+        assert!(LinearRule::parse("k1r1c0").is_err());
+        assert!(LinearRule::parse("B3/S23").is_err());
+        assert_eq!(LinearRule::Elementary(255), LinearRule::Elementary(0).previous());
+        assert_eq!(LinearRule::totalistic(3, 1, 2186), LinearRule::totalistic(3, 1, 0).map(|rule| rule.previous()));
+        assert_eq!(LinearRule::totalistic(3, 1, 30), Some(LinearRule::Elementary(30).next_kind()));
+        assert_eq!(Some(Automaton::Linear(rule)), Automaton::from_name("k3r1c777"));
+        assert_eq!(Some(Automaton::LifeLike), Automaton::from_name("life"));
+
+        let mut space = Space::new(3, 3);
+        space.automaton = Automaton::Linear(rule);
+        space.get_cell_mut(1, 0).unwrap().set_state(automaton::get_cell_state(1, 3));
+        space.compute_next_generation();
+        space.save_state(1);
+        let colors: Vec<u8> = (0..3).map(|x| automaton::get_state_index(space.get_cell(x, 1).unwrap().get_state(), 3)).collect();
+        assert_eq!(vec![1, 1, 1], colors);
+        space.compute_next_generation();
+        space.save_state(2);
+        let colors: Vec<u8> = (0..3).map(|x| automaton::get_state_index(space.get_cell(x, 2).unwrap().get_state(), 3)).collect();
+        assert_eq!(vec![2, 1, 2], colors);
+        assert_eq!(7, space.states_hash_map.get(&2).unwrap().len());
+
+        let file_path = std::env::temp_dir().join("totalistic_linear_automata_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.automaton, loaded_space.automaton);
+        assert_eq!(space.states_hash_map, loaded_space.states_hash_map);
+        assert_eq!(space.get_cells_with_energy(), loaded_space.get_cells_with_energy());
+    }
+
+    #[test]
+    fn multi_state_automata_work() {
+        // electron (head 3, tail 2) running along a wire of conductors (1)
+        let rows: [[u8; 6]; 1] = [[2, 3, 1, 1, 1, 1]];
+        let mut space = Space::new(6, 1);
+        space.automaton = Automaton::MultiState(NamedRule::WireWorld);
+        for (x, state) in rows[0].iter().enumerate() {
+            space.get_cell_mut(x as u16, 0).unwrap().set_state_index(*state, 4);
+        }
+        let get_states = |space: &Space| -> Vec<u8> { (0..6).map(|x| space.get_cell(x, 0).unwrap().get_state_index(4)).collect() };
+        space.compute_next_generation();
+        assert_eq!(vec![1, 2, 3, 1, 1, 1], get_states(&space));
+        space.save_state(1);
+        space.compute_next_generation();
+        assert_eq!(vec![1, 1, 2, 3, 1, 1], get_states(&space));
+        space.load_state(1);
+        assert_eq!(vec![1, 2, 3, 1, 1, 1], get_states(&space));
+        assert_eq!(1, space.get_num_alive_cells());
+
+        let mut space = Space::build_from_array(&[vec![0, 0, 0, 0], vec![0, 1, 1, 0], vec![0, 0, 0, 0]]);
+        space.automaton = Automaton::MultiState(NamedRule::BriansBrain);
+        space.compute_next_generation();
+        let states: Vec<u8> = space.get_cells_with_energy().iter().map(|cell| cell.get_state_index(3)).collect();
+        assert_eq!(6, states.len());
+        assert_eq!(2, states.iter().filter(|&&state| state == 1).count());
+        assert_eq!(4, states.iter().filter(|&&state| state == 2).count());
+        assert!(space.get_cell(1, 0).unwrap().is_alive());
+
+        assert_eq!(Some(Automaton::MultiState(NamedRule::BriansBrain)), Automaton::from_name("briansbrain"));
+        assert_eq!(Automaton::MultiState(NamedRule::WireWorld), Automaton::LifeLike.next_multi_state());
+        assert!(matches!(Automaton::MultiState(NamedRule::BriansBrain).next_multi_state(), Automaton::Cyclic(_)));
+        assert_eq!(Some(&["off", "dying", "on"][..]), space.automaton.get_state_names());
+
+        let image = render::render(&space, &RenderOptions { cell_size: 1, ..RenderOptions::default() });
+        assert_eq!([26, 77, 230, 255], image.get_pixel(1, 1));
+        assert_eq!([255, 255, 255, 255], image.get_pixel(1, 0));
+        assert_eq!([0, 0, 0, 255], image.get_pixel(0, 0));
+    }
+
+    #[test]
+    fn switching_automata_works() {
+        let mut space = Space::build_from_array(&[vec![0, 0, 0, 0], vec![0, 1, 1, 0], vec![0, 0, 0, 0]]);
+        space.automaton = Automaton::MultiState(NamedRule::BriansBrain);
+        space.compute_next_generation();
+        space.save_state(1);
+        assert!(space.get_cells_with_energy().iter().any(|cell| cell.get_state() == 127));
+        space.set_automaton(Automaton::LifeLike);
+        // dying cells move to the aging grid of life and fade out from there
+        assert_eq!(119, space.get_cell(1, 1).unwrap().get_state());
+        assert!(space.get_cells_with_energy().iter().all(|cell| Cell::is_aging_state(cell.get_state())));
+        assert!(space.states_hash_map.get(&1).unwrap().iter().all(|saved_tuple| Cell::is_aging_state(saved_tuple.2)));
+        for _ in 0..20 {
+            space.compute_next_generation();
+        }
+        space.load_state(1).unwrap();
+        assert_eq!(4, space.get_num_alive_cells());
+
+        let mut cell = Cell::new(0, 0, 127);
+        cell.age();
+        assert_eq!(119, cell.get_state());
+
+        // alive cells have no particles, the lattice gas starts empty
+        space.set_automaton(Automaton::LatticeGas(GasModel::Hpp));
+        assert_eq!(0, space.get_cells_with_energy().len());
+        space.compute_next_generation();
+        assert_eq!(0, space.get_num_particles());
+    }
+
+    #[test]
+    fn turmites_work() {
+        let mut space = Space::new(11, 11);
+        space.automaton = Automaton::Turmite(TurmiteRule::langtons_ant());
+        space.add_ant(5, 5);
+        space.add_ant(5, 5);
+        assert_eq!(1, space.ants.len());
+        space.save_state(0);
+        for time in 1..=4 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        assert_eq!(vec![Ant::new(5, 5)], space.ants);
+        assert_eq!(4, space.get_num_alive_cells());
+        space.compute_next_generation();
+        assert_eq!(Ant { x: 4, y: 5, heading: Heading::West, state: 0 }, space.ants[0]);
+        assert!(!space.get_cell(5, 5).unwrap().is_alive());
+        space.load_state(2);
+        assert_eq!(vec![Ant { x: 6, y: 6, heading: Heading::South, state: 0 }], space.ants);
+        assert_eq!(2, space.get_num_alive_cells());
+        space.load_state(0);
+        assert_eq!(vec![Ant::new(5, 5)], space.ants);
+        assert_eq!(0, space.get_num_alive_cells());
+
+        space.load_state(2);
+        let file_path = std::env::temp_dir().join("turmites_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.automaton, loaded_space.automaton);
+        assert_eq!(space.ants, loaded_space.ants);
+        assert_eq!(space.ants_hash_map, loaded_space.ants_hash_map);
+
+        let mut space = Space::new(3, 3);
+        space.automaton = Automaton::Turmite(TurmiteRule::langtons_ant());
+        space.boundary = Boundary::Toroidal;
+        space.ants = vec![Ant { x: 1, y: 0, heading: Heading::West, state: 0 }];
+        space.compute_next_generation();
+        assert_eq!(vec![Ant { x: 1, y: 2, heading: Heading::North, state: 0 }], space.ants);
+        space.boundary = Boundary::Bounded;
+        space.kill_all_cells();
+        space.ants = vec![Ant { x: 1, y: 0, heading: Heading::West, state: 0 }];
+        space.compute_next_generation();
+        assert!(space.ants.is_empty());
+
+        let rule = TurmiteRule::parse("{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}").unwrap();
+        assert_eq!(2, rule.get_num_colors());
+        assert_eq!("{{{1,8,1},{1,8,1}},{{1,2,1},{0,1,0}}}", rule.to_string());
+        assert_eq!(Some(Automaton::Turmite(TurmiteRule::langtons_ant())), Automaton::from_name("turmite:{{{1,2,0},{0,8,0}}}"));
+        assert_eq!("turmite:RL", Automaton::Turmite(TurmiteRule::langtons_ant()).name());
+        assert_eq!(4, TurmiteRule::parse("llrr").unwrap().get_num_colors());
+        assert!(TurmiteRule::parse("R").is_err());
+        assert!(TurmiteRule::parse("RX").is_err());
+        assert!(TurmiteRule::parse("{{{1,2,0},{2,8,0}}}").is_err());
+        assert!(TurmiteRule::parse("{{{1,2,1},{0,8,0}}}").is_err());
+        assert_eq!(Automaton::Turmite(TurmiteRule::langtons_ant()), Automaton::LifeLike.next_turmite());
+    }
+
+    #[test]
+    fn continuous_automata_work() {
+        let rule = ContinuousRule::parse("lenia:R=5,mu=0.15,sigma=0.015,dt=0.1,b=1/0.5").unwrap();
+        assert_eq!("lenia:R=5,mu=0.15,sigma=0.015,dt=0.1,b=1/0.5", rule.to_string());
+        let ContinuousRule::Lenia(lenia_rule) = rule else { panic!() };
+        let kernel_sum: f32 = lenia_rule.get_kernel().iter().map(|entry| entry.2).sum();
+        assert!((kernel_sum - 1.).abs() < 1e-4);
+        assert!((lenia_rule.get_growth(0.15) - 1.).abs() < 1e-6);
+        assert!(ContinuousRule::parse("lenia:R=5,mu=0.15,sigma=0,dt=0.1,b=1").is_err());
+        assert!(ContinuousRule::parse("smoothlife:r=4,b=0.365/0.278,d=0.267/0.445,dt=0.05").is_err());
+        assert!(ContinuousRule::parse("lenia:R=5,mu=0.15").is_err());
+
+        let mut grid = ContinuousGrid::new(20, 20);
+        for y in 0..20 {
+            for x in 0..20 {
+                grid.set_value(x, y, 0.15);
+            }
+        }
+        grid.step(&rule, Boundary::Toroidal);
+        assert!((grid.get_value(3, 7) - 0.25).abs() < 1e-4);
+        let mut grid = ContinuousGrid::new(20, 20);
+        grid.set_value(10, 10, 1.);
+        grid.step(&rule, Boundary::Bounded);
+        assert!((grid.get_value(10, 10) - 0.9).abs() < 1e-4);
+        assert_eq!(0., grid.get_value(12, 10));
+
+        let mut space = Space::new(30, 30);
+        space.automaton = Automaton::LifeLike.next_continuous();
+        space.set_seed(7);
+        space.fill_random(&SoupOptions { region: Some(Region::new(5, 5, 16, 16)), ..SoupOptions::default() });
+        assert!(space.get_cells_with_energy().iter().any(|cell| cell.get_state() > 0 && cell.get_state() < 255));
+        space.save_state(0);
+        for time in 1..=3 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        let CellValues::Continuous(grid) = space.values.clone() else { panic!() };
+        assert_eq!(30, grid.x_dim());
+        let state = space.get_cell(12, 12).unwrap().get_state();
+        assert_eq!(state, (grid.get_value(12, 12) * 255.).round() as u8);
+        let saved_cells = space.states_hash_map.get(&3).unwrap().clone();
+        space.load_state(0).unwrap();
+        space.load_state(3).unwrap();
+        assert_eq!(saved_cells, *space.states_hash_map.get(&3).unwrap());
+        assert_eq!(state, space.get_cell(12, 12).unwrap().get_state());
+        assert_eq!(CellValues::Continuous(grid), space.values);
+        // the exact values are saved, so generations computed again from the history are the same
+        let saved_values = space.values_hash_map.get(&3).unwrap().clone();
+        space.load_state(1).unwrap();
+        space.compute_next_generation();
+        space.save_state(2);
+        space.compute_next_generation();
+        space.save_state(3);
+        assert_eq!(saved_cells, *space.states_hash_map.get(&3).unwrap());
+        assert_eq!(saved_values, space.values);
+
+        let file_path = std::env::temp_dir().join("continuous_automata_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.values, loaded_space.values);
+        assert_eq!(space.values_hash_map, loaded_space.values_hash_map);
+
+        // values too small to show in the cell states are kept and keep growing
+        let mut space = Space::new(5, 5);
+        space.automaton = Automaton::from_name("lenia:R=2,mu=0.001,sigma=0.01,dt=0.0001,b=1").unwrap();
+        space.boundary = Boundary::Toroidal;
+        let mut grid = ContinuousGrid::new(5, 5);
+        for y in 0..5 {
+            for x in 0..5 {
+                grid.set_value(x, y, 0.001);
+            }
+        }
+        space.values = CellValues::Continuous(grid);
+        space.compute_next_generation();
+        let CellValues::Continuous(next_grid) = &space.values else { panic!() };
+        assert_eq!(0, space.get_num_alive_cells());
+        assert!((next_grid.get_value(2, 2) - 0.0011).abs() < 1e-6);
+        space.set_automaton(Automaton::LifeLike);
+        assert_eq!(CellValues::Empty, space.values);
+        let smooth_life = Automaton::from_name("smoothlife:r=4,b=0.278/0.365,d=0.267/0.445,dt=0.05").unwrap();
+        assert_eq!(smooth_life, Automaton::LifeLike.next_continuous().next_continuous().next_continuous());
+        assert!(matches!(smooth_life.next_continuous(), Automaton::ReactionDiffusion(_)));
+        space.automaton = smooth_life;
+        space.compute_next_generation();
+    }
+
+    #[test]
+    fn reaction_diffusion_works() {
+        let rule = GrayScottRule::parse("grayscott:F=0.0367,k=0.0649,Du=1,Dv=0.5,steps=10").unwrap();
+        assert_eq!("grayscott:F=0.0367,k=0.0649,Du=1,Dv=0.5,steps=10", rule.to_string());
+        assert!(GrayScottRule::parse("grayscott:F=0.0367,k=0.0649,Du=2,Dv=0.5,steps=10").is_err());
+        assert!(GrayScottRule::parse("grayscott:F=0.0367,k=0.0649,Du=1,Dv=0.5,steps=0").is_err());
+        assert!(GrayScottRule::parse("grayscott:F=0.0367,k=0.0649").is_err());
+        assert_eq!((0., 0.), rule.get_change((1., 0.), (0., 0.)));
+
+        let mut space = Space::new(30, 30);
+        space.boundary = Boundary::Toroidal;
+        space.automaton = Automaton::ReactionDiffusion(rule);
+        for (x, y) in [(14, 14), (15, 14), (14, 15), (15, 15)] {
+            space.paint_chemical(x, y);
+        }
+        assert_eq!((0.5, 0.25), space.get_concentrations(14, 14));
+        assert_eq!(64, space.get_cell(14, 14).unwrap().get_state());
+        space.save_state(0);
+        for time in 1..=5 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        let (_, spread_v) = space.get_concentrations(17, 14);
+        assert!(spread_v > 0.);
+        assert!(!space.get_cell(0, 0).unwrap().is_alive() && space.get_cell(0, 0).unwrap().get_state() == 0);
+        let concentrations = space.get_concentrations(15, 15);
+        space.load_state(0).unwrap();
+        assert_eq!((0.5, 0.25), space.get_concentrations(14, 14));
+        space.load_state(5).unwrap();
+        assert_eq!(concentrations, space.get_concentrations(15, 15));
+        let CellValues::Chemicals(u, v) = &space.values else { panic!() };
+        assert_eq!((u.get_value(3, 20), v.get_value(3, 20)), space.get_concentrations(3, 20));
+
+        let file_path = std::env::temp_dir().join("reaction_diffusion_works.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.values, loaded_space.values);
+        assert_eq!(space.values_hash_map, loaded_space.values_hash_map);
+        assert_eq!((1., 0.), space.resized(40, 40).get_concentrations(35, 35));
+
+        let options = RenderOptions { cell_size: 1, colormap: Colormap::Grayscale, ..RenderOptions::default() };
+        let cell = space.get_cell(15, 15).unwrap();
+        let (u, v) = space.get_concentrations(15, 15);
+        assert_eq!(Colormap::Grayscale.map(v, &options.color, &options.custom_gradient), render::get_cell_color(&space, cell, &options));
+        let options = RenderOptions { channel: Channel::U, ..options };
+        assert_eq!(Colormap::Grayscale.map(1. - u, &options.color, &options.custom_gradient), render::get_cell_color(&space, cell, &options));
+        assert_eq!(Some(Channel::Blend), Channel::from_name("blend"));
+        assert_eq!(Channel::V, Channel::Blend.next());
+        assert_eq!(Some(space.automaton.clone()), Automaton::from_name(&space.automaton.name()));
+        // the cells only show v, they have no age
+        space.revive_cell(3, 3);
+        assert_eq!(None, space.get_birth_time(3, 3).unwrap());
+        assert_eq!(Some("u: 1.000  v: 0.000".to_string()), space.values.get_description(3, 3));
+    }
+
+    #[test]
+    fn cyclic_automata_work() {
+        let rule = CyclicRule::parse("cyclic:R1/T1/C14/NN").unwrap();
+        assert_eq!(Neighborhood::VonNeumann, rule.neighborhood);
+        assert_eq!("cyclic:R1/T1/C14/NN", rule.to_string());
+        assert_eq!(0, rule.get_successor(13));
+        assert_eq!(14, rule.get_palette().len());
+        assert_eq!(12, Neighborhood::VonNeumann.get_offsets(2).len());
+        assert_eq!(24, Neighborhood::Moore.get_offsets(2).len());
+        assert!(CyclicRule::parse("cyclic:R1/T9/C3/NM").is_err());
+        assert!(CyclicRule::parse("cyclic:R1/T1/C17/NM").is_err());
+        assert!(CyclicRule::parse("cyclic:R1/T1/C3/NX").is_err());
+        assert!(CyclicRule::parse("cyclic:R1/C3").is_err());
+
+        let mut space = Space::new(5, 5);
+        space.automaton = Automaton::Cyclic(CyclicRule::parse("cyclic:R1/T2/C3/NM").unwrap());
+        let set_state = |space: &mut Space, x: u16, y: u16, state: u8| space.get_cell_mut(x, y).unwrap().set_state_index(state, 3);
+        set_state(&mut space, 1, 2, 1);
+        set_state(&mut space, 3, 2, 1);
+        set_state(&mut space, 2, 2, 2);
+        space.compute_next_generation();
+        let get_state = |space: &Space, x: u16, y: u16| space.get_cell(x, y).unwrap().get_state_index(3);
+        assert_eq!(1, get_state(&space, 2, 1));
+        assert_eq!(1, get_state(&space, 2, 3));
+        assert_eq!(0, get_state(&space, 0, 0));
+        assert_eq!(0, get_state(&space, 2, 2));
+        assert_eq!(1, get_state(&space, 1, 2));
+
+        let rule = CyclicRule::parse("rps:R1/T3/C3/NM").unwrap();
+        assert_eq!(CyclicKind::RockPaperScissors, rule.kind);
+        assert_eq!(&["rock", "paper", "scissors"][..], rule.get_state_names());
+        let mut space = Space::new(20, 20);
+        let mut other_space = Space::new(20, 20);
+        for space in [&mut space, &mut other_space] {
+            space.automaton = Automaton::Cyclic(rule);
+            space.set_seed(3);
+            space.fill_random(&SoupOptions::default());
+            space.compute_next_generation();
+        }
+        assert_eq!(space.get_cells_with_energy(), other_space.get_cells_with_energy());
+        assert!((0..3).all(|state| space.get_cells_with_energy().len() < 400 && space.flat().iter().any(|cell| cell.get_state_index(3) == state)));
+        assert_eq!(Some(space.automaton), Automaton::from_name("rps:R1/T3/C3/NM"));
+
+        let rule = CyclicRule::parse("gh:R1/T1/C5/NN").unwrap();
+        assert_eq!(CyclicKind::GreenbergHastings, rule.kind);
+        assert_eq!("gh:R1/T1/C5/NN", rule.to_string());
+        assert_eq!(&["resting", "excited", "refractory 1", "refractory 2", "refractory 3"][..], rule.get_state_names());
+        let mut space = Space::new(5, 5);
+        space.automaton = Automaton::Cyclic(rule);
+        space.get_cell_mut(2, 2).unwrap().set_state_index(1, 5);
+        let get_state = |space: &Space, x: u16, y: u16| space.get_cell(x, y).unwrap().get_state_index(5);
+        space.compute_next_generation();
+        assert_eq!(2, get_state(&space, 2, 2));
+        assert!([(2, 1), (3, 2), (2, 3), (1, 2)].iter().all(|&(x, y)| get_state(&space, x, y) == 1));
+        assert_eq!(0, get_state(&space, 1, 1));
+        space.compute_next_generation();
+        assert_eq!(3, get_state(&space, 2, 2));
+        assert_eq!(1, get_state(&space, 1, 1));
+        space.compute_next_generation();
+        space.compute_next_generation();
+        assert_eq!(0, get_state(&space, 2, 2));
+    }
+
+    #[test]
+    fn forest_fires_work() {
+        let rule = ForestFireRule::parse("forestfire:p=0.02,f=0.0002").unwrap();
+        assert_eq!("forestfire:p=0.02,f=0.0002", rule.to_string());
+        assert_eq!(Some(Automaton::ForestFire(rule)), Automaton::from_name("forestfire:p=0.02,f=0.0002"));
+        assert!(ForestFireRule::parse("forestfire:p=2,f=0").is_err());
+        assert!(ForestFireRule::parse("forestfire:p=0.1").is_err());
+
+        // two groves set on fire in a corner, the trees burnt down afterwards make up the sizes
+        let mut space = Space::new(10, 10);
+        space.automaton = Automaton::ForestFire(ForestFireRule { growth: 0., lightning: 0. });
+        for (xs, ys) in [(0..3, 0..3), (5..10, 5..7)] {
+            for x in xs {
+                for y in ys.clone() {
+                    space.get_cell_mut(x, y).unwrap().set_state_index(1, 3);
+                }
+            }
+        }
+        let mut fires = ValueGrid::new(10, 10);
+        for (fire, (x, y)) in [(1, (0, 0)), (2, (5, 5))] {
+            space.get_cell_mut(x, y).unwrap().set_state_index(2, 3);
+            fires.set_value(x, y, fire);
+        }
+        space.values = CellValues::Fires(fires);
+        space.save_state(0);
+        space.compute_next_generation();
+        space.save_state(1);
+        assert_eq!(0, space.fire_statistics.get_num_fires(1));
+        assert_eq!(2, space.fire_statistics.get_num_burning_fires(1));
+        for time in 2..=7 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        assert_eq!(2, space.fire_statistics.get_num_fires(7));
+        assert!(space.flat().iter().all(|cell| cell.get_state_index(3) == 0));
+        let histogram: Vec<(usize, usize)> = space.fire_statistics.get_histogram(7).iter().map(|(&size, &count)| (size, count)).collect();
+        assert_eq!(vec![(8, 1), (9, 1)], histogram);
+
+        let path = std::env::temp_dir().join("forest_fires_work.csv");
+        space.fire_statistics.write_histogram_csv(path.to_str().unwrap(), 7).unwrap();
+        assert_eq!("size,count\n8,1\n9,1\n", std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(path).unwrap();
+
+        let file_path = std::env::temp_dir().join("forest_fires_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.fire_statistics, loaded_space.fire_statistics);
+        assert_eq!(space.values_hash_map, loaded_space.values_hash_map);
+
+        // earlier generations show the fires up to them, computing again from one replaces the later fires
+        assert_eq!(2, space.fire_statistics.get_num_burning_fires(1));
+        assert!(space.fire_statistics.get_num_fires(3) < 2);
+        space.load_state(1).unwrap();
+        for time in 2..=7 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        assert_eq!(vec![(8, 1), (9, 1)], space.fire_statistics.get_histogram(7).into_iter().collect::<Vec<_>>());
+
+        // a lightning strike in a full forest burns it down
+        let mut space = Space::new(7, 7);
+        space.automaton = Automaton::ForestFire(ForestFireRule { growth: 0., lightning: 0.05 });
+        space.fill_random(&SoupOptions { density: 1., ..SoupOptions::default() });
+        assert!(space.flat().iter().all(|cell| cell.get_state_index(3) == 1));
+        space.set_seed(1);
+        let mut time = 0;
+        while space.fire_statistics.get_num_burning_fires(time) == 0 {
+            space.compute_next_generation();
+            time += 1;
+            space.save_state(time);
+        }
+        space.automaton = Automaton::ForestFire(ForestFireRule { growth: 0., lightning: 0. });
+        for _ in 0..13 {
+            space.compute_next_generation();
+            time += 1;
+            space.save_state(time);
+        }
+        assert_eq!(49, space.fire_statistics.get_histogram(time).iter().map(|(size, count)| size * count).sum::<usize>());
+    }
+
+    #[test]
+    fn sandpiles_work() {
+        let rule = SandpileRule::parse("sandpile:center,n=50").unwrap();
+        assert_eq!(GrainDrop::Center, rule.drop);
+        assert_eq!("sandpile:center,n=50", rule.to_string());
+        assert_eq!(SandpileRule { drop: GrainDrop::Random, grains: 1 }, SandpileRule::parse("sandpile:random").unwrap());
+        assert_eq!(Some(Automaton::Sandpile(rule)), Automaton::from_name("sandpile:center,n=50"));
+        assert!(SandpileRule::parse("sandpile:corner,n=1").is_err());
+        assert!(SandpileRule::parse("sandpile:random,n=1.5").is_err());
+
+        let get_heights = |space: &Space| space.flat().iter().map(|cell| space.get_height(cell.x, cell.y)).collect::<Vec<u32>>();
+        let mut space = Space::new(5, 5);
+        space.automaton = Automaton::Sandpile(SandpileRule { drop: GrainDrop::Center, grains: 3 });
+        space.compute_next_generation();
+        space.save_state(1);
+        assert_eq!(3, space.get_height(2, 2));
+        assert_eq!(0, space.avalanche_statistics.get_num_avalanches(1));
+        space.drop_grain(2, 2);
+        assert_eq!(3, space.avalanche_statistics.get_num_drops(2));
+        space.save_state(2);
+        assert_eq!(0, space.get_height(2, 2));
+        assert!([(2, 1), (3, 2), (2, 3), (1, 2)].iter().all(|&(x, y)| space.get_height(x, y) == 1));
+        assert_eq!(4, space.avalanche_statistics.get_num_drops(2));
+        assert_eq!(Some(&1), space.avalanche_statistics.get_size_histogram(2).get(&1));
+        assert_eq!(Some(&1), space.avalanche_statistics.get_duration_histogram(2).get(&1));
+        assert_eq!(0, space.avalanche_statistics.get_num_avalanches(1));
+
+        // a tall pile relaxes to a stable, symmetric pattern, grains only get lost over the edges
+        let mut space = Space::new(21, 21);
+        space.automaton = Automaton::Sandpile(SandpileRule { drop: GrainDrop::Center, grains: 0 });
+        space.set_height(10, 10, 300);
+        assert_eq!(4, space.get_cell(10, 10).unwrap().get_state_index(5));
+        space.compute_next_generation();
+        let heights = get_heights(&space);
+        assert!(heights.iter().all(|&height| height < 4));
+        assert_eq!(300, heights.iter().sum::<u32>());
+        assert!((0..21).all(|x| (0..21).all(|y| space.get_height(x, y) == space.get_height(y, x) && space.get_height(x, y) == space.get_height(20 - x, y))));
+
+        // the stable pile does not depend on the order of the drops
+        let mut piles = Vec::new();
+        for positions in [[(1, 1), (1, 2), (2, 2)], [(2, 2), (1, 2), (1, 1)]] {
+            let mut space = Space::new(4, 4);
+            space.automaton = Automaton::Sandpile(SandpileRule { drop: GrainDrop::Random, grains: 0 });
+            for (x, y) in (0..4).flat_map(|x| (0..4).map(move |y| (x, y))) {
+                space.set_height(x, y, 3);
+            }
+            for (x, y) in positions {
+                space.drop_grain(x, y);
+            }
+            piles.push(get_heights(&space));
+        }
+        assert_eq!(piles[0], piles[1]);
+
+        let mut space = Space::new(12, 12);
+        space.automaton = Automaton::Sandpile(SandpileRule { drop: GrainDrop::Random, grains: 20 });
+        space.set_seed(4);
+        space.fill_random(&SoupOptions::default());
+        space.save_state(0);
+        for time in 1..=10 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        let statistics = space.avalanche_statistics.clone();
+        assert_eq!(200, statistics.get_num_drops(10));
+        assert_eq!(100, statistics.get_num_drops(5));
+        assert!(statistics.get_num_avalanches(10) > 0);
+        assert_eq!(statistics.get_num_avalanches(10), statistics.get_duration_histogram(10).values().sum::<usize>());
+        let last_pile = get_heights(&space);
+        space.load_state(5).unwrap();
+        space.load_state(10).unwrap();
+        assert_eq!(last_pile, get_heights(&space));
+
+        let path = std::env::temp_dir().join("sandpiles_work.csv");
+        statistics.write_histogram_csv(path.to_str().unwrap(), 10).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        std::fs::remove_file(path).unwrap();
+        assert!(content.starts_with("measure,value,count\nsize,1,"));
+        assert_eq!(1 + statistics.get_size_histogram(10).len() + statistics.get_duration_histogram(10).len(), content.lines().count());
+
+        let file_path = std::env::temp_dir().join("sandpiles_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(statistics, loaded_space.avalanche_statistics);
+        assert_eq!(space.values_hash_map, loaded_space.values_hash_map);
+        assert_eq!(last_pile, get_heights(&loaded_space));
+
+        // painted cells get the height of their state
+        let mut painted_space = loaded_space.clone();
+        painted_space.get_cell_mut(0, 0).unwrap().set_state_index(3, 5);
+        assert_eq!(3, painted_space.get_height(0, 0));
+
+        // computing again from an earlier generation replaces the later avalanches
+        space.load_state(5).unwrap();
+        space.compute_next_generation();
+        space.save_state(6);
+        assert_eq!(120, space.avalanche_statistics.get_num_drops(10));
+        assert_eq!(statistics.get_num_drops(5), space.avalanche_statistics.get_num_drops(5));
+    }
+
+    #[test]
+    fn lattice_gases_work() {
+        assert_eq!(Some(Automaton::LatticeGas(GasModel::Fhp)), Automaton::from_name("fhp"));
+        assert_eq!(6, Automaton::LatticeGas(GasModel::Hpp).get_num_states());
+        assert_eq!(Some("obstacle"), Automaton::LatticeGas(GasModel::Fhp).get_state_names().unwrap().last().copied());
+        assert_eq!(0b1010, GasModel::Hpp.collide(0b0101, false));
+        assert_eq!(0b010010, GasModel::Fhp.collide(0b001001, true));
+        assert_eq!(0b100100, GasModel::Fhp.collide(0b001001, false));
+        assert_eq!(0b101010, GasModel::Fhp.collide(0b010101, false));
+        assert_eq!(0b000011, GasModel::Fhp.collide(0b000011, true));
+
+        // head-on particles meet, turn and leave north and south
+        let mut space = Space::new(6, 5);
+        space.automaton = Automaton::LatticeGas(GasModel::Hpp);
+        space.set_particles(1, 2, 0b0001, GasModel::Hpp);
+        space.set_particles(3, 2, 0b0100, GasModel::Hpp);
+        space.compute_next_generation();
+        assert_eq!(0b0101, space.get_particles(2, 2));
+        space.compute_next_generation();
+        assert_eq!(0b0010, space.get_particles(2, 3));
+        assert_eq!(0b1000, space.get_particles(2, 1));
+        assert_eq!(2, space.get_num_particles());
+
+        // particles bounce back from the edges of a bounded space and from obstacles
+        let mut space = Space::new(6, 5);
+        space.automaton = Automaton::LatticeGas(GasModel::Hpp);
+        space.set_particles(5, 0, 0b0001, GasModel::Hpp);
+        space.paint_lattice_gas(3, 3, 5, GasModel::Hpp);
+        space.set_particles(2, 3, 0b0001, GasModel::Hpp);
+        space.compute_next_generation();
+        assert_eq!(0b0100, space.get_particles(5, 0));
+        assert_eq!(5, space.get_cell(3, 3).unwrap().get_state_index(6));
+        space.compute_next_generation();
+        assert_eq!(0b0100, space.get_particles(4, 0));
+        assert_eq!(0b0100, space.get_particles(2, 3));
+        assert_eq!(5, space.get_cell(3, 3).unwrap().get_state_index(6));
+        // the obstacle has no age, the inspector shows the particles instead
+        assert_eq!(None, space.get_birth_time(3, 3).unwrap());
+        assert_eq!(Some("particles: 1".to_string()), space.values.get_description(2, 3));
+        space.get_cell_mut(2, 3).unwrap().kill();
+        assert_eq!(0, space.get_particles(2, 3));
+
+        // FHP particles go around the hexagons of the shifted rows and come back
+        let mut space = Space::new(8, 8);
+        space.automaton = Automaton::LatticeGas(GasModel::Fhp);
+        space.set_particles(3, 2, 0b000010, GasModel::Fhp);
+        space.compute_next_generation();
+        assert_eq!(0b000010, space.get_particles(3, 3));
+        space.compute_next_generation();
+        assert_eq!(0b000010, space.get_particles(4, 4));
+        let field = space.get_velocity_field(GasModel::Fhp, 4);
+        assert_eq!(4, field.len());
+        let ((x, y), (vx, vy)) = field[3];
+        assert_eq!((6., 6.), (x, y));
+        assert!((vx - 0.5 / 16.).abs() < 1e-6 && (vy - 0.866 / 16.).abs() < 1e-3);
+
+        // mass is conserved, and runs repeat with the seed
+        let mut spaces = Vec::new();
+        for _ in 0..2 {
+            let mut space = Space::new(16, 16);
+            space.automaton = Automaton::LatticeGas(GasModel::Fhp);
+            space.set_seed(9);
+            space.fill_random(&SoupOptions::default());
+            space.paint_lattice_gas(8, 8, 7, GasModel::Fhp);
+            let num_particles = space.get_num_particles();
+            assert!(num_particles > 0);
+            for _ in 0..20 {
+                space.compute_next_generation();
+                assert_eq!(num_particles, space.get_num_particles());
+            }
+            spaces.push(space);
+        }
+        assert_eq!(spaces[0].get_cells_with_energy(), spaces[1].get_cells_with_energy());
+        assert_eq!(7, spaces[0].get_cell(8, 8).unwrap().get_state_index(8));
+
+        let mut space = spaces.pop().unwrap();
+        space.save_state(0);
+        space.compute_next_generation();
+        space.save_state(1);
+        let file_path = std::env::temp_dir().join("lattice_gases_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.values, loaded_space.values);
+        assert_eq!(space.values_hash_map, loaded_space.values_hash_map);
+
+        // HPP keeps the particles of FHP only if they head in its directions
+        let mut hpp_space = space.clone();
+        hpp_space.set_automaton(Automaton::LatticeGas(GasModel::Hpp));
+        assert_eq!(0, hpp_space.get_num_particles());
+        assert_eq!(0, hpp_space.get_cells_with_energy().len());
+        space.kill_all_cells();
+        space.set_particles(1, 1, 0b0101, GasModel::Fhp);
+        space.set_automaton(Automaton::LatticeGas(GasModel::Hpp));
+        assert_eq!(2, space.get_num_particles());
+        assert_eq!(2, space.get_cell(1, 1).unwrap().get_state_index(6));
+    }
+
+    #[test]
+    fn majority_and_segregation_work() {
+        assert_eq!(Rule::parse("B4678/S35678").unwrap(), Rule::parse("anneal").unwrap());
+        assert_eq!("B5678/S45678", Rule::parse("Majority").unwrap().to_string());
+        for (rulestring, num_alive_cells) in [("majority", 5), ("anneal", 9)] {
+            let mut space = Space::build_from_array(&[
+                vec![0, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 0, 0, 0, 0, 0],
+                vec![0, 0, 1, 1, 1, 0, 0],
+                vec![0, 0, 1, 1, 1, 0, 0],
+                vec![0, 0, 1, 1, 1, 0, 0],
+                vec![0, 0, 0, 0, 0, 0, 0],
+                vec![0, 1, 0, 0, 0, 0, 0],
+            ]);
+            space.rule = Rule::parse(rulestring).unwrap();
+            space.compute_next_generation();
+            assert_eq!(num_alive_cells, space.get_num_alive_cells());
+        }
+
+        let rule = SchellingRule::parse("schelling:t=0.375").unwrap();
+        assert_eq!("schelling:t=0.375", rule.to_string());
+        assert_eq!(Some(Automaton::Schelling(rule)), Automaton::from_name("schelling:t=0.375"));
+        assert!(SchellingRule::parse("schelling:t=2").is_err());
+
+        // separated populations are content and stay
+        let mut space = Space::new(5, 4);
+        space.automaton = Automaton::Schelling(rule);
+        for x in [0, 1, 3, 4] {
+            for y in 0..4 {
+                space.get_cell_mut(x, y).unwrap().set_state_index(if x < 2 { 1 } else { 2 }, 3);
+            }
+        }
+        assert_eq!(Some(1.), space.get_segregation_index());
+        let cells: Vec<(u16, u16, u8)> = space.get_cells_with_energy().iter().map(|cell| (cell.x, cell.y, cell.get_state())).collect();
+        space.compute_next_generation();
+        space.save_state(1);
+        assert_eq!(cells, space.get_cells_with_energy().iter().map(|cell| (cell.x, cell.y, cell.get_state())).collect::<Vec<_>>());
+        assert_eq!(Some(0), space.segregation_statistics.get_moves(1));
+
+        // a mixed soup segregates, keeping the populations
+        let count = |space: &Space, population: u8| space.flat().iter().filter(|cell| cell.get_state_index(3) == population).count();
+        let mut space = Space::new(20, 20);
+        space.automaton = Automaton::Schelling(rule);
+        space.set_seed(2);
+        space.fill_random(&SoupOptions { density: 0.8, ..SoupOptions::default() });
+        let populations = (count(&space, 1), count(&space, 2));
+        space.save_state(0);
+        for time in 1..=20 {
+            space.compute_next_generation();
+            space.save_state(time);
+        }
+        assert_eq!(populations, (count(&space, 1), count(&space, 2)));
+        let statistics = &space.segregation_statistics;
+        assert_eq!(21, statistics.get_indices().len());
+        assert!(statistics.get_index(20).unwrap() > statistics.get_index(0).unwrap() + 0.1);
+        assert!(statistics.get_moves(1).unwrap() > 0);
+        assert_eq!(statistics.get_index(20), space.get_segregation_index());
+
+        let file_path = std::env::temp_dir().join("majority_and_segregation_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!(space.segregation_statistics, loaded_space.segregation_statistics);
+
+        // computing again from an earlier generation replaces the later statistics
+        space.load_state(5).unwrap();
+        space.compute_next_generation();
+        assert_eq!(7, space.segregation_statistics.get_indices().len());
+    }
+
+    #[test]
+    fn margolus_automata_work() {
+        let mut space = Space::new(8, 8);
+        space.automaton = Automaton::Margolus(BlockRule::billiard_ball());
+        space.revive_cell(0, 0);
+        space.save_state(0);
+        for time in 1..=3 {
+            space.compute_next_generation();
+            space.save_state(time);
+            assert!(space.check_cell_is_alive(time as u16, time as u16));
+            assert_eq!(1, space.get_num_alive_cells());
+        }
+
+        let mut space = Space::new(6, 6);
+        space.automaton = Automaton::Margolus(BlockRule::tron());
+        space.compute_next_generation();
+        assert_eq!(36, space.get_num_alive_cells());
+        space.displayed_time = 1;
+        space.compute_next_generation();
+        assert_eq!(20, space.get_num_alive_cells());
+
+        for (boundary, x_dim, y_dim) in [(Boundary::Toroidal, 20, 16), (Boundary::Bounded, 19, 15), (Boundary::Toroidal, 17, 16)] {
+            let mut space = Space::new(x_dim, y_dim);
+            space.automaton = Automaton::Margolus(BlockRule::critters());
+            space.boundary = boundary;
+            space.set_seed(11);
+            space.fill_random(&SoupOptions::default());
+            space.save_state(0);
+            for time in 1..=10 {
+                space.compute_next_generation();
+                space.save_state(time);
+            }
+            let alive_cells: Vec<(u16, u16)> = space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect();
+            while space.compute_previous_generation() {}
+            assert_eq!(0, space.displayed_time);
+            assert_eq!(*space.states_hash_map.get(&0).unwrap(), space.get_saved_cells().iter().map(|cell| (cell.x, cell.y, cell.get_state(), cell.get_generations_alive())).collect::<Vec<_>>());
+            for _ in 1..=10 {
+                space.compute_next_generation();
+                space.displayed_time += 1;
+            }
+            assert_eq!(alive_cells, space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect::<Vec<_>>());
+        }
+
+        assert_eq!(Some(BlockRule::tron()), BlockRule::parse("margolus:15,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0").ok());
+        assert_eq!("margolus:tron", BlockRule::tron().to_string());
+        let sand = BlockRule::parse("margolus:0,0,0,3,1,3,3,7,2,3,3,11,12,13,14,15").unwrap();
+        assert!(!sand.is_reversible());
+        assert!(BlockRule::critters().is_reversible());
+        assert_eq!(Some(Automaton::Margolus(sand)), Automaton::from_name(&sand.to_string()));
+        assert!(BlockRule::parse("margolus:0,1,2").is_err());
+        assert!(BlockRule::parse("margolus:16,1,2,3,4,5,6,7,8,9,10,11,12,13,14,0").is_err());
+        let mut space = Space::new(4, 4);
+        space.automaton = Automaton::Margolus(sand);
+        space.displayed_time = 1;
+        assert!(!space.compute_previous_generation());
+    }
+
+    const WIREWORLD_TABLE: &str = "@RULE WireWorldTable
+# the states of the built-in WireWorld: empty, conductor, tail and head
+@TABLE
+n_states:4
+neighborhood:Moore
+symmetries:permute
+var a={0,1,2,3}
+var b={a}
+var c={a}
+var d={a}
+var e={a}
+var f={a}
+var g={a}
+var h={a}
+var o={0,1,2}
+var p={o}
+var q={o}
+var r={o}
+var s={o}
+var t={o}
+var u={o}
+3,a,b,c,d,e,f,g,h,2
+2,a,b,c,d,e,f,g,h,1
+1,3,o,p,q,r,s,t,u,3
+1,3,3,o,p,q,r,s,t,3
+@COLORS
+1 255 128 0
+";
+
+    #[test]
+    fn rule_tables_work() {
+        let rule = TableRule::new(RuleTable::parse(WIREWORLD_TABLE).unwrap());
+        assert_eq!(rule, TableRule::new(RuleTable::parse(WIREWORLD_TABLE).unwrap()));
+        assert_eq!("table:WireWorldTable", Automaton::Table(rule.clone()).name());
+        assert_eq!(None, Automaton::from_name("table:WireWorldTable"));
+        assert_eq!(4, Automaton::Table(rule.clone()).get_num_states());
+        let colors = rule.get_table().get_colors();
+        assert_eq!((0., 0., 0.), colors[0]);
+        assert_eq!((1., 128. / 255., 0.), colors[1]);
+        assert_eq!((1., 1., 0.), colors[3]);
+        let mut table_space = Space::new(30, 20);
+        table_space.automaton = Automaton::Table(rule.clone());
+        table_space.set_seed(5);
+        table_space.fill_random(&SoupOptions::default());
+        let mut space = table_space.clone();
+        space.automaton = Automaton::MultiState(NamedRule::WireWorld);
+        for _ in 0..20 {
+            space.compute_next_generation();
+            table_space.compute_next_generation();
+        }
+        assert!(space.get_num_alive_cells() > 0);
+        assert_eq!(space.get_saved_cells(), table_space.get_saved_cells());
+
+        // a von Neumann table in compact form, the signal moves north and leaves the bounded space
+        let signal = RuleTable::parse("@RULE Signal\n@TABLE\nn_states:2\nneighborhood:vonNeumann\nsymmetries:none\nvar a={0,1}\nvar b={a}\nvar c={a}\nvar d={a}\n000101\n1,a,b,c,d,0\n").unwrap();
+        let mut space = Space::new(5, 5);
+        space.automaton = Automaton::Table(TableRule::new(signal));
+        space.revive_cell(2, 2);
+        space.compute_next_generation();
+        assert_eq!(vec![(2, 1)], space.get_alive_cells().iter().map(|cell| (cell.x, cell.y)).collect::<Vec<_>>());
+        space.compute_next_generation();
+        space.compute_next_generation();
+        assert_eq!(0, space.get_num_alive_cells());
+
+        // variables take the same value everywhere in a transition, symmetries add the rotated transitions
+        let bound = RuleTable::parse("@RULE Bound\n@TABLE\nn_states:3\nneighborhood:vonNeumann\nsymmetries:rotate4\nvar a={1,2}\n0,a,0,a,0,a\n").unwrap();
+        assert_eq!(1, bound.get_next_state(0, &mut [1, 0, 1, 0]));
+        assert_eq!(2, bound.get_next_state(0, &mut [0, 2, 0, 2]));
+        assert_eq!(0, bound.get_next_state(0, &mut [1, 0, 2, 0]));
+        assert_eq!(table::Symmetry::Rotate4, bound.get_symmetry());
+        assert_eq!(4, bound.get_num_transitions());
+
+        let header = "@RULE Invalid\n@TABLE\nn_states:3\nneighborhood:vonNeumann\n";
+        assert!(RuleTable::parse(&format!("{}symmetries:rotate8\n0,1,0,0,0,1\n", header)).is_err());
+        assert!(RuleTable::parse(&format!("{}symmetries:none\n0,3,0,0,0,1\n", header)).is_err());
+        assert!(RuleTable::parse(&format!("{}symmetries:none\n0,1,0,0,1\n", header)).is_err());
+        assert!(RuleTable::parse(&format!("{}symmetries:none\n0,x,0,0,0,1\n", header)).is_err());
+        assert!(RuleTable::parse(&format!("{}symmetries:none\n@COLORS\n3 0 0 0\n", header)).is_err());
+        assert!(RuleTable::parse("@RULE Tree\n@TREE\nnum_states=2\n").is_err());
+
+        let file_path = std::env::temp_dir().join("rule_tables_work.casession");
+        let file_str = file_path.to_str().unwrap();
+        let mut space = Space::new(10, 10);
+        space.automaton = Automaton::Table(rule);
+        session::write_session(file_str, &space, &RenderOptions::default()).unwrap();
+        let content = std::fs::read_to_string(file_str).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        assert_eq!(space.automaton, loaded_space.automaton);
+        // the table of a session comes from its lines
+        let renamed = content.replace("WireWorldTable", "WireWorldSession");
+        std::fs::write(file_str, renamed).unwrap();
+        let (loaded_space, _) = session::read_session(file_str).unwrap();
+        std::fs::remove_file(file_str).unwrap();
+        assert_eq!("table:WireWorldSession", loaded_space.automaton.name());
+        assert_ne!(space.automaton, loaded_space.automaton);
+        std::fs::write(file_str, content.replace("automaton table:WireWorldTable", "automaton table:Other")).unwrap();
+        assert!(session::read_session(file_str).is_err());
+        std::fs::remove_file(file_str).unwrap();
+    }
+
+    #[test]
+    fn update_schemes_work() {
+        let mut soup = Space::new(24, 24);
+        soup.set_seed(3);
+        soup.fill_random(&SoupOptions::default());
+        let get_cells = |space: &Space| space.get_saved_cells().iter().map(|cell| (cell.x, cell.y, cell.get_state())).collect::<Vec<_>>();
+        let compute = |update: UpdateOptions, seed: u64| {
+            let mut space = soup.clone();
+            space.update = update;
+            space.set_seed(seed);
+            for _ in 0..8 {
+                space.compute_next_generation();
+            }
+            get_cells(&space)
+        };
+        let synchronous = compute(UpdateOptions::default(), 1);
+        assert!(!synchronous.is_empty());
+        assert_eq!(synchronous, compute(UpdateOptions { scheme: UpdateScheme::Asynchronous, rate: 1., ..UpdateOptions::default() }, 1));
+        assert_eq!(synchronous, compute(UpdateOptions { scheme: UpdateScheme::BlockSequential, block_size: 24, ..UpdateOptions::default() }, 1));
+        assert_ne!(synchronous, compute(UpdateOptions { scheme: UpdateScheme::BlockSequential, block_size: 5, ..UpdateOptions::default() }, 1));
+        assert_eq!(get_cells(&soup), compute(UpdateOptions { probability: 0., ..UpdateOptions::default() }, 1));
+        assert_eq!(get_cells(&soup), compute(UpdateOptions { scheme: UpdateScheme::Asynchronous, rate: 0., ..UpdateOptions::default() }, 1));
+        // noisy runs are repeated with the same seed
+        for update in [
+            UpdateOptions { scheme: UpdateScheme::RandomSequential, ..UpdateOptions::default() },
+            UpdateOptions { scheme: UpdateScheme::Asynchronous, ..UpdateOptions::default() },
+            UpdateOptions { probability: 0.5, ..UpdateOptions::default() },
+        ] {
+            assert_eq!(compute(update, 7), compute(update, 7));
+            assert_ne!(synchronous, compute(update, 7));
+        }
+
+        // cells updated one after the other see the new states of the cells before them, so the blinker does not oscillate
+        let mut space = Space::build_from_array(&[
+            vec![0, 0, 0, 0, 0],
+            vec![0, 1, 1, 1, 0],
+            vec![0, 0, 0, 0, 0],
+        ]);
+        space.update = UpdateOptions { scheme: UpdateScheme::BlockSequential, block_size: 1, ..UpdateOptions::default() };
+        space.compute_next_generation();
+        assert!(space.check_cell_is_alive(2, 0));
+        assert!(!space.check_cell_is_alive(2, 2));
+        assert_eq!(UpdateScheme::RandomSequential, UpdateScheme::Synchronous.next());
+        assert_eq!(Some(UpdateScheme::BlockSequential), UpdateScheme::from_name("block-sequential"));
+        assert_eq!("asynchronous rate 0.5 p 0.9", UpdateOptions { scheme: UpdateScheme::Asynchronous, probability: 0.9, ..UpdateOptions::default() }.to_string());
+        let options = UpdateOptions { scheme: UpdateScheme::Asynchronous, ..UpdateOptions::default() };
+        assert_eq!(0.25, options.next_parameter().rate);
+        assert_eq!(0.5, (0..RATES.len()).fold(options, |options, _| options.next_parameter()).rate);
+        let options = UpdateOptions { scheme: UpdateScheme::BlockSequential, block_size: 5, ..UpdateOptions::default() };
+        assert_eq!(8, options.next_parameter().block_size);
+        assert_eq!(4, options.next_parameter().next_parameter().block_size);
+        assert_eq!(UpdateOptions::default(), UpdateOptions::default().next_parameter());
+    }
 }